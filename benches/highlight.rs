@@ -0,0 +1,157 @@
+use std::collections::HashSet;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use navcat::domain::filter_config::HighlightConfig;
+use navcat::domain::message_highlighter::{HighlightPriority, MessageHighlighter};
+use ratatui::text::Span;
+
+const BUFFER_LINES: usize = 50_000;
+
+const MESSAGES: &[&str] = &[
+    "Route progress updated: distanceAlongRoute=12873m remaining=4021m",
+    "Replan requested, reason=deviation route_id=3fa2-b1c9 old route removed",
+    "MatchLocation projected onto segment 8812 with confidence 0.93",
+    "Guidance instruction changed: turn left in 300 m, lane guidance available",
+    "Waypoint 2 visited, arrived at destination after 41 min",
+    "Planner started planning route with 3 waypoints",
+    "Traffic jam ahead on current route, delay 6 min",
+    "Socket read timeout while fetching map tiles, retrying in 2 s",
+];
+
+/// The red, green, yellow and custom term sets both highlighters are built from.
+fn term_sets() -> [HashSet<String>; 4] {
+    let config = HighlightConfig::default();
+    let to_set =
+        |words: &[String]| -> HashSet<String> { words.iter().map(|w| w.to_lowercase()).collect() };
+    [
+        to_set(&config.red),
+        to_set(&config.green),
+        to_set(&config.yellow),
+        to_set(&["timeout".to_owned(), "tiles".to_owned()]),
+    ]
+}
+
+fn highlighter() -> MessageHighlighter {
+    let [red, green, yellow, custom] = term_sets();
+    MessageHighlighter::new(red, green, yellow, custom)
+}
+
+/// The highlighter `MessageHighlighter` replaced, kept as the baseline its
+/// automaton is measured against: every term is searched for separately with
+/// `match_indices`, and overlapping matches are resolved by priority afterwards.
+struct PerTermHighlighter {
+    buckets: Vec<(HashSet<String>, HighlightPriority)>,
+}
+
+impl PerTermHighlighter {
+    fn new() -> Self {
+        let [red, green, yellow, custom] = term_sets();
+        Self {
+            buckets: vec![
+                (red, HighlightPriority::Red),
+                (yellow, HighlightPriority::Yellow),
+                (green, HighlightPriority::Green),
+                (custom, HighlightPriority::Custom),
+            ],
+        }
+    }
+
+    fn highlight_message(&self, message: &str) -> Vec<Span<'static>> {
+        let lower = message.to_lowercase();
+        let mut matches: Vec<(usize, usize, HighlightPriority)> = Vec::new();
+        for (terms, priority) in &self.buckets {
+            for term in terms {
+                for (pos, _) in lower.match_indices(term.as_str()) {
+                    if is_complete_match(&lower, pos, term.len()) {
+                        matches.push((pos, pos + term.len(), *priority));
+                    }
+                }
+            }
+        }
+        matches.sort_by_key(|m| m.0);
+
+        let mut resolved: Vec<(usize, usize, HighlightPriority)> = Vec::new();
+        let mut overlapping: Vec<(usize, usize, HighlightPriority)> = Vec::new();
+        for m in matches {
+            if overlapping.first().is_some_and(|first| m.0 > first.1) {
+                resolved.extend(overlapping.drain(..).max_by_key(|o| o.2));
+            }
+            overlapping.push(m);
+        }
+        resolved.extend(overlapping.into_iter().max_by_key(|o| o.2));
+
+        if resolved.is_empty() {
+            return vec![Span::raw(message.to_owned())];
+        }
+        let mut spans = Vec::new();
+        let mut last_end = 0;
+        for (start, end, priority) in resolved {
+            if start > last_end {
+                spans.push(Span::raw(message[last_end..start].to_owned()));
+            }
+            spans.push(Span::styled(
+                message[start..end].to_owned(),
+                priority.style(),
+            ));
+            last_end = end;
+        }
+        if last_end < message.len() {
+            spans.push(Span::raw(message[last_end..].to_owned()));
+        }
+        spans
+    }
+}
+
+fn is_complete_match(lower: &str, pos: usize, len: usize) -> bool {
+    let is_word_boundary =
+        |c: char| c.is_whitespace() || c.is_ascii_punctuation() || c == '(' || c == ')' || c == '=';
+    let starts_at_boundary = lower[..pos]
+        .chars()
+        .next_back()
+        .is_none_or(is_word_boundary);
+    let ends_at_boundary = lower[pos + len..]
+        .chars()
+        .next()
+        .is_none_or(is_word_boundary);
+    starts_at_boundary && ends_at_boundary
+}
+
+fn highlight_buffer(c: &mut Criterion) {
+    let highlighter = highlighter();
+    let baseline = PerTermHighlighter::new();
+    let buffer: Vec<String> = (0..BUFFER_LINES)
+        .map(|i| format!("{} #{}", MESSAGES[i % MESSAGES.len()], i))
+        .collect();
+    for message in MESSAGES {
+        assert_eq!(
+            baseline.highlight_message(message),
+            highlighter.highlight_message(message),
+            "baseline disagrees on {message:?}"
+        );
+    }
+
+    c.bench_function("highlight 50k lines (per-term baseline)", |b| {
+        b.iter(|| {
+            buffer
+                .iter()
+                .map(|line| baseline.highlight_message(line).len())
+                .sum::<usize>()
+        })
+    });
+
+    c.bench_function("highlight 50k lines", |b| {
+        b.iter(|| {
+            buffer
+                .iter()
+                .map(|line| highlighter.highlight_message(line).len())
+                .sum::<usize>()
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = highlight_buffer
+}
+criterion_main!(benches);
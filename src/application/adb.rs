@@ -1,46 +1,325 @@
+use std::collections::VecDeque;
 use std::error::Error;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
+use std::net::{Shutdown, TcpStream};
 use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
 use std::sync::mpsc::{self, Receiver};
 use std::sync::{
     Arc, Mutex,
     atomic::{AtomicBool, Ordering},
 };
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use crate::application::adb_client::{AdbClient, read_message, shell_command};
+use crate::application::ingest::{
+    INGEST_CAPACITY, IngestReceiver, IngestSender, Overflow, bounded,
+};
+use crate::application::source::{DeviceAction, DeviceEvent, DeviceState, LogcatEvent};
+use crate::domain::filter::LogFilter;
 use crate::shared::logger::Logger;
 
-pub enum LogcatEvent {
-    Line(String),
-    Connected,
-    Disconnected,
+impl DeviceState {
+    /// The state of `serial` in a device list; without a serial, of the device
+    /// logcat would pick.
+    pub fn of(devices: &[DeviceEntry], serial: Option<&str>) -> Self {
+        let device = match serial {
+            Some(serial) => devices.iter().find(|d| d.serial == serial),
+            None => devices
+                .iter()
+                .find(|d| d.is_ready())
+                .or_else(|| devices.first()),
+        };
+        match device.map(|d| d.state.as_str()) {
+            None => Self::Unplugged,
+            Some("device") => Self::Online,
+            Some("unauthorized" | "no permissions") => Self::Unauthorized,
+            Some(_) => Self::Rebooting,
+        }
+    }
 }
 
-pub struct LogcatHandle {
-    receiver: Receiver<LogcatEvent>,
-    stop: Arc<AtomicBool>,
-    current_child: Arc<Mutex<Option<Child>>>,
+/// Ring buffers `--buffers` accepts, as named by `logcat -b`.
+pub const LOG_BUFFERS: &[&str] = &[
+    "main", "system", "crash", "events", "radio", "kernel", "default", "all",
+];
+
+/// How much of the device's ring buffer logcat prints before streaming new lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum History {
+    /// The last N lines; 0 streams only lines logged after navcat started.
+    Lines(usize),
+    /// Everything still in the ring buffer.
+    All,
+    /// Lines logged at or after a logcat time (`MM-DD hh:mm:ss.mmm`).
+    Since(String),
+}
+
+impl FromStr for History {
+    type Err = String;
+
+    /// `500`, `all` or `since=01-15 10:30:00.000`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "all" {
+            return Ok(Self::All);
+        }
+        if let Some(time) = s.strip_prefix("since=") {
+            let time = time.trim();
+            if time.is_empty() || !time.starts_with(|c: char| c.is_ascii_digit()) {
+                return Err(format!(
+                    "invalid history start '{}': expected a logcat time such as '01-15 10:30:00.000'",
+                    time
+                ));
+            }
+            return Ok(Self::Since(time.to_owned()));
+        }
+        s.parse().map(Self::Lines).map_err(|_| {
+            format!(
+                "invalid history '{}': expected a line count, 'all' or 'since=<time>'",
+                s
+            )
+        })
+    }
+}
+
+/// Which buffers logcat reads and how far back it starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogcatOptions {
+    /// `logcat -b` buffer names; empty leaves logcat's default set.
+    pub buffers: Vec<String>,
+    pub history: History,
+    /// `Tag:Level` filterspecs applied on the device; empty passes every line.
+    pub filterspecs: Vec<String>,
+    /// Also stream the kernel buffer, merged in by time with its lines at level `K`.
+    pub kernel: bool,
+    /// Stream through the adb server's host protocol instead of the `adb` binary.
+    pub server: Option<AdbClient>,
+    /// What happens to new lines while the TUI is `INGEST_CAPACITY` lines behind.
+    pub overflow: Overflow,
 }
 
-impl LogcatHandle {
-    pub fn receiver(&self) -> &Receiver<LogcatEvent> {
+impl Default for LogcatOptions {
+    fn default() -> Self {
+        Self {
+            buffers: vec![],
+            history: History::Lines(0),
+            filterspecs: vec![],
+            kernel: false,
+            server: None,
+            overflow: Overflow::Block,
+        }
+    }
+}
+
+/// One logcat process per device, all sending into a single channel.
+pub struct MultiLogcat {
+    receiver: IngestReceiver<DeviceEvent>,
+    processes: Vec<LogcatProcess>,
+    /// The kernel log readers, which keep their own (empty) filterspecs.
+    kernel: Vec<LogcatProcess>,
+    tracking: Option<DeviceTracking>,
+}
+
+impl MultiLogcat {
+    pub fn receiver(&self) -> &IngestReceiver<DeviceEvent> {
         &self.receiver
     }
 
     pub fn shutdown(&mut self) {
+        if let Some(tracking) = &mut self.tracking {
+            tracking.shutdown();
+        }
+        for process in self.processes.iter_mut().chain(&mut self.kernel) {
+            process.shutdown();
+        }
+    }
+
+    /// Restarts every device's logcat with new filterspecs.
+    pub fn set_filterspecs(&self, filterspecs: Vec<String>) {
+        for process in &self.processes {
+            process.set_filterspecs(filterspecs.clone());
+        }
+    }
+}
+
+/// The restarting reader thread of one device and the adb child it currently reads.
+struct LogcatProcess {
+    stop: Arc<AtomicBool>,
+    /// Set when the child is killed only to restart it with new filterspecs.
+    refilter: Arc<AtomicBool>,
+    filterspecs: Arc<Mutex<Vec<String>>>,
+    current_child: Arc<Mutex<Option<AdbConnection>>>,
+}
+
+/// What a reader thread streams logcat from: an `adb` child, an adb server socket
+/// or a `--cmd` shell leading its own process group.
+enum AdbConnection {
+    Child(Child),
+    Socket(TcpStream),
+    Group(Child),
+}
+
+impl AdbConnection {
+    /// Ends the stream, so the reader sees end of file.
+    fn kill(&mut self) {
+        match self {
+            Self::Child(child) => {
+                let _ = child.kill();
+            }
+            Self::Socket(socket) => {
+                let _ = socket.shutdown(Shutdown::Both);
+            }
+            Self::Group(shell) => {
+                // Whatever the shell started (ssh, a vendor CLI) holds its stdout
+                // open too, so the whole group has to go. Only unix starts the
+                // command in a group of its own; elsewhere just the shell is killed.
+                #[cfg(unix)]
+                kill_process_group(shell.id());
+                let _ = shell.kill();
+            }
+        }
+    }
+
+    fn wait(self) {
+        match self {
+            Self::Child(mut child) | Self::Group(mut child) => {
+                let _ = child.wait();
+            }
+            Self::Socket(_) => {}
+        }
+    }
+}
+
+impl LogcatProcess {
+    fn shutdown(&mut self) {
         self.stop.store(true, Ordering::Relaxed);
         kill_current_child(&self.current_child);
         wait_current_child(&self.current_child);
     }
+
+    fn set_filterspecs(&self, filterspecs: Vec<String>) {
+        if let Ok(mut guard) = self.filterspecs.lock() {
+            *guard = filterspecs;
+        }
+        self.refilter.store(true, Ordering::Relaxed);
+        kill_current_child(&self.current_child);
+    }
+}
+
+impl Drop for LogcatProcess {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// What the device tracker tells one logcat reader about its device.
+struct DeviceWatch {
+    serial: Option<String>,
+    /// Sends a `LogcatEvent::State`; false once the receiver is gone.
+    notify: Box<dyn Fn(DeviceState) -> bool + Send>,
+    /// Cuts the reader's reconnect wait short.
+    wake: mpsc::Sender<()>,
+}
+
+/// The thread following `adb track-devices` and the connection it reads.
+struct DeviceTracking {
+    stop: Arc<AtomicBool>,
+    connection: Arc<Mutex<Option<AdbConnection>>>,
+}
+
+impl DeviceTracking {
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        kill_current_child(&self.connection);
+        wait_current_child(&self.connection);
+    }
 }
 
-impl Drop for LogcatHandle {
+impl Drop for DeviceTracking {
     fn drop(&mut self) {
         self.shutdown();
     }
 }
 
+/// One line of `adb devices -l`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceEntry {
+    pub serial: String,
+    /// `device` when ready; otherwise `unauthorized`, `offline`, `recovery`, …
+    pub state: String,
+    pub model: Option<String>,
+    pub product: Option<String>,
+}
+
+impl DeviceEntry {
+    pub fn is_ready(&self) -> bool {
+        self.state == "device"
+    }
+
+    /// What to do about a device that is attached but cannot stream logcat.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self.state.as_str() {
+            "device" => None,
+            "unauthorized" => Some("accept the USB debugging prompt on the device"),
+            "offline" => Some("reconnect the cable or run `adb reconnect offline`"),
+            "no permissions" => Some("fix udev rules for this USB device"),
+            _ => Some("wait for the device to finish booting"),
+        }
+    }
+}
+
+/// Lists attached devices with their state, model and product via `adb devices -l`.
+pub fn list_devices() -> Result<Vec<DeviceEntry>, Box<dyn Error>> {
+    let output = Command::new(adb_program())
+        .args(["devices", "-l"])
+        .output()?;
+    Ok(parse_adb_devices_long_output(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+fn parse_adb_devices_long_output(output: &str) -> Vec<DeviceEntry> {
+    parse_device_lines(output.split_once('\n').map_or("", |(_, devices)| devices))
+}
+
+/// Parses `serial state [key:value ...]` lines, as listed by `adb devices -l` after
+/// its header and by the server's `host:devices-l` and `host:track-devices`.
+pub(crate) fn parse_device_lines(output: &str) -> Vec<DeviceEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let serial = words.next()?.to_owned();
+            let mut state = words.next()?.to_owned();
+            let mut model = None;
+            let mut product = None;
+            for word in words {
+                match word.split_once(':') {
+                    Some(("model", value)) => model = Some(value.replace('_', " ")),
+                    Some(("product", value)) => product = Some(value.to_owned()),
+                    Some(_) => {}
+                    // `no permissions (...)` spans several words.
+                    None if model.is_none() && product.is_none() => {
+                        state = format!("{} {}", state, word)
+                    }
+                    None => {}
+                }
+            }
+            if state.starts_with("no permissions") {
+                state = "no permissions".to_owned();
+            }
+            Some(DeviceEntry {
+                serial,
+                state,
+                model,
+                product,
+            })
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum DeviceCheck {
     Ready,
@@ -66,7 +345,26 @@ pub fn check_adb_available() -> Result<(), Box<dyn Error>> {
 
 pub fn check_device_connected(serial: Option<&str>) -> Result<(), Box<dyn Error>> {
     let output = Command::new(adb_program()).arg("devices").output()?;
-    match parse_adb_devices_output(&String::from_utf8_lossy(&output.stdout), serial) {
+    device_check_result(parse_adb_devices_output(
+        &String::from_utf8_lossy(&output.stdout),
+        serial,
+    ))
+}
+
+/// Like `check_device_connected`, for a device list read from the adb server.
+pub fn check_listed_device(
+    devices: &[DeviceEntry],
+    serial: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let devices: Vec<(&str, &str)> = devices
+        .iter()
+        .map(|d| (d.serial.as_str(), d.state.as_str()))
+        .collect();
+    device_check_result(device_check(&devices, serial))
+}
+
+fn device_check_result(check: DeviceCheck) -> Result<(), Box<dyn Error>> {
+    match check {
         DeviceCheck::Ready => Ok(()),
         DeviceCheck::Unready => Err(
             "Android device detected, but it is not ready. Check adb authorization / device state."
@@ -90,28 +388,28 @@ pub fn check_device_connected(serial: Option<&str>) -> Result<(), Box<dyn Error>
 }
 
 fn parse_adb_devices_output(output: &str, serial: Option<&str>) -> DeviceCheck {
-    let mut saw_any_device = false;
-    let mut ready_devices = Vec::new();
-    let mut requested_serial_seen = false;
+    let devices: Vec<(&str, &str)> = output
+        .lines()
+        .skip(1)
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| line.split_once('\t').unwrap_or((line, "")))
+        .collect();
+    device_check(&devices, serial)
+}
 
-    for line in output.lines().skip(1) {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        saw_any_device = true;
-        if let Some((device_serial, state)) = trimmed.split_once('\t') {
-            if serial == Some(device_serial) {
-                requested_serial_seen = true;
-            }
-            if state == "device" {
-                ready_devices.push(device_serial);
-            }
-        }
-    }
+/// Judges `(serial, state)` pairs against the requested serial, if any.
+fn device_check(devices: &[(&str, &str)], serial: Option<&str>) -> DeviceCheck {
+    let saw_any_device = !devices.is_empty();
+    let requested_serial_seen = devices.iter().any(|(s, _)| Some(*s) == serial);
+    let ready_devices: Vec<&str> = devices
+        .iter()
+        .filter(|(_, state)| *state == "device")
+        .map(|(s, _)| *s)
+        .collect();
 
     if let Some(serial) = serial {
-        if ready_devices.iter().any(|device| *device == serial) {
+        if ready_devices.contains(&serial) {
             return DeviceCheck::Ready;
         }
         return if requested_serial_seen {
@@ -137,95 +435,917 @@ fn parse_adb_devices_output(output: &str, serial: Option<&str>) -> DeviceCheck {
     }
 }
 
-fn logcat_args(serial: Option<&str>) -> Vec<String> {
+/// System properties recorded alongside captured crashes.
+const DEVICE_PROPERTIES: [&str; 5] = [
+    "ro.product.manufacturer",
+    "ro.product.model",
+    "ro.build.version.release",
+    "ro.build.version.sdk",
+    "ro.build.fingerprint",
+];
+
+/// Reads the device's model and build properties via `adb shell getprop`.
+/// With `server`, the properties are read through the adb server instead.
+pub fn device_properties(
+    serial: Option<&str>,
+    server: Option<&AdbClient>,
+) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let output = String::from_utf8_lossy(&device_output(
+        serial,
+        server,
+        DeviceService::Shell,
+        &["getprop"],
+    )?)
+    .into_owned();
+    let mut properties = vec![];
+    if let Some(serial) = serial {
+        properties.push(("serial".to_owned(), serial.to_owned()));
+    }
+    properties.extend(parse_getprop_output(&output));
+    Ok(properties)
+}
+
+/// The installed version of `package` from `adb shell dumpsys package`, as
+/// `versionName (versionCode)`; `None` when the package is not installed.
+pub fn app_version(
+    serial: Option<&str>,
+    server: Option<&AdbClient>,
+    package: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let output = device_output(
+        serial,
+        server,
+        DeviceService::Shell,
+        &["dumpsys", "package", package],
+    )?;
+    Ok(parse_package_version(&String::from_utf8_lossy(&output)))
+}
+
+/// The device's model and build properties, and the installed version of `package`.
+/// What cannot be read is logged and left out.
+pub fn device_info(
+    serial: Option<&str>,
+    server: Option<&AdbClient>,
+    package: Option<&str>,
+) -> Vec<(String, String)> {
+    let mut info = device_properties(serial, server).unwrap_or_else(|e| {
+        Logger::info_fmt("could not read device properties:", &[&e.to_string()]);
+        vec![]
+    });
+    if let Some(package) = package {
+        match app_version(serial, server, package) {
+            Ok(version) => info.push((
+                format!("{} version", package),
+                version.unwrap_or_else(|| "not installed".to_owned()),
+            )),
+            Err(e) => Logger::info_fmt("could not read the app version:", &[&e.to_string()]),
+        }
+    }
+    info
+}
+
+/// How `device_output` runs a command on the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeviceService {
+    /// Through the device shell, with its output as text.
+    Shell,
+    /// Without a shell or terminal, so binary output arrives unchanged.
+    Exec,
+}
+
+impl DeviceService {
+    fn subcommand(self) -> &'static str {
+        match self {
+            Self::Shell => "shell",
+            Self::Exec => "exec-out",
+        }
+    }
+}
+
+/// Runs `args` on the device with `adb shell` or `adb exec-out`, or the matching
+/// server service, and returns what it printed. Both ways get one command line
+/// quoted by `shell_command`, so typed tags and package names stay single words.
+fn device_output(
+    serial: Option<&str>,
+    server: Option<&AdbClient>,
+    service: DeviceService,
+    args: &[&str],
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let line = shell_command(&args);
+    if let Some(server) = server {
+        return Ok(match service {
+            DeviceService::Exec => server.exec_output(serial, &line)?,
+            DeviceService::Shell => server.shell_output(serial, &line)?.into_bytes(),
+        });
+    }
+    let mut command = Command::new(adb_program());
+    if let Some(serial) = serial {
+        command.args(["-s", serial]);
+    }
+    let subcommand = service.subcommand();
+    let output = command
+        .arg(subcommand)
+        .arg(&line)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("adb {} failed: {}", subcommand, stderr.trim()).into());
+    }
+    Ok(output.stdout)
+}
+
+/// Lists every thread on the device with `ps -A -T`, asking for the full process
+/// name (`NAME`) next to the thread name (`CMD`).
+pub fn process_table(
+    serial: Option<&str>,
+    server: Option<&AdbClient>,
+) -> Result<String, Box<dyn Error>> {
+    let output = device_output(
+        serial,
+        server,
+        DeviceService::Shell,
+        &["ps", "-A", "-T", "-o", "PID,TID,NAME,CMD"],
+    )?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Runs `action` on the device and describes the outcome for the status bar.
+pub fn run_device_action(
+    serial: Option<&str>,
+    server: Option<&AdbClient>,
+    package: Option<&str>,
+    action: &DeviceAction,
+) -> Result<String, Box<dyn Error>> {
+    let package = || package.ok_or("no app package; start navcat with --package");
+    match action {
+        DeviceAction::ClearLog => {
+            device_output(serial, server, DeviceService::Shell, &["logcat", "-c"])?;
+            Ok("device log cleared".to_owned())
+        }
+        DeviceAction::Screenshot => {
+            let png = device_output(serial, server, DeviceService::Exec, &["screencap", "-p"])?;
+            if !png.starts_with(b"\x89PNG") {
+                return Err(
+                    format!("screencap failed: {}", String::from_utf8_lossy(&png).trim()).into(),
+                );
+            }
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            let filename = format!("navcat_{}.png", now.as_secs());
+            std::fs::write(&filename, png)?;
+            Ok(format!("screenshot saved to {}", filename))
+        }
+        DeviceAction::SetTagLevel { tag, level } => {
+            let property = format!("log.tag.{}", tag);
+            let output = device_output(
+                serial,
+                server,
+                DeviceService::Shell,
+                &["setprop", &property, level],
+            )?;
+            let output = String::from_utf8_lossy(&output);
+            if !output.trim().is_empty() {
+                return Err(output.trim().to_owned().into());
+            }
+            Ok(format!("{} set to {}", property, level))
+        }
+        DeviceAction::ForceStop => {
+            let package = package()?;
+            device_output(
+                serial,
+                server,
+                DeviceService::Shell,
+                &["am", "force-stop", package],
+            )?;
+            Ok(format!("{} force-stopped", package))
+        }
+        DeviceAction::StartApp => {
+            let package = package()?;
+            let output = device_output(
+                serial,
+                server,
+                DeviceService::Shell,
+                &[
+                    "monkey",
+                    "-p",
+                    package,
+                    "-c",
+                    "android.intent.category.LAUNCHER",
+                    "1",
+                ],
+            )?;
+            let output = String::from_utf8_lossy(&output);
+            if !output.contains("Events injected") {
+                let reason = output.lines().rfind(|line| !line.trim().is_empty());
+                return Err(reason.unwrap_or("monkey did not start it").trim().into());
+            }
+            Ok(format!("{} started", package))
+        }
+    }
+}
+
+/// Picks `DEVICE_PROPERTIES` out of `[key]: [value]` lines, in that order.
+fn parse_getprop_output(output: &str) -> Vec<(String, String)> {
+    let all: Vec<(&str, &str)> = output
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().split_once("]: [")?;
+            Some((key.strip_prefix('[')?, value.strip_suffix(']')?))
+        })
+        .collect();
+    DEVICE_PROPERTIES
+        .iter()
+        .filter_map(|wanted| {
+            all.iter()
+                .find(|(key, _)| key == wanted)
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Reads `versionName=` and `versionCode=` from `dumpsys package` output; the first
+/// of each belongs to the installed package, later ones to hidden system copies.
+fn parse_package_version(output: &str) -> Option<String> {
+    let field = |name: &str| {
+        output.split_whitespace().find_map(|word| {
+            word.strip_prefix(name)
+                .filter(|value| !value.is_empty())
+                .map(str::to_owned)
+        })
+    };
+    match (field("versionName="), field("versionCode=")) {
+        (Some(name), Some(code)) => Some(format!("{} ({})", name, code)),
+        (Some(version), None) | (None, Some(version)) => Some(version),
+        (None, None) => None,
+    }
+}
+
+fn logcat_args(
+    serial: Option<&str>,
+    buffers: &[String],
+    history: &History,
+    filterspecs: &[String],
+) -> Vec<String> {
     let mut args = Vec::new();
     if let Some(serial) = serial {
         args.push("-s".to_string());
         args.push(serial.to_string());
     }
-    args.extend(
-        ["logcat", "-v", "threadtime", "-T", "0"]
-            .into_iter()
-            .map(str::to_string),
-    );
+    args.extend(logcat_command(buffers, history, filterspecs));
     args
 }
 
+/// The `logcat ...` command line run on the device.
+fn logcat_command(buffers: &[String], history: &History, filterspecs: &[String]) -> Vec<String> {
+    let mut args: Vec<String> = ["logcat", "-v", "threadtime"]
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    for buffer in buffers {
+        args.push("-b".to_string());
+        args.push(buffer.clone());
+    }
+    match history {
+        History::Lines(n) => args.extend(["-T".to_string(), n.to_string()]),
+        History::All => {}
+        History::Since(time) => args.extend(["-T".to_string(), time.clone()]),
+    }
+    args.extend(filterspecs.iter().cloned());
+    args
+}
+
+/// Where a restarted logcat picks up: the newest timestamp streamed so far and the
+/// lines already sent with it, which `-T <timestamp>` prints again.
+#[derive(Debug, Default)]
+struct ResumePoint {
+    timestamp: Option<String>,
+    sent: Vec<String>,
+    /// Lines of a restart still expected to repeat ones sent before it.
+    replay: Vec<String>,
+}
+
+impl ResumePoint {
+    /// The history for a restart: from the newest timestamp, or `initial` if no
+    /// line has been streamed yet.
+    fn restart(&mut self, initial: &History) -> History {
+        match &self.timestamp {
+            Some(timestamp) => {
+                self.replay = self.sent.clone();
+                History::Since(timestamp.clone())
+            }
+            None => initial.clone(),
+        }
+    }
+
+    /// Records `line`; false if it repeats a line sent before the restart.
+    fn admit(&mut self, line: &str) -> bool {
+        let timestamp = LogFilter::parse_header(line).map(|h| format!("{} {}", h.date, h.time));
+        if !self.replay.is_empty() {
+            if line.starts_with("--------- beginning of") {
+                return false;
+            }
+            if let Some(i) = self.replay.iter().position(|sent| sent == line) {
+                self.replay.swap_remove(i);
+                return false;
+            }
+            if timestamp.is_some() {
+                self.replay.clear();
+            }
+        }
+        match timestamp {
+            Some(timestamp) if self.timestamp.as_ref() == Some(&timestamp) => {
+                self.sent.push(line.to_owned());
+            }
+            Some(timestamp) => {
+                self.timestamp = Some(timestamp);
+                self.sent = vec![line.to_owned()];
+            }
+            None => {}
+        }
+        true
+    }
+}
+
 fn adb_program() -> String {
     std::env::var("NAVCAT_ADB").unwrap_or_else(|_| "adb".to_string())
 }
 
-fn spawn_adb_logcat(serial: Option<&str>, stderr: Stdio) -> Result<Child, Box<dyn Error>> {
-    Ok(Command::new(adb_program())
-        .args(logcat_args(serial))
+/// Starts logcat through the adb server or an `adb` child, returning its output.
+fn open_logcat(
+    serial: Option<&str>,
+    options: &LogcatOptions,
+    history: &History,
+    filterspecs: &[String],
+) -> Result<(Box<dyn Read + Send>, AdbConnection), Box<dyn Error>> {
+    if let Some(server) = &options.server {
+        let command = logcat_command(&options.buffers, history, filterspecs);
+        let socket = server.shell(serial, &shell_command(&command))?;
+        let stdout = socket.try_clone()?;
+        return Ok((Box::new(stdout), AdbConnection::Socket(socket)));
+    }
+    let mut child = Command::new(adb_program())
+        .args(logcat_args(serial, &options.buffers, history, filterspecs))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().ok_or("Failed to capture adb stdout")?;
+    let stderr = child.stderr.take().ok_or("Failed to capture adb stderr")?;
+    spawn_stderr_logger("adb stderr:", stderr);
+    Ok((Box::new(stdout), AdbConnection::Child(child)))
+}
+
+/// Starts `command` with `sh -c` in a process group of its own, so shutdown
+/// reaches everything it spawns.
+fn open_command(command: &str) -> Result<(Box<dyn Read + Send>, AdbConnection), Box<dyn Error>> {
+    let mut shell = Command::new("sh");
+    shell
+        .args(["-c", command])
         .stdin(Stdio::null())
         .stdout(Stdio::piped())
-        .stderr(stderr)
-        .spawn()?)
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut shell, 0);
+    let mut child = shell.spawn()?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or("Failed to capture command stdout")?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or("Failed to capture command stderr")?;
+    spawn_stderr_logger("command stderr:", stderr);
+    Ok((Box::new(stdout), AdbConnection::Group(child)))
 }
 
-fn spawn_stderr_logger(stderr: impl std::io::Read + Send + 'static) {
+/// Sends SIGKILL to every process in the group `pgid`. A group that is already
+/// gone is not an error.
+#[cfg(unix)]
+fn kill_process_group(pgid: u32) {
+    // SAFETY: killpg only sends a signal and takes no pointers.
+    if unsafe { libc::killpg(pgid as libc::pid_t, libc::SIGKILL) } == 0 {
+        return;
+    }
+    let error = std::io::Error::last_os_error();
+    if error.raw_os_error() != Some(libc::ESRCH) {
+        Logger::info_fmt(
+            "could not kill the command's process group:",
+            &[&error.to_string()],
+        );
+    }
+}
+
+fn spawn_stderr_logger(label: &'static str, stderr: impl std::io::Read + Send + 'static) {
     thread::spawn(move || {
         let reader = BufReader::new(stderr);
         for line in reader.lines().map_while(Result::ok) {
-            Logger::info_fmt("adb stderr:", &[&line]);
+            Logger::info_fmt(label, &[&line]);
         }
     });
 }
 
-fn kill_current_child(current_child: &Arc<Mutex<Option<Child>>>) {
-    if let Ok(mut guard) = current_child.lock() {
-        if let Some(child) = guard.as_mut() {
-            let _ = child.kill();
-        }
+fn kill_current_child(current_child: &Arc<Mutex<Option<AdbConnection>>>) {
+    if let Ok(mut guard) = current_child.lock()
+        && let Some(child) = guard.as_mut()
+    {
+        child.kill();
     }
 }
 
-fn wait_current_child(current_child: &Arc<Mutex<Option<Child>>>) {
-    if let Ok(mut guard) = current_child.lock() {
-        if let Some(mut child) = guard.take() {
-            let _ = child.wait();
-        }
+fn wait_current_child(current_child: &Arc<Mutex<Option<AdbConnection>>>) {
+    if let Ok(mut guard) = current_child.lock()
+        && let Some(child) = guard.take()
+    {
+        child.wait();
     }
 }
 
-fn stream_stdout(
+/// Sends `event` on: lines under the channel's overflow policy, connection and
+/// state events always. False once the receiver is gone.
+fn forward<E>(
+    sender: &IngestSender<E>,
+    wrap: &impl Fn(LogcatEvent) -> E,
+    event: LogcatEvent,
+) -> bool {
+    let sent = match event {
+        LogcatEvent::Line(_) => sender.send(wrap(event)),
+        _ => sender.send_control(wrap(event)),
+    };
+    sent.is_ok()
+}
+
+fn stream_stdout<E>(
     stdout: impl std::io::Read,
-    sender: &mpsc::Sender<LogcatEvent>,
+    sender: &IngestSender<E>,
+    wrap: &impl Fn(LogcatEvent) -> E,
     stop: &AtomicBool,
+    resume: &mut ResumePoint,
 ) -> bool {
     let reader = BufReader::new(stdout);
-    for line in reader.lines().map_while(Result::ok) {
+    for mut line in reader.lines().map_while(Result::ok) {
         if stop.load(Ordering::Relaxed) {
             return false;
         }
-        if sender.send(LogcatEvent::Line(line)).is_err() {
+        // Older devices' shell service ends lines with CR LF.
+        if line.ends_with('\r') {
+            line.pop();
+        }
+        if !resume.admit(&line) {
+            continue;
+        }
+        if !forward(sender, wrap, LogcatEvent::Line(line)) {
             return false;
         }
     }
     true
 }
 
-/// Spawns `adb logcat -T 0` and returns a channel receiver that emits raw log lines.
-/// `-T 0` skips the historical ring-buffer and streams only live events.
-/// The reading thread automatically restarts on exit so live logs keep flowing.
-pub fn spawn_logcat(serial: Option<&str>) -> Result<LogcatHandle, Box<dyn Error>> {
-    let mut child = spawn_adb_logcat(serial, Stdio::piped())?;
+/// Spawns `adb logcat` on the `options` buffers and history for every serial,
+/// merging their events into one channel tagged with the serial's index.
+/// Each reading thread automatically restarts on exit so live logs keep flowing,
+/// resuming with `-T <last timestamp>` so no line is lost or sent twice.
+pub fn spawn_multi_logcat(
+    serials: &[Option<String>],
+    options: &LogcatOptions,
+) -> Result<MultiLogcat, Box<dyn Error>> {
+    let (sender, receiver) = bounded(INGEST_CAPACITY, options.overflow);
+    let mut processes = Vec::new();
+    let mut kernel = Vec::new();
+    let mut watches = Vec::new();
+    for (device, serial) in serials.iter().enumerate() {
+        let streams = start_device(serial.as_deref(), options, sender.clone(), move |event| {
+            DeviceEvent { device, event }
+        })?;
+        processes.push(streams.logcat);
+        kernel.extend(streams.kernel);
+        watches.extend(streams.watches);
+    }
+    let tracking = start_device_tracking(options.server.as_ref(), watches);
+    Ok(MultiLogcat {
+        receiver,
+        processes,
+        kernel,
+        tracking,
+    })
+}
+
+/// Runs every command in `sh -c` and streams its stdout as the log, restarting it
+/// whenever it exits, with the same events as `spawn_multi_logcat`, merging them
+/// into one channel tagged with the command's index.
+pub fn spawn_multi_command(
+    commands: &[String],
+    overflow: Overflow,
+) -> Result<MultiLogcat, Box<dyn Error>> {
+    let (sender, receiver) = bounded(INGEST_CAPACITY, overflow);
+    let processes = commands
+        .iter()
+        .enumerate()
+        .map(|(device, command)| {
+            start_command(command, sender.clone(), move |event| DeviceEvent {
+                device,
+                event,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(MultiLogcat {
+        receiver,
+        processes,
+        kernel: vec![],
+        tracking: None,
+    })
+}
+
+/// Longest wait between attempts to reopen a lost `track-devices` stream.
+const MAX_TRACKING_RETRY: Duration = Duration::from_secs(10);
+
+/// Follows device changes through `adb track-devices` (or the server's
+/// `host:track-devices`) and reports each watched device's state. When the stream
+/// ends, e.g. because the adb server restarted, every device is reported as
+/// `Unknown` and the tracker reconnects with a growing delay. Returns `None` if
+/// tracking cannot start; readers then just retry on their timer.
+fn start_device_tracking(
+    server: Option<&AdbClient>,
+    watches: Vec<DeviceWatch>,
+) -> Option<DeviceTracking> {
+    let (mut reader, connection) = match open_device_tracker(server) {
+        Ok(opened) => opened,
+        Err(e) => {
+            Logger::info_fmt("adb track-devices unavailable:", &[&e.to_string()]);
+            return None;
+        }
+    };
 
-    let stdout = child.stdout.take().ok_or("Failed to capture adb stdout")?;
-    let stderr = child.stderr.take().ok_or("Failed to capture adb stderr")?;
-    let (sender, receiver) = mpsc::channel();
     let stop = Arc::new(AtomicBool::new(false));
-    let current_child = Arc::new(Mutex::new(Some(child)));
+    let connection = Arc::new(Mutex::new(Some(connection)));
     let reader_stop = Arc::clone(&stop);
-    let reader_child = Arc::clone(&current_child);
+    let reader_connection = Arc::clone(&connection);
+    let server = server.cloned();
+    // The server answers at once, so it is asked again sooner than the adb binary.
+    let first_retry = match server {
+        Some(_) => Duration::from_millis(250),
+        None => Duration::from_secs(1),
+    };
+    thread::spawn(move || {
+        let mut states: Vec<Option<DeviceState>> = vec![None; watches.len()];
+        let report = |states: &mut Vec<Option<DeviceState>>, devices: Option<&[DeviceEntry]>| {
+            for (watch, last) in watches.iter().zip(states.iter_mut()) {
+                let state = match devices {
+                    Some(devices) => DeviceState::of(devices, watch.serial.as_deref()),
+                    // A tracker that never listed the device has nothing to take back.
+                    None if last.is_none() => continue,
+                    None => DeviceState::Unknown,
+                };
+                if *last == Some(state) {
+                    continue;
+                }
+                *last = Some(state);
+                if !(watch.notify)(state) {
+                    return false;
+                }
+                if state == DeviceState::Online {
+                    let _ = watch.wake.send(());
+                }
+            }
+            true
+        };
+        loop {
+            while !reader_stop.load(Ordering::Relaxed) {
+                let devices = match read_message(&mut reader) {
+                    Ok(list) => parse_device_lines(&list),
+                    Err(e) => {
+                        Logger::info_fmt("adb track-devices ended:", &[&e.to_string()]);
+                        break;
+                    }
+                };
+                if !report(&mut states, Some(&devices)) {
+                    return;
+                }
+            }
+            wait_current_child(&reader_connection);
+            if reader_stop.load(Ordering::Relaxed) || !report(&mut states, None) {
+                return;
+            }
+
+            let mut retry = first_retry;
+            reader = loop {
+                thread::sleep(retry);
+                if reader_stop.load(Ordering::Relaxed) {
+                    return;
+                }
+                match open_device_tracker(server.as_ref()) {
+                    Ok((reader, connection)) => {
+                        if let Ok(mut guard) = reader_connection.lock() {
+                            *guard = Some(connection);
+                        }
+                        break reader;
+                    }
+                    Err(e) => {
+                        Logger::info_fmt("adb track-devices reconnect failed:", &[&e.to_string()]);
+                        retry = (retry * 2).min(MAX_TRACKING_RETRY);
+                    }
+                }
+            };
+            // Shut down while reconnecting: the new connection is ours to end.
+            if reader_stop.load(Ordering::Relaxed) {
+                kill_current_child(&reader_connection);
+                wait_current_child(&reader_connection);
+                return;
+            }
+        }
+    });
+    Some(DeviceTracking { stop, connection })
+}
+
+/// Opens `track-devices` through the adb server or the adb binary.
+fn open_device_tracker(
+    server: Option<&AdbClient>,
+) -> Result<(Box<dyn Read + Send>, AdbConnection), Box<dyn Error>> {
+    match server {
+        Some(server) => {
+            let tracker = server.track_devices()?;
+            let socket = tracker.try_clone_stream()?;
+            Ok((Box::new(tracker), AdbConnection::Socket(socket)))
+        }
+        None => {
+            let mut child = Command::new(adb_program())
+                .arg("track-devices")
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()?;
+            let stdout = child.stdout.take().ok_or("Failed to capture adb stdout")?;
+            Ok((Box::new(stdout), AdbConnection::Child(child)))
+        }
+    }
+}
+
+/// Starts a device's logcat and, with `options.kernel`, its kernel log, merging the
+/// two by time before they reach `sender`.
+fn start_device<E: Send + 'static>(
+    serial: Option<&str>,
+    options: &LogcatOptions,
+    sender: IngestSender<E>,
+    wrap: impl Fn(LogcatEvent) -> E + Clone + Send + 'static,
+) -> Result<DeviceStreams, Box<dyn Error>> {
+    if !options.kernel {
+        let (logcat, watch) = start_logcat(serial, options, sender, wrap)?;
+        return Ok(DeviceStreams {
+            logcat,
+            kernel: None,
+            watches: vec![watch],
+        });
+    }
+    // Bounded too, so a stalled merge holds the readers back instead of queueing.
+    let (streams, merged) = bounded(INGEST_CAPACITY, Overflow::Block);
+    let (logcat, watch) = start_logcat(serial, options, streams.clone(), |event| {
+        (Stream::Logcat, event)
+    })?;
+    let kernel_options = LogcatOptions {
+        buffers: vec!["kernel".to_owned()],
+        filterspecs: vec![],
+        kernel: false,
+        ..options.clone()
+    };
+    let (kernel, kernel_watch) = start_logcat(serial, &kernel_options, streams, |event| {
+        (Stream::Kernel, event)
+    })?;
+    thread::spawn(move || merge_streams(merged, sender, wrap));
+    Ok(DeviceStreams {
+        logcat,
+        kernel: Some(kernel),
+        watches: vec![watch, kernel_watch],
+    })
+}
+
+/// A device's reader threads and what the device tracker needs to wake them.
+struct DeviceStreams {
+    logcat: LogcatProcess,
+    kernel: Option<LogcatProcess>,
+    watches: Vec<DeviceWatch>,
+}
+
+/// Which of a device's two streams an event came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stream {
+    Logcat,
+    Kernel,
+}
+
+/// How long a line waits for the device's other stream before it is let through
+/// without knowing whether an earlier line is still on its way.
+const MERGE_HOLD: Duration = Duration::from_millis(200);
+
+/// Interleaves a device's logcat and kernel lines by timestamp. The oldest queued
+/// line goes out once the other stream has a line queued too, or after `MERGE_HOLD`.
+#[derive(Debug, Default)]
+struct TimeMerge {
+    queues: [VecDeque<Queued>; 2],
+    /// Newest timestamp per stream, which lines without a header inherit.
+    times: [String; 2],
+}
+
+#[derive(Debug)]
+struct Queued {
+    arrived: Instant,
+    time: String,
+    line: String,
+}
+
+impl TimeMerge {
+    fn push(&mut self, stream: Stream, line: String, now: Instant) {
+        let i = stream as usize;
+        if let Some(header) = LogFilter::parse_header(&line) {
+            self.times[i] = format!("{} {}", header.date, header.time);
+        }
+        self.queues[i].push_back(Queued {
+            arrived: now,
+            time: self.times[i].clone(),
+            line,
+        });
+    }
+
+    /// Lines due at `now`, oldest timestamp first.
+    fn ready(&mut self, now: Instant) -> Vec<String> {
+        self.drain(|queued| now.duration_since(queued.arrived) >= MERGE_HOLD)
+    }
+
+    /// Every queued line, oldest timestamp first.
+    fn flush(&mut self) -> Vec<String> {
+        self.drain(|_| true)
+    }
+
+    /// When the next line falls due, if any is queued.
+    fn deadline(&self) -> Option<Instant> {
+        self.queues
+            .iter()
+            .filter_map(|queue| queue.front())
+            .map(|queued| queued.arrived + MERGE_HOLD)
+            .min()
+    }
+
+    fn drain(&mut self, due: impl Fn(&Queued) -> bool) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            let next = match (self.queues[0].front(), self.queues[1].front()) {
+                (Some(logcat), Some(kernel)) => usize::from(kernel.time < logcat.time),
+                (Some(logcat), None) if due(logcat) => 0,
+                (None, Some(kernel)) if due(kernel) => 1,
+                _ => break,
+            };
+            if let Some(queued) = self.queues[next].pop_front() {
+                lines.push(queued.line);
+            }
+        }
+        lines
+    }
+}
+
+/// Forwards a device's logcat events with its kernel lines merged in by time.
+/// The kernel stream's own connection events are dropped, as logcat's cover the device.
+fn merge_streams<E>(
+    merged: IngestReceiver<(Stream, LogcatEvent)>,
+    sender: IngestSender<E>,
+    wrap: impl Fn(LogcatEvent) -> E,
+) {
+    let mut merge = TimeMerge::default();
+    loop {
+        let received = match merge.deadline() {
+            Some(deadline) => {
+                merged.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => merged
+                .recv()
+                .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        let mut events = Vec::new();
+        match received {
+            Ok((Stream::Kernel, LogcatEvent::Line(line))) => {
+                merge.push(
+                    Stream::Kernel,
+                    LogFilter::kernel_line(&line),
+                    Instant::now(),
+                );
+            }
+            Ok((Stream::Logcat, LogcatEvent::Line(line))) => {
+                merge.push(Stream::Logcat, line, Instant::now());
+            }
+            Ok((Stream::Kernel, _)) => {}
+            Ok((Stream::Logcat, event)) => {
+                events.extend(merge.flush().into_iter().map(LogcatEvent::Line));
+                events.push(event);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                for line in merge.flush() {
+                    forward(&sender, &wrap, LogcatEvent::Line(line));
+                }
+                return;
+            }
+        }
+        events.extend(
+            merge
+                .ready(Instant::now())
+                .into_iter()
+                .map(LogcatEvent::Line),
+        );
+        for event in events {
+            if !forward(&sender, &wrap, event) {
+                return;
+            }
+        }
+    }
+}
+
+/// Opens the stream a reader thread follows, from `History` with these filterspecs.
+type Opener = Box<
+    dyn Fn(&History, &[String]) -> Result<(Box<dyn Read + Send>, AdbConnection), Box<dyn Error>>
+        + Send,
+>;
+
+fn start_logcat<E: Send + 'static>(
+    serial: Option<&str>,
+    options: &LogcatOptions,
+    sender: IngestSender<E>,
+    wrap: impl Fn(LogcatEvent) -> E + Clone + Send + 'static,
+) -> Result<(LogcatProcess, DeviceWatch), Box<dyn Error>> {
+    let (wake, woken) = mpsc::channel();
+    let notify_sender = sender.clone();
+    let notify_wrap = wrap.clone();
+    let watch = DeviceWatch {
+        serial: serial.map(str::to_owned),
+        notify: Box::new(move |state| {
+            forward(&notify_sender, &notify_wrap, LogcatEvent::State(state))
+        }),
+        wake,
+    };
+
+    // The server answers at once, so reconnecting through it need not wait long.
+    let retry_delay = match options.server {
+        Some(_) => Duration::from_millis(250),
+        None => Duration::from_secs(1),
+    };
     let serial = serial.map(str::to_owned);
+    let open_options = options.clone();
+    let process = start_reader(
+        Box::new(move |history, filterspecs| {
+            open_logcat(serial.as_deref(), &open_options, history, filterspecs)
+        }),
+        options,
+        retry_delay,
+        woken,
+        sender,
+        wrap,
+    )?;
+    Ok((process, watch))
+}
+
+fn start_command<E: Send + 'static>(
+    command: &str,
+    sender: IngestSender<E>,
+    wrap: impl Fn(LogcatEvent) -> E + Send + 'static,
+) -> Result<LogcatProcess, Box<dyn Error>> {
+    let command = command.to_owned();
+    // Nothing tracks the far end of a command, so restarts just wait out the timer.
+    let (_, woken) = mpsc::channel();
+    start_reader(
+        Box::new(move |_, _| open_command(&command)),
+        &LogcatOptions::default(),
+        Duration::from_secs(1),
+        woken,
+        sender,
+        wrap,
+    )
+}
 
-    spawn_stderr_logger(stderr);
+/// Opens the first stream and starts the reader thread, which restarts it from
+/// the last line whenever it ends.
+fn start_reader<E: Send + 'static>(
+    open: Opener,
+    options: &LogcatOptions,
+    retry_delay: Duration,
+    woken: Receiver<()>,
+    sender: IngestSender<E>,
+    wrap: impl Fn(LogcatEvent) -> E + Send + 'static,
+) -> Result<LogcatProcess, Box<dyn Error>> {
+    let (stdout, connection) = open(&options.history, &options.filterspecs)?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let current_child = Arc::new(Mutex::new(Some(connection)));
+    let reader_stop = Arc::clone(&stop);
+    let reader_child = Arc::clone(&current_child);
+    let refilter = Arc::new(AtomicBool::new(false));
+    let filterspecs = Arc::new(Mutex::new(options.filterspecs.clone()));
+    let reader_refilter = Arc::clone(&refilter);
+    let reader_filterspecs = Arc::clone(&filterspecs);
+    let initial_history = options.history.clone();
 
-    // Reading thread: streams the initial spawn, then restarts live-only on exit.
+    // Reading thread: streams the initial spawn, then restarts from the last line on exit.
     thread::spawn(move || {
-        if !stream_stdout(stdout, &sender, &reader_stop) {
+        let mut resume = ResumePoint::default();
+        if !stream_stdout(stdout, &sender, &wrap, &reader_stop, &mut resume) {
             kill_current_child(&reader_child);
             wait_current_child(&reader_child);
             return;
@@ -235,53 +1355,63 @@ pub fn spawn_logcat(serial: Option<&str>) -> Result<LogcatHandle, Box<dyn Error>
         if reader_stop.load(Ordering::Relaxed) {
             return;
         }
-        if sender.send(LogcatEvent::Disconnected).is_err() {
-            return;
+        // A restart for new filterspecs is immediate and invisible to the TUI.
+        let mut refiltering = reader_refilter.swap(false, Ordering::Relaxed);
+        if !refiltering {
+            if !forward(&sender, &wrap, LogcatEvent::Disconnected) {
+                return;
+            }
+            Logger::info_fmt(
+                "log stream exited after initial buffer; restarting from the last line",
+                &[],
+            );
         }
-        Logger::info_fmt(
-            "adb logcat exited after initial buffer; restarting live-only",
-            &[],
-        );
 
         loop {
             if reader_stop.load(Ordering::Relaxed) {
                 return;
             }
-            thread::sleep(Duration::from_secs(1));
+            // The device tracker wakes us as soon as the device is back online.
+            if !refiltering
+                && let Err(mpsc::RecvTimeoutError::Disconnected) = woken.recv_timeout(retry_delay)
+            {
+                thread::sleep(retry_delay);
+            }
             if reader_stop.load(Ordering::Relaxed) {
                 return;
             }
 
-            let mut child = match spawn_adb_logcat(serial.as_deref(), Stdio::piped()) {
-                Ok(c) => c,
+            let history = resume.restart(&initial_history);
+            let filterspecs = reader_filterspecs
+                .lock()
+                .map(|specs| specs.clone())
+                .unwrap_or_default();
+            let (stdout, connection) = match open(&history, &filterspecs) {
+                Ok(opened) => opened,
                 Err(e) => {
-                    Logger::info_fmt("adb restart failed:", &[&e.to_string()]);
+                    Logger::info_fmt("log stream restart failed:", &[&e.to_string()]);
+                    if refiltering {
+                        refiltering = false;
+                        if !forward(&sender, &wrap, LogcatEvent::Disconnected) {
+                            return;
+                        }
+                    }
                     continue;
                 }
             };
-
-            let stdout = match child.stdout.take() {
-                Some(s) => s,
-                None => return,
-            };
-            let stderr = match child.stderr.take() {
-                Some(s) => s,
-                None => return,
-            };
             if let Ok(mut guard) = reader_child.lock() {
-                *guard = Some(child);
+                *guard = Some(connection);
             } else {
                 return;
             }
-            spawn_stderr_logger(stderr);
 
-            if sender.send(LogcatEvent::Connected).is_err() {
+            if !refiltering && !forward(&sender, &wrap, LogcatEvent::Connected) {
                 kill_current_child(&reader_child);
                 wait_current_child(&reader_child);
                 return;
             }
 
-            if !stream_stdout(stdout, &sender, &reader_stop) {
+            if !stream_stdout(stdout, &sender, &wrap, &reader_stop, &mut resume) {
                 kill_current_child(&reader_child);
                 wait_current_child(&reader_child);
                 return;
@@ -291,16 +1421,20 @@ pub fn spawn_logcat(serial: Option<&str>) -> Result<LogcatHandle, Box<dyn Error>
             if reader_stop.load(Ordering::Relaxed) {
                 return;
             }
-            if sender.send(LogcatEvent::Disconnected).is_err() {
-                return;
+            refiltering = reader_refilter.swap(false, Ordering::Relaxed);
+            if !refiltering {
+                if !forward(&sender, &wrap, LogcatEvent::Disconnected) {
+                    return;
+                }
+                Logger::info_fmt("log stream exited, retrying", &[]);
             }
-            Logger::info_fmt("adb logcat live instance exited, retrying", &[]);
         }
     });
 
-    Ok(LogcatHandle {
-        receiver,
+    Ok(LogcatProcess {
         stop,
+        refilter,
+        filterspecs,
         current_child,
     })
 }
@@ -355,6 +1489,100 @@ mod tests {
         );
     }
 
+    #[test]
+    fn time_merge_interleaves_streams_by_timestamp() {
+        let start = Instant::now();
+        let mut merge = TimeMerge::default();
+        merge.push(
+            Stream::Logcat,
+            "01-15 10:30:45.300 1 1 I Nav: b".to_owned(),
+            start,
+        );
+        assert!(merge.ready(start).is_empty());
+
+        merge.push(
+            Stream::Kernel,
+            "01-15 10:30:45.100 0 0 K gnss: a".to_owned(),
+            start,
+        );
+        merge.push(
+            Stream::Kernel,
+            "01-15 10:30:45.500 0 0 K gnss: c".to_owned(),
+            start,
+        );
+        let lines = merge.ready(start);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(": a") && lines[1].ends_with(": b"));
+
+        assert_eq!(merge.deadline(), Some(start + MERGE_HOLD));
+        assert!(merge.ready(start + MERGE_HOLD / 2).is_empty());
+        assert_eq!(merge.ready(start + MERGE_HOLD).len(), 1);
+        assert_eq!(merge.deadline(), None);
+    }
+
+    #[test]
+    fn time_merge_keeps_headerless_lines_after_their_entry() {
+        let start = Instant::now();
+        let mut merge = TimeMerge::default();
+        merge.push(
+            Stream::Logcat,
+            "01-15 10:30:45.300 1 1 E Nav: boom".to_owned(),
+            start,
+        );
+        merge.push(
+            Stream::Logcat,
+            "    at com.example.Nav.run(Nav.kt:3)".to_owned(),
+            start,
+        );
+        merge.push(
+            Stream::Kernel,
+            "01-15 10:30:45.400 0 0 K gnss: x".to_owned(),
+            start,
+        );
+
+        let lines = merge.flush();
+        assert!(lines[1].trim_start().starts_with("at "));
+        assert!(lines[2].contains("gnss"));
+    }
+
+    #[test]
+    fn getprop_output_keeps_device_properties_in_order() {
+        let output = "[ro.build.version.release]: [14]\n[ro.boot.mode]: [normal]\n[ro.product.model]: [Pixel 7]\n";
+        assert_eq!(
+            parse_getprop_output(output),
+            vec![
+                ("ro.product.model".to_owned(), "Pixel 7".to_owned()),
+                ("ro.build.version.release".to_owned(), "14".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn package_version_comes_from_the_installed_package() {
+        let output = "Packages:\n  Package [com.example.nav] (3b1e2f0):\n    userId=10123\n    versionCode=4200 minSdk=26 targetSdk=34\n    versionName=4.2.0\n";
+        assert_eq!(
+            parse_package_version(output).as_deref(),
+            Some("4.2.0 (4200)")
+        );
+        assert_eq!(parse_package_version("Unable to find package: x\n"), None);
+    }
+
+    #[test]
+    fn adb_devices_long_lists_state_model_and_product() {
+        let output = "List of devices attached\n\
+            emulator-5554          device product:sdk_gphone64 model:Pixel_7 device:emu64xa transport_id:1\n\
+            R58M123ABC             unauthorized usb:1-1 transport_id:3\n\
+            0123ABCD               no permissions (missing udev rules?); see [http://developer.android.com/tools/device.html] usb:1-2 transport_id:4\n\n";
+        let devices = parse_adb_devices_long_output(output);
+        assert_eq!(devices.len(), 3);
+        assert_eq!(devices[0].model.as_deref(), Some("Pixel 7"));
+        assert_eq!(devices[0].product.as_deref(), Some("sdk_gphone64"));
+        assert!(devices[0].is_ready() && devices[0].hint().is_none());
+        assert_eq!(devices[1].state, "unauthorized");
+        assert!(devices[1].hint().unwrap().contains("USB debugging"));
+        assert_eq!(devices[2].state, "no permissions");
+    }
+
     #[test]
     fn adb_devices_report_requested_serial_as_unready() {
         let output = "List of devices attached\nemulator-5554\tunauthorized\n";
@@ -363,4 +1591,89 @@ mod tests {
             DeviceCheck::RequestedSerialUnready
         );
     }
+
+    #[test]
+    fn device_state_follows_the_watched_serial() {
+        let devices = parse_device_lines("emulator-5554\tdevice\nR58M123ABC\tunauthorized\n");
+        assert_eq!(
+            DeviceState::of(&devices, Some("emulator-5554")),
+            DeviceState::Online
+        );
+        assert_eq!(
+            DeviceState::of(&devices, Some("R58M123ABC")),
+            DeviceState::Unauthorized
+        );
+        assert_eq!(
+            DeviceState::of(&devices, Some("emulator-5556")),
+            DeviceState::Unplugged
+        );
+        assert_eq!(DeviceState::of(&devices, None), DeviceState::Online);
+        let rebooting = parse_device_lines("emulator-5554\toffline\n");
+        assert_eq!(DeviceState::of(&rebooting, None), DeviceState::Rebooting);
+    }
+
+    #[test]
+    fn history_parses_count_all_and_since() {
+        assert_eq!("500".parse(), Ok(History::Lines(500)));
+        assert_eq!("all".parse(), Ok(History::All));
+        assert_eq!(
+            "since=01-15 10:30:00.000".parse(),
+            Ok(History::Since("01-15 10:30:00.000".to_owned()))
+        );
+        assert!("yesterday".parse::<History>().is_err());
+        assert!("since=".parse::<History>().is_err());
+    }
+
+    #[test]
+    fn logcat_args_pass_buffers_and_history() {
+        let buffers = vec!["main".to_owned(), "crash".to_owned()];
+        assert_eq!(
+            logcat_args(Some("emulator-5554"), &buffers, &History::Lines(0), &[]).join(" "),
+            "-s emulator-5554 logcat -v threadtime -b main -b crash -T 0"
+        );
+        let filterspecs = vec!["Planner:I".to_owned(), "*:F".to_owned()];
+        assert_eq!(
+            logcat_args(None, &[], &History::Lines(0), &filterspecs).join(" "),
+            "logcat -v threadtime -T 0 Planner:I *:F"
+        );
+        assert_eq!(
+            logcat_args(None, &[], &History::All, &[]).join(" "),
+            "logcat -v threadtime"
+        );
+    }
+
+    #[test]
+    fn restart_resumes_at_last_timestamp_without_repeating_lines() {
+        let mut resume = ResumePoint::default();
+        assert_eq!(resume.restart(&History::All), History::All);
+        for line in [
+            "01-15 10:30:45.100  1234  5678 I A: one",
+            "01-15 10:30:45.123  1234  5678 I A: two",
+            "01-15 10:30:45.123  1234  5678 I A: three",
+        ] {
+            assert!(resume.admit(line));
+        }
+
+        assert_eq!(
+            resume.restart(&History::All),
+            History::Since("01-15 10:30:45.123".to_owned())
+        );
+        let admitted: Vec<&str> = [
+            "--------- beginning of main",
+            "01-15 10:30:45.123  1234  5678 I A: three",
+            "01-15 10:30:45.123  1234  5678 I A: two",
+            "01-15 10:30:45.123  1234  5678 I A: four",
+            "01-15 10:30:46.000  1234  5678 I A: five",
+        ]
+        .into_iter()
+        .filter(|line| resume.admit(line))
+        .collect();
+        assert_eq!(
+            admitted,
+            [
+                "01-15 10:30:45.123  1234  5678 I A: four",
+                "01-15 10:30:46.000  1234  5678 I A: five"
+            ]
+        );
+    }
 }
@@ -0,0 +1,238 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::application::adb::{DeviceEntry, parse_device_lines};
+
+/// Where the adb server listens unless `--adb-server` names another address.
+pub const DEFAULT_SERVER: &str = "127.0.0.1:5037";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Why a request to the adb server failed.
+#[derive(Debug)]
+pub enum AdbError {
+    /// Nothing accepted a connection at the address, e.g. the server is not running.
+    Unreachable {
+        addr: String,
+        source: io::Error,
+    },
+    /// The server answered `FAIL` with this message, e.g. `device 'x' not found`.
+    Failed(String),
+    /// The server sent something other than the adb protocol.
+    Protocol(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for AdbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreachable { addr, source } => write!(
+                f,
+                "cannot reach the adb server at {} ({}); start it with `adb start-server`",
+                addr, source
+            ),
+            Self::Failed(message) => write!(f, "adb server: {}", message),
+            Self::Protocol(message) => write!(f, "adb protocol error: {}", message),
+            Self::Io(e) => write!(f, "adb connection error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AdbError {}
+
+impl From<io::Error> for AdbError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Talks to the adb server over its host protocol instead of running the `adb` binary:
+/// each request is a hex length and a service name, answered by `OKAY` or `FAIL`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdbClient {
+    addr: String,
+}
+
+impl AdbClient {
+    pub fn new(addr: impl Into<String>) -> Self {
+        Self { addr: addr.into() }
+    }
+
+    /// The server's protocol version, which also proves it is running.
+    pub fn version(&self) -> Result<u32, AdbError> {
+        let mut stream = self.request("host:version")?;
+        let reply = read_message(&mut stream)?;
+        u32::from_str_radix(&reply, 16)
+            .map_err(|_| AdbError::Protocol(format!("bad version '{}'", reply)))
+    }
+
+    /// Attached devices with their state, model and product (`host:devices-l`).
+    pub fn devices(&self) -> Result<Vec<DeviceEntry>, AdbError> {
+        let mut stream = self.request("host:devices-l")?;
+        Ok(parse_device_lines(&read_message(&mut stream)?))
+    }
+
+    /// Subscribes to device changes (`host:track-devices`); the tracker yields the
+    /// full device list now and after every change.
+    pub fn track_devices(&self) -> Result<DeviceTracker, AdbError> {
+        let stream = self.request("host:track-devices")?;
+        Ok(DeviceTracker { stream })
+    }
+
+    /// Runs `command` in the device shell and returns its output stream, which
+    /// ends when the command exits or the device goes away.
+    pub fn shell(&self, serial: Option<&str>, command: &str) -> Result<TcpStream, AdbError> {
+        let mut stream = self.transport(serial)?;
+        send_request(&mut stream, &format!("shell:{}", command))?;
+        Ok(stream)
+    }
+
+    /// Runs `command` in the device shell and collects all of its output.
+    pub fn shell_output(&self, serial: Option<&str>, command: &str) -> Result<String, AdbError> {
+        let mut output = String::new();
+        self.shell(serial, command)?.read_to_string(&mut output)?;
+        Ok(output)
+    }
+
+    /// Runs `command` on the device without a shell (`exec:`), so binary output
+    /// such as `screencap -p` arrives unchanged.
+    pub fn exec_output(&self, serial: Option<&str>, command: &str) -> Result<Vec<u8>, AdbError> {
+        let mut stream = self.transport(serial)?;
+        send_request(&mut stream, &format!("exec:{}", command))?;
+        let mut output = Vec::new();
+        stream.read_to_end(&mut output)?;
+        Ok(output)
+    }
+
+    /// A connection switched over to the device, ready for a device service.
+    fn transport(&self, serial: Option<&str>) -> Result<TcpStream, AdbError> {
+        self.request(&match serial {
+            Some(serial) => format!("host:transport:{}", serial),
+            None => "host:transport-any".to_owned(),
+        })
+    }
+
+    fn request(&self, service: &str) -> Result<TcpStream, AdbError> {
+        let mut stream = self.connect()?;
+        send_request(&mut stream, service)?;
+        Ok(stream)
+    }
+
+    fn connect(&self) -> Result<TcpStream, AdbError> {
+        let unreachable = |source| AdbError::Unreachable {
+            addr: self.addr.clone(),
+            source,
+        };
+        let addr = std::net::ToSocketAddrs::to_socket_addrs(&self.addr)
+            .map_err(unreachable)?
+            .next()
+            .ok_or_else(|| {
+                unreachable(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "address did not resolve",
+                ))
+            })?;
+        TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).map_err(unreachable)
+    }
+}
+
+/// The open `host:track-devices` connection.
+pub struct DeviceTracker {
+    stream: TcpStream,
+}
+
+impl Read for DeviceTracker {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl DeviceTracker {
+    /// Blocks until the server reports the device list, then returns it.
+    pub fn next_devices(&mut self) -> Result<Vec<DeviceEntry>, AdbError> {
+        Ok(parse_device_lines(&read_message(&mut self.stream)?))
+    }
+
+    /// A handle whose `shutdown` unblocks `next_devices` from another thread.
+    pub fn try_clone_stream(&self) -> Result<TcpStream, AdbError> {
+        Ok(self.stream.try_clone()?)
+    }
+}
+
+/// Quotes `args` for the device shell, leaving plain words as they are.
+pub fn shell_command(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_.,:/=@%+".contains(c));
+            if plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn send_request(stream: &mut TcpStream, service: &str) -> Result<(), AdbError> {
+    stream.write_all(format!("{:04x}{}", service.len(), service).as_bytes())?;
+    let mut status = [0u8; 4];
+    stream.read_exact(&mut status)?;
+    match &status {
+        b"OKAY" => Ok(()),
+        b"FAIL" => Err(AdbError::Failed(read_message(stream)?)),
+        other => Err(AdbError::Protocol(format!(
+            "unexpected status '{}'",
+            String::from_utf8_lossy(other)
+        ))),
+    }
+}
+
+/// Reads one hex-length-prefixed message, as the server sends replies and
+/// `adb track-devices` prints device lists.
+pub(crate) fn read_message(stream: &mut impl Read) -> Result<String, AdbError> {
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len)?;
+    let len = std::str::from_utf8(&len)
+        .ok()
+        .and_then(|hex| usize::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| {
+            AdbError::Protocol(format!("bad length '{}'", String::from_utf8_lossy(&len)))
+        })?;
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body)?;
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_command_quotes_only_what_the_shell_would_split_or_expand() {
+        let args: Vec<String> = ["logcat", "-T", "01-15 10:30:45.123", "*:F", "it's"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        assert_eq!(
+            shell_command(&args),
+            r"logcat -T '01-15 10:30:45.123' '*:F' 'it'\''s'"
+        );
+    }
+
+    #[test]
+    fn unreachable_server_says_how_to_start_it() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        let error = AdbClient::new(addr).version().unwrap_err();
+        assert!(matches!(error, AdbError::Unreachable { .. }));
+        assert!(error.to_string().contains("adb start-server"));
+    }
+}
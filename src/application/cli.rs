@@ -1,4 +1,9 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use crate::application::adb::{History, LOG_BUFFERS};
+use crate::application::adb_client::DEFAULT_SERVER;
+use crate::application::ingest::Overflow;
+use crate::domain::crash_capture::CaptureWindow;
 
 #[derive(Debug, Clone, clap::ValueEnum)]
 pub enum VerbosityLevel {
@@ -48,6 +53,18 @@ EXAMPLES:
   # Select a specific device when multiple are connected
   navcat --serial emulator-5554
 
+  # Include the crash and system buffers and the last 1000 lines before startup
+  navcat --buffers main,system,crash --history 1000
+
+  # Stream logcat from a device attached to another machine
+  navcat --cmd "ssh rig adb logcat -v threadtime"
+
+  # Start with a saved filter preset (see .navcat-presets.toml)
+  navcat --preset replan
+
+  # Print the effective configuration (built-in < ~/.config/navcat/config.toml < .navcat.toml < CLI)
+  navcat config show
+
   # In live mode, use g/r/m keys to toggle guidance/routing/mapmatching at runtime"#
 )]
 pub struct Args {
@@ -55,32 +72,13 @@ pub struct Args {
     #[arg(short, long)]
     pub file: Option<String>,
 
-    /// Log levels to show, comma-separated (V/D/I/W/E/F)
-    #[arg(short, long, default_value = "I,D,E,W")]
-    pub logcat_levels: String,
+    /// Log levels to show, comma-separated (V/D/I/W/E/F) [config default: I,D,E,W]
+    #[arg(short, long)]
+    pub logcat_levels: Option<String>,
 
-    /// Tags to show (comma-separated)
-    #[arg(
-        short,
-        long,
-        default_value = "\
-        DefaultTomTomNavigation,\
-        DistanceAlongRouteCalculator,\
-        ProgressCalculationStep,\
-        RouteTrackingStateStep,\
-        WaypointStatusCheckStep,\
-        DestinationArrivalCheckStep,\
-        DefaultRouteTrackingEngine,\
-        DefaultRouteProgressEngine,\
-        Replan,\
-        Warning,\
-        Guidance,\
-        Planner,\
-        Match,\
-        Project\
-    "
-    )]
-    pub tags: String,
+    /// Tags to show (comma-separated) [config default: the navigation SDK tag list]
+    #[arg(short, long)]
+    pub tags: Option<String>,
 
     /// Additional tags to include beyond the default tag list
     #[arg(short = 'a', long, value_delimiter = ',')]
@@ -90,9 +88,46 @@ pub struct Args {
     #[arg(short, long)]
     pub no_tag_filter: bool,
 
-    /// Target adb device serial for live mode
+    /// Target adb device serial for live mode; repeat to stream several devices
     #[arg(long)]
-    pub serial: Option<String>,
+    pub serial: Vec<String>,
+
+    /// Logcat buffers to read (comma-separated: main, system, crash, events, …); default: logcat's own
+    #[arg(long, value_delimiter = ',', value_parser = clap::builder::PossibleValuesParser::new(LOG_BUFFERS))]
+    pub buffers: Vec<String>,
+
+    /// Log from before navcat started: a line count, `all`, or `since=<MM-DD hh:mm:ss.mmm>`
+    #[arg(long, default_value = "0")]
+    pub history: History,
+
+    /// Apply exact tags and levels on the device with logcat filterspecs, so hidden lines never cross USB
+    #[arg(long)]
+    pub device_filter: bool,
+
+    /// Also stream the kernel log (`logcat -b kernel`) as level K lines merged by time; needs a device that allows reading it
+    #[arg(long)]
+    pub kernel: bool,
+
+    /// Talk to the adb server's host protocol directly instead of running `adb` [default address: 127.0.0.1:5037]
+    #[arg(long, value_name = "HOST:PORT", num_args = 0..=1, default_missing_value = DEFAULT_SERVER)]
+    pub adb_server: Option<String>,
+
+    /// Stream the stdout of this shell command instead of adb logcat, e.g. `ssh rig adb logcat`
+    #[arg(long, value_name = "COMMAND", conflicts_with_all = ["file", "serial", "buffers", "history", "device_filter", "kernel", "adb_server", "package"])]
+    pub cmd: Option<String>,
+
+    /// App package the force-stop and start device actions (key `a`) act on, e.g. `com.example.nav`
+    #[arg(long, value_name = "PACKAGE", conflicts_with = "file")]
+    pub package: Option<String>,
+
+    /// When the screen falls 10000 lines behind: `block` holds adb back, `drop-oldest` or `drop` discard lines and count them in the status bar
+    #[arg(
+        long,
+        value_name = "POLICY",
+        default_value = "block",
+        conflicts_with = "file"
+    )]
+    pub overflow: Overflow,
 
     /// navcat internal debug logging; output goes to /tmp/navcat.log
     #[arg(long, default_value = "none")]
@@ -106,6 +141,30 @@ pub struct Args {
     #[arg(short = 's', long, value_delimiter = ',', allow_hyphen_values = true)]
     pub show_items: Vec<String>,
 
+    /// Only show lines from processes or threads whose name contains one of these (comma-separated), e.g. `:navigation`
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    pub process: Vec<String>,
+
+    /// Load a named filter preset at startup
+    #[arg(long)]
+    pub preset: Option<String>,
+
+    /// Presets file to load and save named filter presets
+    #[arg(long, default_value = ".navcat-presets.toml")]
+    pub presets_file: String,
+
+    /// R8/ProGuard mapping.txt used to retrace obfuscated crash frames and tags
+    #[arg(long)]
+    pub mapping: Option<String>,
+
+    /// Write the raw log around every crash to a file in this directory
+    #[arg(long)]
+    pub crash_dir: Option<String>,
+
+    /// Log kept before and after each captured crash: lines (200) or seconds (30s)
+    #[arg(long, default_value = "200")]
+    pub crash_window: CaptureWindow,
+
     /// Print a shell completion script and exit (supports: zsh)
     #[arg(long)]
     pub completions: Option<String>,
@@ -113,4 +172,30 @@ pub struct Args {
     /// Print version
     #[arg(short = 'v', long)]
     pub version: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Default for Args {
+    /// The arguments of a plain `navcat`, every flag at its default; tests
+    /// override the fields they care about.
+    fn default() -> Self {
+        Self::parse_from(["navcat"])
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Inspect navcat configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ConfigAction {
+    /// Print the effective merged configuration
+    Show,
 }
@@ -0,0 +1,443 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::application::cli::Args;
+use crate::domain::filter_config::{
+    CategoryPatterns, FilterDefaults, FilterRules, HighlightConfig,
+};
+
+pub const PROJECT_CONFIG_FILE: &str = ".navcat.toml";
+pub const DEFAULT_BUFFER_SIZE: usize = 50_000;
+
+/// Effective navcat defaults. Built-in values are overlaid by the user config
+/// (`~/.config/navcat/config.toml`), then the project config (`.navcat.toml`),
+/// then CLI flags.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub tags: Vec<String>,
+    /// Comma-separated levels, same syntax as `-l`.
+    pub levels: String,
+    /// Raw lines kept in memory before the oldest fifth is dropped.
+    pub buffer_size: usize,
+    pub highlight: HighlightConfig,
+    pub categories: CategoryPatterns,
+    pub keys: KeyBindings,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let filter = FilterDefaults::default();
+        Self {
+            tags: filter.tags,
+            levels: filter.levels,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            highlight: HighlightConfig::default(),
+            categories: CategoryPatterns::default(),
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Config files in increasing precedence: user, then project.
+    pub fn default_paths() -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        if let Some(dir) = user_config_dir() {
+            paths.push(dir.join("navcat").join("config.toml"));
+        }
+        paths.push(PathBuf::from(PROJECT_CONFIG_FILE));
+        paths
+    }
+
+    /// Loads the built-in defaults overlaid by each existing file in `paths`, in order.
+    /// Tables are merged key by key, so a file only needs the settings it changes.
+    pub fn load(paths: &[PathBuf]) -> Result<Self, Box<dyn Error>> {
+        let mut merged = toml::Value::try_from(Self::default())?;
+        for path in paths {
+            let text = match fs::read_to_string(path) {
+                Ok(text) => text,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+            };
+            let layer: toml::Value = toml::from_str(&text)
+                .map_err(|e| format!("invalid config {}: {}", path.display(), e))?;
+            merge_toml(&mut merged, layer);
+        }
+        let config: Self = merged
+            .try_into()
+            .map_err(|e| format!("invalid config: {}", e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Applies the CLI flags that override config values.
+    pub fn with_cli_overrides(self, args: &Args) -> Self {
+        let FilterDefaults { tags, levels, .. } = self.filter_defaults().with_cli_overrides(args);
+        Self {
+            tags,
+            levels,
+            ..self
+        }
+    }
+
+    /// What the filters start from under this config, for `FilterState::from_defaults`.
+    pub fn filter_defaults(&self) -> FilterDefaults {
+        FilterDefaults {
+            tags: self.tags.clone(),
+            levels: self.levels.clone(),
+        }
+    }
+
+    /// The highlight rules and tag categories the filters apply under this config.
+    pub fn filter_rules(&self) -> FilterRules {
+        FilterRules {
+            highlight: self.highlight.clone(),
+            categories: self.categories.clone(),
+        }
+    }
+
+    pub fn to_toml(&self) -> Result<String, Box<dyn Error>> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let colors = &self.highlight.colors;
+        for color in [&colors.red, &colors.green, &colors.yellow, &colors.custom] {
+            Color::from_str(color).map_err(|_| format!("invalid highlight color '{}'", color))?;
+        }
+        for rule in &self.highlight.rules {
+            rule.pattern()?;
+            rule.style()?;
+        }
+        if self.buffer_size < 5 {
+            return Err("buffer_size must be at least 5".into());
+        }
+        self.keys.validate()
+    }
+}
+
+fn user_config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+}
+
+/// Recursively overlays `over` onto `base`: tables merge key by key, anything
+/// else (including arrays) replaces the base value.
+fn merge_toml(base: &mut toml::Value, over: toml::Value) {
+    match (base, over) {
+        (toml::Value::Table(base), toml::Value::Table(over)) => {
+            for (key, value) in over {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over,
+    }
+}
+
+/// Actions that can be bound to a single character in the main view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Navigation,
+    Guidance,
+    Routing,
+    MapMatching,
+    Kernel,
+    CategoriesOff,
+    CategoriesOn,
+    LevelsReset,
+    LevelsOff,
+    Undo,
+    Presets,
+    SavePreset,
+    Search,
+    Crashes,
+    Fold,
+    FoldAll,
+    Original,
+    Processes,
+    DeviceInfo,
+    DeviceActions,
+    Save,
+    Follow,
+    Help,
+    Quit,
+    ScrollUp,
+    ScrollDown,
+}
+
+/// Single-character key bindings for the main view. Arrow keys, page keys,
+/// `1`–`6` level toggles and Ctrl combinations are fixed. The category off/on
+/// keys also prefix the crashes key to jump to the previous/next crash (`[c`, `]c`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyBindings {
+    pub navigation: char,
+    pub guidance: char,
+    pub routing: char,
+    pub mapmatching: char,
+    pub kernel: char,
+    pub categories_off: char,
+    pub categories_on: char,
+    pub levels_reset: char,
+    pub levels_off: char,
+    pub undo: char,
+    pub presets: char,
+    pub save_preset: char,
+    pub search: char,
+    pub crashes: char,
+    pub fold: char,
+    pub fold_all: char,
+    pub original: char,
+    pub processes: char,
+    pub device_info: char,
+    pub device_actions: char,
+    pub save: char,
+    pub follow: char,
+    pub help: char,
+    pub quit: char,
+    pub scroll_up: char,
+    pub scroll_down: char,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            navigation: 'n',
+            guidance: 'g',
+            routing: 'r',
+            mapmatching: 'm',
+            kernel: 'K',
+            categories_off: '[',
+            categories_on: ']',
+            levels_reset: '0',
+            levels_off: '-',
+            undo: 'u',
+            presets: 'p',
+            save_preset: 'P',
+            search: '/',
+            crashes: 'c',
+            fold: 'z',
+            fold_all: 'Z',
+            original: 'o',
+            processes: 't',
+            device_info: 'i',
+            device_actions: 'a',
+            save: 'w',
+            follow: 'f',
+            help: '?',
+            quit: 'q',
+            scroll_up: 'k',
+            scroll_down: 'j',
+        }
+    }
+}
+
+impl KeyBindings {
+    fn bindings(&self) -> [(char, Action); 26] {
+        [
+            (self.navigation, Action::Navigation),
+            (self.guidance, Action::Guidance),
+            (self.routing, Action::Routing),
+            (self.mapmatching, Action::MapMatching),
+            (self.kernel, Action::Kernel),
+            (self.categories_off, Action::CategoriesOff),
+            (self.categories_on, Action::CategoriesOn),
+            (self.levels_reset, Action::LevelsReset),
+            (self.levels_off, Action::LevelsOff),
+            (self.undo, Action::Undo),
+            (self.presets, Action::Presets),
+            (self.save_preset, Action::SavePreset),
+            (self.search, Action::Search),
+            (self.crashes, Action::Crashes),
+            (self.fold, Action::Fold),
+            (self.fold_all, Action::FoldAll),
+            (self.original, Action::Original),
+            (self.processes, Action::Processes),
+            (self.device_info, Action::DeviceInfo),
+            (self.device_actions, Action::DeviceActions),
+            (self.save, Action::Save),
+            (self.follow, Action::Follow),
+            (self.help, Action::Help),
+            (self.quit, Action::Quit),
+            (self.scroll_up, Action::ScrollUp),
+            (self.scroll_down, Action::ScrollDown),
+        ]
+    }
+
+    /// Maps a plain (or shifted) character key press to its action.
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        if !(key.modifiers == KeyModifiers::NONE || key.modifiers == KeyModifiers::SHIFT) {
+            return None;
+        }
+        let KeyCode::Char(c) = key.code else {
+            return None;
+        };
+        self.bindings()
+            .into_iter()
+            .find(|(bound, _)| *bound == c)
+            .map(|(_, action)| action)
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let bindings = self.bindings();
+        for (i, (key, action)) in bindings.iter().enumerate() {
+            if ('1'..='6').contains(key) {
+                return Err(format!(
+                    "key '{}' for {:?} is reserved for level toggles",
+                    key, action
+                )
+                .into());
+            }
+            if let Some((_, other)) = bindings[..i].iter().find(|(k, _)| k == key) {
+                return Err(format!(
+                    "key '{}' is bound to both {:?} and {:?}",
+                    key, other, action
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::filter_config::HighlightColors;
+
+    fn write(dir: &Path, name: &str, text: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn missing_files_yield_builtin_defaults() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = Config::load(&[dir.path().join("none.toml")]).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn project_overrides_user_and_tables_merge_per_key() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let user = write(
+            dir.path(),
+            "user.toml",
+            "levels = \"V,D\"\nbuffer_size = 1000\n[highlight.colors]\nred = \"lightred\"\n",
+        );
+        let project = write(
+            dir.path(),
+            "project.toml",
+            "buffer_size = 2000\n[keys]\nundo = \"x\"\n",
+        );
+
+        let config = Config::load(&[user, project]).unwrap();
+
+        assert_eq!(config.levels, "V,D");
+        assert_eq!(config.buffer_size, 2000);
+        assert_eq!(config.highlight.colors.red, "lightred");
+        assert_eq!(config.highlight.colors.green, "green");
+        assert_eq!(config.keys.undo, 'x');
+        assert_eq!(config.keys.quit, 'q');
+    }
+
+    #[test]
+    fn cli_overrides_config() {
+        let config = Config {
+            levels: "V".to_owned(),
+            ..Config::default()
+        };
+        let args = Args {
+            logcat_levels: Some("E".to_owned()),
+            tags: Some("A, B".to_owned()),
+            ..Args::default()
+        };
+
+        let config = config.with_cli_overrides(&args);
+
+        assert_eq!(config.levels, "E");
+        assert_eq!(config.tags, vec!["A", "B"]);
+    }
+
+    #[test]
+    fn unknown_keys_and_bad_colors_are_rejected() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let typo = write(dir.path(), "typo.toml", "buffersize = 10\n");
+        assert!(Config::load(&[typo]).is_err());
+
+        let color = write(
+            dir.path(),
+            "color.toml",
+            "[highlight.colors]\nred = \"reddish\"\n",
+        );
+        assert!(Config::load(&[color]).is_err());
+    }
+
+    #[test]
+    fn highlight_rules_are_loaded_and_validated() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ok = write(
+            dir.path(),
+            "ok.toml",
+            "[[highlight.rules]]\nregex = \"route_id=[0-9a-f-]+\"\nfg = \"cyan\"\nmodifiers = [\"bold\"]\npriority = 40\n",
+        );
+        let config = Config::load(&[ok]).unwrap();
+        assert_eq!(config.highlight.rules.len(), 1);
+        assert_eq!(config.highlight.rules[0].priority, 40);
+        assert_eq!(config.highlight.colors, HighlightColors::default());
+
+        let bad_regex = write(
+            dir.path(),
+            "regex.toml",
+            "[[highlight.rules]]\nregex = \"(\"\npriority = 1\n",
+        );
+        assert!(Config::load(&[bad_regex]).is_err());
+
+        let two_patterns = write(
+            dir.path(),
+            "two.toml",
+            "[[highlight.rules]]\nterm = \"a\"\nphrase = \"b\"\npriority = 1\n",
+        );
+        assert!(Config::load(&[two_patterns]).is_err());
+    }
+
+    #[test]
+    fn duplicate_key_bindings_are_rejected() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let dup = write(dir.path(), "dup.toml", "[keys]\nundo = \"q\"\n");
+        let err = Config::load(&[dup]).unwrap_err().to_string();
+        assert!(err.contains("bound to both"));
+    }
+
+    #[test]
+    fn key_bindings_ignore_control_combinations() {
+        let keys = KeyBindings::default();
+        let plain = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        let ctrl = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(keys.action(&plain), Some(Action::Routing));
+        assert_eq!(keys.action(&ctrl), None);
+    }
+
+    #[test]
+    fn effective_config_round_trips_through_toml() {
+        let text = Config::default().to_toml().unwrap();
+        let parsed: Config = toml::from_str(&text).unwrap();
+        assert_eq!(parsed, Config::default());
+    }
+}
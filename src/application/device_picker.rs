@@ -0,0 +1,221 @@
+use std::error::Error;
+use std::io;
+use std::time::Duration;
+
+use crossterm::{
+    event::{self, Event, KeyCode},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{
+    Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+
+use crate::application::adb::DeviceEntry;
+
+/// The startup device list: every attached device, ready or not, and the highlighted row.
+pub struct DevicePicker {
+    devices: Vec<DeviceEntry>,
+    selected: usize,
+    notice: Option<String>,
+}
+
+impl DevicePicker {
+    /// Starts with the first ready device highlighted.
+    pub fn new(devices: Vec<DeviceEntry>) -> Self {
+        let selected = devices.iter().position(DeviceEntry::is_ready).unwrap_or(0);
+        Self {
+            devices,
+            selected,
+            notice: None,
+        }
+    }
+
+    pub fn move_selection(&mut self, down: bool) {
+        self.selected = if down {
+            (self.selected + 1).min(self.devices.len().saturating_sub(1))
+        } else {
+            self.selected.saturating_sub(1)
+        };
+        self.notice = None;
+    }
+
+    /// The highlighted device's serial if it is ready; otherwise explains why not.
+    pub fn choose(&mut self) -> Option<String> {
+        let device = self.devices.get(self.selected)?;
+        if device.is_ready() {
+            return Some(device.serial.clone());
+        }
+        self.notice = Some(format!(
+            "{} is {}: {}",
+            device.serial,
+            device.state,
+            device.hint().unwrap_or_default()
+        ));
+        None
+    }
+
+    /// Replaces the list, keeping the highlighted device if it is still attached.
+    pub fn refresh(&mut self, devices: Vec<DeviceEntry>) {
+        let serial = self.devices.get(self.selected).map(|d| d.serial.clone());
+        *self = Self::new(devices);
+        if let Some(index) = serial.and_then(|s| self.devices.iter().position(|d| d.serial == s)) {
+            self.selected = index;
+        }
+    }
+}
+
+/// Lets the user choose one of `devices`, re-listed by `list` on refresh.
+/// Returns `None` if they quit instead.
+pub fn pick_device(
+    devices: Vec<DeviceEntry>,
+    list: impl Fn() -> Result<Vec<DeviceEntry>, Box<dyn Error>>,
+) -> Result<Option<String>, Box<dyn Error>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let result = picker_loop(&mut terminal, DevicePicker::new(devices), list);
+
+    let _ = disable_raw_mode();
+    let _ = execute!(terminal.backend_mut(), LeaveAlternateScreen);
+    let _ = terminal.show_cursor();
+    result
+}
+
+fn picker_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    mut picker: DevicePicker,
+    list: impl Fn() -> Result<Vec<DeviceEntry>, Box<dyn Error>>,
+) -> Result<Option<String>, Box<dyn Error>> {
+    loop {
+        terminal.draw(|frame| render(&picker, frame))?;
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+            KeyCode::Up | KeyCode::Char('k') => picker.move_selection(false),
+            KeyCode::Down | KeyCode::Char('j') => picker.move_selection(true),
+            KeyCode::Char('r') => picker.refresh(list()?),
+            KeyCode::Enter => {
+                if let Some(serial) = picker.choose() {
+                    return Ok(Some(serial));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn render(picker: &DevicePicker, frame: &mut ratatui::Frame) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(frame.area());
+
+    let items: Vec<ListItem> = picker.devices.iter().map(device_item).collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" select a device — enter:start  r:refresh  esc:quit "),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::White)
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut state = ListState::default().with_selected(Some(picker.selected));
+    frame.render_stateful_widget(list, chunks[0], &mut state);
+
+    let notice = picker.notice.clone().unwrap_or_default();
+    frame.render_widget(
+        Paragraph::new(Line::from(Span::styled(
+            format!("  {}", notice),
+            Style::default().fg(Color::Yellow),
+        ))),
+        chunks[1],
+    );
+}
+
+fn device_item(device: &DeviceEntry) -> ListItem<'static> {
+    let dim = Style::default().fg(Color::DarkGray);
+    let state_style = if device.is_ready() {
+        Style::default().fg(Color::Green)
+    } else {
+        Style::default().fg(Color::Yellow)
+    };
+    let mut spans = vec![
+        Span::raw(format!(" {:<24}", device.serial)),
+        Span::styled(format!("{:<16}", device.state), state_style),
+        Span::raw(format!("{:<24}", device.model.as_deref().unwrap_or("-"))),
+        Span::styled(device.product.clone().unwrap_or_default(), dim),
+    ];
+    if let Some(hint) = device.hint() {
+        spans.push(Span::styled(format!("  ({})", hint), dim));
+    }
+    ListItem::new(Line::from(spans))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(serial: &str, state: &str) -> DeviceEntry {
+        DeviceEntry {
+            serial: serial.to_owned(),
+            state: state.to_owned(),
+            model: None,
+            product: None,
+        }
+    }
+
+    #[test]
+    fn starts_on_first_ready_device_and_chooses_it() {
+        let mut picker = DevicePicker::new(vec![
+            device("R58M123ABC", "unauthorized"),
+            device("emulator-5554", "device"),
+        ]);
+        assert_eq!(picker.choose().as_deref(), Some("emulator-5554"));
+    }
+
+    #[test]
+    fn choosing_unready_device_shows_hint() {
+        let mut picker = DevicePicker::new(vec![
+            device("R58M123ABC", "unauthorized"),
+            device("emulator-5554", "device"),
+        ]);
+        picker.move_selection(false);
+
+        assert!(picker.choose().is_none());
+        assert!(picker.notice.as_deref().unwrap().contains("USB debugging"));
+    }
+
+    #[test]
+    fn refresh_keeps_highlighted_device() {
+        let mut picker = DevicePicker::new(vec![
+            device("emulator-5554", "device"),
+            device("emulator-5556", "device"),
+        ]);
+        picker.move_selection(true);
+
+        picker.refresh(vec![
+            device("R58M123ABC", "device"),
+            device("emulator-5554", "device"),
+            device("emulator-5556", "device"),
+        ]);
+
+        assert_eq!(picker.choose().as_deref(), Some("emulator-5556"));
+    }
+}
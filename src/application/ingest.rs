@@ -0,0 +1,309 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Log events the TUI may fall behind by before the `Overflow` policy applies.
+pub const INGEST_CAPACITY: usize = 10_000;
+
+/// What a full ingestion channel does with the next log line. Connection and
+/// device state events are never dropped or held back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Waits for room: the reader stops reading adb, so logcat falls behind on the device.
+    #[default]
+    Block,
+    /// Drops the oldest queued line to make room, keeping the log current.
+    DropOldest,
+    /// Drops the new line, keeping what is already queued.
+    Drop,
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    /// `block`, `drop-oldest` or `drop`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "block" => Ok(Self::Block),
+            "drop-oldest" => Ok(Self::DropOldest),
+            "drop" => Ok(Self::Drop),
+            other => Err(format!(
+                "invalid overflow policy '{}': expected block, drop-oldest or drop",
+                other
+            )),
+        }
+    }
+}
+
+/// A multi-producer channel holding at most `capacity` lines, between the reader
+/// threads and the TUI. Mirrors the `mpsc` API the readers and the TUI use.
+pub fn bounded<T>(capacity: usize, overflow: Overflow) -> (IngestSender<T>, IngestReceiver<T>) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(Queue {
+            events: VecDeque::new(),
+            lines: 0,
+            senders: 1,
+            receiver: true,
+        }),
+        filled: Condvar::new(),
+        emptied: Condvar::new(),
+        capacity: capacity.max(1),
+        overflow,
+        dropped: AtomicU64::new(0),
+    });
+    (
+        IngestSender {
+            shared: Arc::clone(&shared),
+        },
+        IngestReceiver { shared },
+    )
+}
+
+struct Shared<T> {
+    queue: Mutex<Queue<T>>,
+    /// Signalled when an event is queued or the last sender goes away.
+    filled: Condvar,
+    /// Signalled when an event is taken or the receiver goes away.
+    emptied: Condvar,
+    capacity: usize,
+    overflow: Overflow,
+    dropped: AtomicU64,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, Queue<T>> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+struct Queue<T> {
+    /// Queued events; `true` marks a line, which the policy may drop.
+    events: VecDeque<(T, bool)>,
+    /// Lines in `events`; only lines count against the capacity.
+    lines: usize,
+    senders: usize,
+    receiver: bool,
+}
+
+pub struct IngestSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> IngestSender<T> {
+    /// Queues a log line under the channel's `Overflow` policy. Fails only once
+    /// the receiver is gone.
+    pub fn send(&self, line: T) -> Result<(), SendError<T>> {
+        let shared = &self.shared;
+        let mut queue = shared.lock();
+        loop {
+            if !queue.receiver {
+                return Err(SendError(line));
+            }
+            if queue.lines < shared.capacity {
+                break;
+            }
+            match shared.overflow {
+                Overflow::Block => {
+                    queue = shared
+                        .emptied
+                        .wait(queue)
+                        .unwrap_or_else(|e| e.into_inner());
+                }
+                Overflow::DropOldest => {
+                    if let Some(oldest) = queue.events.iter().position(|(_, line)| *line) {
+                        queue.events.remove(oldest);
+                        queue.lines -= 1;
+                    }
+                    shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    break;
+                }
+                Overflow::Drop => {
+                    shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+            }
+        }
+        queue.events.push_back((line, true));
+        queue.lines += 1;
+        shared.filled.notify_one();
+        Ok(())
+    }
+
+    /// Queues an event that must arrive, such as `Connected`, even when the channel is full.
+    pub fn send_control(&self, event: T) -> Result<(), SendError<T>> {
+        let mut queue = self.shared.lock();
+        if !queue.receiver {
+            return Err(SendError(event));
+        }
+        queue.events.push_back((event, false));
+        self.shared.filled.notify_one();
+        Ok(())
+    }
+}
+
+impl<T> Clone for IngestSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for IngestSender<T> {
+    fn drop(&mut self) {
+        let mut queue = self.shared.lock();
+        queue.senders -= 1;
+        if queue.senders == 0 {
+            self.shared.filled.notify_all();
+        }
+    }
+}
+
+pub struct IngestReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> IngestReceiver<T> {
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut queue = self.shared.lock();
+        match self.take(&mut queue) {
+            Some(event) => Ok(event),
+            None if queue.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut queue = self.shared.lock();
+        loop {
+            if let Some(event) = self.take(&mut queue) {
+                return Ok(event);
+            }
+            if queue.senders == 0 {
+                return Err(RecvError);
+            }
+            queue = self
+                .shared
+                .filled
+                .wait(queue)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut queue = self.shared.lock();
+        loop {
+            if let Some(event) = self.take(&mut queue) {
+                return Ok(event);
+            }
+            if queue.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            queue = self
+                .shared
+                .filled
+                .wait_timeout(queue, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    /// Every event queued right now.
+    pub fn try_iter(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.try_recv().ok())
+    }
+
+    /// Lines the `Overflow` policy has dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    fn take(&self, queue: &mut Queue<T>) -> Option<T> {
+        let (event, line) = queue.events.pop_front()?;
+        if line {
+            queue.lines -= 1;
+            self.shared.emptied.notify_one();
+        }
+        Some(event)
+    }
+}
+
+impl<T> Drop for IngestReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.lock().receiver = false;
+        self.shared.emptied.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(overflow: Overflow) -> IngestReceiver<u32> {
+        let (sender, receiver) = bounded(2, overflow);
+        sender.send(1).unwrap();
+        sender.send_control(100).unwrap();
+        sender.send(2).unwrap();
+        sender.send(3).unwrap();
+        receiver
+    }
+
+    #[test]
+    fn drop_oldest_keeps_the_newest_lines_and_every_control_event() {
+        let receiver = fill(Overflow::DropOldest);
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![100, 2, 3]);
+        assert_eq!(receiver.dropped(), 1);
+    }
+
+    #[test]
+    fn drop_keeps_the_queued_lines() {
+        let receiver = fill(Overflow::Drop);
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![1, 100, 2]);
+        assert_eq!(receiver.dropped(), 1);
+    }
+
+    #[test]
+    fn block_waits_for_the_receiver_to_make_room() {
+        let (sender, receiver) = bounded(1, Overflow::Block);
+        sender.send(1).unwrap();
+        let blocked = std::thread::spawn(move || sender.send(2));
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!blocked.is_finished());
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Ok(1));
+        blocked.join().unwrap().unwrap();
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Ok(2));
+        assert_eq!(receiver.dropped(), 0);
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn blocked_sender_gives_up_when_the_receiver_goes_away() {
+        let (sender, receiver) = bounded(1, Overflow::Block);
+        sender.send(1).unwrap();
+        let blocked = std::thread::spawn(move || sender.send(2));
+
+        std::thread::sleep(Duration::from_millis(50));
+        drop(receiver);
+        assert!(blocked.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn overflow_policy_parses_from_the_command_line() {
+        assert_eq!("drop-oldest".parse(), Ok(Overflow::DropOldest));
+        assert_eq!("block".parse(), Ok(Overflow::Block));
+        assert!("newest".parse::<Overflow>().is_err());
+    }
+}
@@ -1,3 +1,8 @@
 pub mod adb;
+pub mod adb_client;
 pub mod cli;
+pub mod config;
+pub mod device_picker;
+pub mod ingest;
+pub mod source;
 pub mod tui;
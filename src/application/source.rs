@@ -0,0 +1,552 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::thread;
+
+use crate::application::adb::{
+    History, LogcatOptions, MultiLogcat, device_info, process_table, run_device_action,
+    spawn_multi_command, spawn_multi_logcat,
+};
+use crate::application::ingest::{INGEST_CAPACITY, IngestReceiver, Overflow, bounded};
+use crate::shared::logger::Logger;
+
+/// A device job ready to run off the UI thread; yields the status message or output.
+pub type DeviceJob = Box<dyn FnOnce() -> Result<String, String> + Send>;
+
+/// A job that reads a device's info off the UI thread, as `(key, value)` pairs.
+pub type DeviceInfoJob = Box<dyn FnOnce() -> Vec<(String, String)> + Send>;
+
+/// What a log source can do beyond streaming lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Restarts itself when its stream drops, reporting `Disconnected` and `Connected`.
+    pub can_reconnect: bool,
+    /// Starts with log written before navcat opened it.
+    pub has_history: bool,
+    /// Applies logcat filterspecs on the device.
+    pub device_filter: bool,
+    /// Streams the kernel log next to logcat, as lines of level `K`.
+    pub kernel: bool,
+    /// Runs `DeviceAction`s such as clearing the log or taking a screenshot.
+    pub device_actions: bool,
+    /// Lists the device's processes and threads for the process name column.
+    pub process_names: bool,
+}
+
+/// What a log stream reports: its lines, and changes to its connection.
+pub enum LogcatEvent {
+    Line(String),
+    Connected,
+    Disconnected,
+    /// The device's state as `adb track-devices` reports it changed.
+    State(DeviceState),
+}
+
+/// Where a device is, as far as the adb server knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceState {
+    Online,
+    Unplugged,
+    Unauthorized,
+    /// Attached but not ready: `offline`, `bootloader`, `recovery`, …
+    Rebooting,
+    /// The adb server cannot be asked, e.g. while it restarts.
+    Unknown,
+}
+
+impl DeviceState {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Online => "online",
+            Self::Unplugged => "unplugged",
+            Self::Unauthorized => "unauthorized",
+            Self::Rebooting => "rebooting",
+            Self::Unknown => "state unknown",
+        }
+    }
+}
+
+/// A `LogcatEvent` from the device at index `device` of the `--serial` list.
+pub struct DeviceEvent {
+    pub device: usize,
+    pub event: LogcatEvent,
+}
+
+/// Something the TUI asks the device to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceAction {
+    /// `logcat -c`
+    ClearLog,
+    /// `screencap -p`, saved to `navcat_<secs>.png`.
+    Screenshot,
+    /// `setprop log.tag.<tag> <level>`, with `level` a word such as `VERBOSE`.
+    SetTagLevel { tag: String, level: String },
+    /// `am force-stop` on the `--package` app.
+    ForceStop,
+    /// Launches the `--package` app through its launcher activity.
+    StartApp,
+}
+
+impl DeviceAction {
+    pub fn label(&self) -> String {
+        match self {
+            Self::ClearLog => "clear device log".to_owned(),
+            Self::Screenshot => "screenshot".to_owned(),
+            Self::SetTagLevel { tag, .. } => format!("set log.tag.{}", tag),
+            Self::ForceStop => "force-stop".to_owned(),
+            Self::StartApp => "start app".to_owned(),
+        }
+    }
+}
+
+/// The `log.tag` property value for a level letter or word, e.g. `v` → `VERBOSE`.
+pub fn log_tag_level(level: &str) -> Option<&'static str> {
+    const LEVELS: [&str; 7] = [
+        "VERBOSE", "DEBUG", "INFO", "WARN", "ERROR", "ASSERT", "SUPPRESS",
+    ];
+    let level = level.to_ascii_uppercase();
+    let level = match level.as_str() {
+        "F" | "FATAL" => "ASSERT",
+        "S" | "SILENT" => "SUPPRESS",
+        other => other,
+    };
+    LEVELS
+        .into_iter()
+        .find(|word| *word == level || (level.len() == 1 && word.starts_with(level)))
+}
+
+/// Where the TUI reads log lines from. Each event carries the index of the
+/// stream (device) it came from.
+pub trait LogSource {
+    /// Starts reading; events arrive on `events` from then on.
+    fn start(&mut self) -> Result<(), Box<dyn Error>>;
+
+    /// The event channel, once started.
+    fn events(&self) -> Option<&IngestReceiver<DeviceEvent>>;
+
+    /// Stops reading and ends every process the source started.
+    fn shutdown(&mut self);
+
+    /// Short name for status messages, e.g. `adb` or the file name.
+    fn description(&self) -> String;
+
+    fn capabilities(&self) -> Capabilities;
+
+    /// Restarts with new device filterspecs; ignored without `device_filter`.
+    fn set_filterspecs(&self, _filterspecs: Vec<String>) {}
+
+    /// The job that runs `action` on the stream `device`; `None` without `device_actions`.
+    fn device_action(&self, _device: usize, _action: DeviceAction) -> Option<DeviceJob> {
+        None
+    }
+
+    /// The job that lists the processes and threads of the stream `device` as
+    /// `ps -A -T` prints them; `None` without `process_names`.
+    fn process_table(&self, _device: usize) -> Option<DeviceJob> {
+        None
+    }
+
+    /// The job that reads the model, build and app version of the stream `device`
+    /// again, e.g. once it is back after a disconnect; `None` for sources without devices.
+    fn device_info(&self, _device: usize) -> Option<DeviceInfoJob> {
+        None
+    }
+}
+
+/// Live logcat from one or more devices through adb.
+pub struct AdbSource {
+    serials: Vec<Option<String>>,
+    options: LogcatOptions,
+    /// The app `ForceStop` and `StartApp` act on.
+    package: Option<String>,
+    logcat: Option<MultiLogcat>,
+}
+
+impl AdbSource {
+    pub fn new(serials: Vec<Option<String>>, options: LogcatOptions) -> Self {
+        Self {
+            serials,
+            options,
+            package: None,
+            logcat: None,
+        }
+    }
+
+    pub fn with_package(mut self, package: Option<String>) -> Self {
+        self.package = package;
+        self
+    }
+}
+
+impl LogSource for AdbSource {
+    fn start(&mut self) -> Result<(), Box<dyn Error>> {
+        self.logcat = Some(spawn_multi_logcat(&self.serials, &self.options)?);
+        Ok(())
+    }
+
+    fn events(&self) -> Option<&IngestReceiver<DeviceEvent>> {
+        self.logcat.as_ref().map(MultiLogcat::receiver)
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(logcat) = &mut self.logcat {
+            logcat.shutdown();
+        }
+    }
+
+    fn description(&self) -> String {
+        "adb".to_owned()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            can_reconnect: true,
+            has_history: self.options.history != History::Lines(0),
+            device_filter: true,
+            kernel: self.options.kernel,
+            device_actions: true,
+            process_names: true,
+        }
+    }
+
+    fn set_filterspecs(&self, filterspecs: Vec<String>) {
+        if let Some(logcat) = &self.logcat {
+            logcat.set_filterspecs(filterspecs);
+        }
+    }
+
+    fn device_action(&self, device: usize, action: DeviceAction) -> Option<DeviceJob> {
+        let serial = self.serials.get(device)?.clone();
+        let server = self.options.server.clone();
+        let package = self.package.clone();
+        Some(Box::new(move || {
+            run_device_action(
+                serial.as_deref(),
+                server.as_ref(),
+                package.as_deref(),
+                &action,
+            )
+            .map_err(|e| e.to_string())
+        }))
+    }
+
+    fn process_table(&self, device: usize) -> Option<DeviceJob> {
+        let serial = self.serials.get(device)?.clone();
+        let server = self.options.server.clone();
+        Some(Box::new(move || {
+            process_table(serial.as_deref(), server.as_ref()).map_err(|e| e.to_string())
+        }))
+    }
+
+    fn device_info(&self, device: usize) -> Option<DeviceInfoJob> {
+        let serial = self.serials.get(device)?.clone();
+        let server = self.options.server.clone();
+        let package = self.package.clone();
+        Some(Box::new(move || {
+            device_info(serial.as_deref(), server.as_ref(), package.as_deref())
+        }))
+    }
+}
+
+/// The stdout of a `--cmd` shell command, restarted whenever it exits.
+pub struct CommandSource {
+    command: String,
+    overflow: Overflow,
+    logcat: Option<MultiLogcat>,
+}
+
+impl CommandSource {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            overflow: Overflow::Block,
+            logcat: None,
+        }
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+impl LogSource for CommandSource {
+    fn start(&mut self) -> Result<(), Box<dyn Error>> {
+        self.logcat = Some(spawn_multi_command(
+            std::slice::from_ref(&self.command),
+            self.overflow,
+        )?);
+        Ok(())
+    }
+
+    fn events(&self) -> Option<&IngestReceiver<DeviceEvent>> {
+        self.logcat.as_ref().map(MultiLogcat::receiver)
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(logcat) = &mut self.logcat {
+            logcat.shutdown();
+        }
+    }
+
+    /// The program the command runs, e.g. `ssh`.
+    fn description(&self) -> String {
+        self.command
+            .split_whitespace()
+            .next()
+            .unwrap_or("command")
+            .to_owned()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            can_reconnect: true,
+            ..Capabilities::default()
+        }
+    }
+}
+
+/// A saved logcat file, streamed by a reader thread from start on.
+pub struct FileSource {
+    path: String,
+    receiver: Option<IngestReceiver<DeviceEvent>>,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            receiver: None,
+        }
+    }
+}
+
+impl LogSource for FileSource {
+    fn start(&mut self) -> Result<(), Box<dyn Error>> {
+        let file = File::open(&self.path)?;
+        // A file has no later chance to resend a line, so the reader waits for room.
+        let (sender, receiver) = bounded(INGEST_CAPACITY, Overflow::Block);
+        let path = self.path.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(file).split(b'\n') {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        Logger::info_fmt("could not read", &[&path, &e.to_string()]);
+                        return;
+                    }
+                };
+                let line = String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(&line));
+                let event = LogcatEvent::Line(line.into_owned());
+                if sender.send(DeviceEvent { device: 0, event }).is_err() {
+                    return;
+                }
+            }
+        });
+        self.receiver = Some(receiver);
+        Ok(())
+    }
+
+    fn events(&self) -> Option<&IngestReceiver<DeviceEvent>> {
+        self.receiver.as_ref()
+    }
+
+    fn shutdown(&mut self) {}
+
+    fn description(&self) -> String {
+        Path::new(&self.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.clone())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            has_history: true,
+            ..Capabilities::default()
+        }
+    }
+}
+
+/// Events sent by the caller through a channel, as tests drive the TUI.
+pub struct ChannelSource {
+    receiver: IngestReceiver<DeviceEvent>,
+    capabilities: Capabilities,
+}
+
+impl ChannelSource {
+    pub fn new(receiver: IngestReceiver<DeviceEvent>, capabilities: Capabilities) -> Self {
+        Self {
+            receiver,
+            capabilities,
+        }
+    }
+}
+
+impl LogSource for ChannelSource {
+    fn start(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn events(&self) -> Option<&IngestReceiver<DeviceEvent>> {
+        Some(&self.receiver)
+    }
+
+    fn shutdown(&mut self) {}
+
+    fn description(&self) -> String {
+        "channel".to_owned()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn lines(source: &dyn LogSource) -> Vec<String> {
+        source
+            .events()
+            .unwrap()
+            .try_iter()
+            .filter_map(|DeviceEvent { event, .. }| match event {
+                LogcatEvent::Line(line) => Some(line),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Every line the source sends until its reader is done.
+    fn all_lines(source: &dyn LogSource) -> Vec<String> {
+        let events = source.events().unwrap();
+        std::iter::from_fn(|| events.recv().ok())
+            .filter_map(|DeviceEvent { event, .. }| match event {
+                LogcatEvent::Line(line) => Some(line),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn file_source_sends_every_line_and_is_named_after_the_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"first\nsecond\r\nbad \xff byte\n").unwrap();
+        let mut source = FileSource::new(file.path().to_string_lossy());
+
+        assert!(source.events().is_none());
+        source.start().unwrap();
+
+        assert_eq!(
+            all_lines(&source),
+            vec!["first", "second", "bad \u{fffd} byte"]
+        );
+        assert_eq!(
+            source.description(),
+            file.path().file_name().unwrap().to_string_lossy()
+        );
+        assert!(!source.capabilities().can_reconnect);
+        assert!(source.capabilities().has_history);
+    }
+
+    #[test]
+    fn file_longer_than_the_channel_arrives_whole() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for i in 0..INGEST_CAPACITY * 2 + 1 {
+            writeln!(file, "line {}", i).unwrap();
+        }
+        let mut source = FileSource::new(file.path().to_string_lossy());
+        source.start().unwrap();
+
+        let lines = all_lines(&source);
+        assert_eq!(lines.len(), INGEST_CAPACITY * 2 + 1);
+        assert_eq!(
+            lines.last().unwrap(),
+            &format!("line {}", INGEST_CAPACITY * 2)
+        );
+        assert_eq!(source.events().unwrap().dropped(), 0);
+    }
+
+    #[test]
+    fn missing_file_fails_to_start() {
+        let mut source = FileSource::new("/nonexistent/navcat.txt");
+        assert!(source.start().is_err());
+    }
+
+    #[test]
+    fn channel_source_passes_on_what_the_caller_sends() {
+        let (sender, receiver) = bounded(16, Overflow::Block);
+        let mut source = ChannelSource::new(receiver, Capabilities::default());
+        source.start().unwrap();
+        sender
+            .send(DeviceEvent {
+                device: 0,
+                event: LogcatEvent::Line("sent".to_owned()),
+            })
+            .unwrap();
+
+        assert_eq!(lines(&source), vec!["sent"]);
+    }
+
+    #[test]
+    fn command_source_is_named_after_its_program() {
+        let source = CommandSource::new("ssh rig adb logcat");
+        assert_eq!(source.description(), "ssh");
+        assert!(source.capabilities().can_reconnect);
+        assert!(!source.capabilities().device_filter);
+    }
+
+    #[test]
+    fn adb_source_has_history_only_when_asked_for() {
+        let live = AdbSource::new(vec![None], LogcatOptions::default());
+        assert!(!live.capabilities().has_history);
+
+        let options = LogcatOptions {
+            history: History::All,
+            ..LogcatOptions::default()
+        };
+        assert!(
+            AdbSource::new(vec![None], options)
+                .capabilities()
+                .has_history
+        );
+    }
+
+    #[test]
+    fn only_adb_runs_device_actions_and_only_for_its_devices() {
+        let adb = AdbSource::new(
+            vec![Some("emulator-5554".to_owned())],
+            LogcatOptions::default(),
+        );
+        assert!(adb.capabilities().device_actions);
+        assert!(adb.device_action(0, DeviceAction::ClearLog).is_some());
+        assert!(adb.device_action(1, DeviceAction::ClearLog).is_none());
+
+        let command = CommandSource::new("ssh rig adb logcat");
+        assert!(!command.capabilities().device_actions);
+        assert!(command.device_action(0, DeviceAction::ClearLog).is_none());
+        assert!(!command.capabilities().process_names);
+        assert!(command.process_table(0).is_none());
+        assert!(adb.process_table(0).is_some());
+    }
+
+    #[test]
+    fn log_tag_level_accepts_letters_and_words() {
+        assert_eq!(log_tag_level("v"), Some("VERBOSE"));
+        assert_eq!(log_tag_level("Debug"), Some("DEBUG"));
+        assert_eq!(log_tag_level("F"), Some("ASSERT"));
+        assert_eq!(log_tag_level("S"), Some("SUPPRESS"));
+        assert_eq!(log_tag_level("loud"), None);
+    }
+
+    #[test]
+    fn app_actions_need_a_package() {
+        let adb = AdbSource::new(vec![None], LogcatOptions::default());
+        let job = adb.device_action(0, DeviceAction::ForceStop).unwrap();
+        assert!(job().unwrap_err().contains("--package"));
+    }
+}
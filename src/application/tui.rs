@@ -1,5 +1,9 @@
+use std::collections::HashSet;
 use std::io;
-use std::sync::mpsc::Receiver;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
 
 use std::time::{Duration, Instant};
 
@@ -14,24 +18,185 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
 };
 
-use crate::application::adb::{LogcatEvent, LogcatHandle};
-use crate::domain::filter::{LogFilter, StyledLine};
-use crate::domain::filter_config::{FilterState, LevelState};
-
-const MAX_BUFFER: usize = 50_000;
-const TRIM_SIZE: usize = 10_000;
+use crate::application::config::{Action, DEFAULT_BUFFER_SIZE, KeyBindings};
+use crate::application::source::{
+    Capabilities, DeviceAction, DeviceEvent, DeviceState, LogSource, LogcatEvent, log_tag_level,
+};
+use crate::domain::crash_capture::CrashCapture;
+use crate::domain::crash_index::{CrashSummary, index_crashes};
+use crate::domain::device_filter::DeviceFilter;
+use crate::domain::entry_group::EntryGrouper;
+use crate::domain::filter::{CrashKind, LogFilter, MARKER_PREFIX, StyledLine};
+use crate::domain::filter_config::{FilterRules, FilterState, LevelState};
+use crate::domain::filter_history::{FilterHistory, FilterSnapshot, describe_change};
+use crate::domain::frame_fold::{CrashTracker, FrameFolds, append_line};
+use crate::domain::preset::{Preset, PresetFile};
+use crate::domain::process_names::ProcessNames;
+use crate::domain::retrace::Retracer;
+use crate::shared::logger::Logger;
+
+const MAX_BUFFER: usize = DEFAULT_BUFFER_SIZE;
+/// Lines dropped from the front of the buffer when it overflows, as a fraction of its size.
+const TRIM_DIVISOR: usize = 5;
+#[cfg(test)]
+const TRIM_SIZE: usize = MAX_BUFFER / TRIM_DIVISOR;
 const FLASH_MS: u64 = 350;
+const NOTICE_MS: u64 = 3000;
+/// How long after a category key the crashes key turns it into a crash jump.
+const PREFIX_MS: u64 = 500;
+/// How often live devices are asked for their process and thread names.
+const PROCESS_REFRESH: Duration = Duration::from_secs(10);
+/// Width of the process name column; longer names keep their end, e.g. `:navigation`.
+const PROCESS_COLUMN_WIDTH: usize = 28;
+/// Events handled per frame at most, so a log storm cannot hold off rendering and keys.
+const MAX_EVENTS_PER_FRAME: usize = 5_000;
+/// How often the lines/sec figure in the status bar is recomputed.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+/// Device column colors, assigned in `--serial` order.
+const DEVICE_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::LightGreen,
+    Color::LightMagenta,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightRed,
+];
+
+/// Startup options for the TUI that are not part of the filter state.
+#[derive(Debug, Clone)]
+pub struct TuiOptions {
+    pub presets_path: PathBuf,
+    pub initial_search: String,
+    pub buffer_size: usize,
+    /// Highlight rules and tag categories from the config.
+    pub rules: FilterRules,
+    pub keys: KeyBindings,
+    pub retracer: Option<Arc<Retracer>>,
+    pub crash_capture: Option<CrashCapture>,
+    /// Labels of the live devices, one per logcat stream.
+    pub devices: Vec<String>,
+    /// The filter logcat was started with, when filtering is pushed to the device.
+    pub device_filter: Option<DeviceFilter>,
+    /// Model, build and app version of each live device, as `(key, value)` pairs.
+    pub device_info: Vec<Vec<(String, String)>>,
+}
+
+impl Default for TuiOptions {
+    fn default() -> Self {
+        Self {
+            presets_path: PathBuf::new(),
+            initial_search: String::new(),
+            buffer_size: MAX_BUFFER,
+            rules: FilterRules::default(),
+            keys: KeyBindings::default(),
+            retracer: None,
+            crash_capture: None,
+            devices: Vec::new(),
+            device_filter: None,
+            device_info: Vec::new(),
+        }
+    }
+}
+
+/// The preset picker overlay: names loaded from the presets file and the highlighted row.
+struct PresetMenu {
+    names: Vec<String>,
+    selected: usize,
+}
+
+/// Rows of the device action menu, in the order `device_action_at` maps them.
+const DEVICE_ACTION_ITEMS: [&str; 5] = [
+    "clear device log",
+    "screenshot",
+    "set log.tag level…",
+    "force-stop app",
+    "start app",
+];
+
+/// The device action overlay: the device it acts on and the highlighted row.
+struct ActionMenu {
+    device: usize,
+    selected: usize,
+}
+
+/// The crash index overlay: every crash report in the filtered lines.
+struct CrashMenu {
+    entries: Vec<CrashSummary>,
+    selected: usize,
+}
+
+/// The original-text overlay: retraced lines in view as `(shown, logged)` pairs.
+struct OriginalView {
+    lines: Vec<(String, String)>,
+}
+
+/// A live device: its label and color in the device column, and whether adb is connected.
+struct DeviceStatus {
+    label: String,
+    color: Color,
+    /// Whether its logcat is streaming.
+    connected: bool,
+    /// Last state reported by the device tracker, if any.
+    state: Option<DeviceState>,
+    /// When the stream dropped, and the log time of the device's last line then.
+    lost: Option<(Instant, Option<String>)>,
+}
+
+/// Incoming lines per second, measured over whole `RATE_WINDOW`s.
+struct LineRate {
+    since: Instant,
+    lines: usize,
+    per_sec: usize,
+}
+
+impl LineRate {
+    fn new() -> Self {
+        Self {
+            since: Instant::now(),
+            lines: 0,
+            per_sec: 0,
+        }
+    }
+
+    /// Closes the window once it is `RATE_WINDOW` old; true if the rate changed.
+    fn tick(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.since);
+        if elapsed < RATE_WINDOW {
+            return false;
+        }
+        let per_sec = (self.lines as u128 * 1000 / elapsed.as_millis()) as usize;
+        self.since = now;
+        self.lines = 0;
+        let changed = per_sec != self.per_sec;
+        self.per_sec = per_sec;
+        changed
+    }
+}
+
+impl DeviceStatus {
+    /// Why a device that is not streaming is away, e.g. `unauthorized`.
+    fn away_reason(&self) -> &'static str {
+        match self.state {
+            Some(state) if state != DeviceState::Online => state.label(),
+            _ => "disconnected",
+        }
+    }
+}
 
 pub struct AppState {
     raw_buffer: Vec<String>,
+    /// Device index of each raw_buffer line.
+    raw_devices: Vec<usize>,
     filtered_cache: Vec<StyledLine>,
     /// Indices into filtered_cache for the current display set (all lines if no search,
     /// matching lines if search is active). Maintained incrementally to keep leave_follow O(1).
     search_result: Vec<usize>,
     pub filter_state: FilterState,
+    /// Config the filter is built with alongside `filter_state`, kept out of undo history.
+    rules: FilterRules,
     filter: LogFilter,
     scroll_offset: usize,
     follow: bool,
@@ -42,18 +207,75 @@ pub struct AppState {
     search_query: String,
     quit_pending: Option<Instant>,
     save_notice: Option<(Instant, String)>,
-    pub adb_connected: bool,
-    last_was_crash: bool,
+    /// Live devices; empty when reading a file.
+    devices: Vec<DeviceStatus>,
+    /// Kind of the last crash line shown; `None` once a non-crash line arrives.
+    last_crash: Option<CrashKind>,
+    history: FilterHistory,
+    /// Snapshot taken when the search bar opened; recorded as one history entry on close.
+    search_snapshot: Option<FilterSnapshot>,
+    presets_path: PathBuf,
+    preset_menu: Option<PresetMenu>,
+    /// Name being typed for "save current filters as preset"; `Some` while the prompt is open.
+    preset_name_input: Option<String>,
+    action_menu: Option<ActionMenu>,
+    /// Device and `Tag Level` being typed for "set log.tag level"; `Some` while the prompt is open.
+    tag_level_input: Option<(usize, String)>,
+    /// Device action chosen in the menu, waiting for the run loop to hand it to the source.
+    pending_device_action: Option<(usize, DeviceAction)>,
+    /// Process and thread names of each device, by device index.
+    processes: Vec<ProcessNames>,
+    /// Whether the process name column is shown.
+    show_processes: bool,
+    /// Model, build and app version of each device, by device index; empty for
+    /// files and commands.
+    device_info: Vec<Vec<(String, String)>>,
+    /// Devices back after a disconnect, waiting for the run loop to read their info again.
+    pending_device_info: Vec<usize>,
+    /// Whether the device info pane lists every property rather than a summary line.
+    device_info_open: bool,
+    max_buffer: usize,
+    keys: KeyBindings,
+    crash_menu: Option<CrashMenu>,
+    folds: FrameFolds,
+    /// Crash of the last line in filtered_cache, for incremental fold visibility.
+    crash_tracker: CrashTracker,
+    /// Folded-frames row of the framework run at the end of filtered_cache, if any.
+    open_fold: Option<usize>,
+    /// A category key held until the deadline, so the crashes key before it turns it
+    /// into a crash jump (`]c` / `[c`) instead of a filter change.
+    pending_prefix: Option<(Instant, Action)>,
+    retracer: Option<Arc<Retracer>>,
+    original_view: Option<OriginalView>,
+    entries: EntryGrouper,
+    /// Entries with a line matching the search query.
+    search_entries: HashSet<usize>,
+    /// Highest entry id in filtered_cache.
+    newest_entry: Option<usize>,
+    crash_capture: Option<CrashCapture>,
+    /// The filter the device is running, when filtering is pushed down to logcat.
+    device_filter: Option<DeviceFilter>,
+    /// Filterspecs to restart logcat with, set when the filter widened past `device_filter`.
+    pending_filterspecs: Option<Vec<String>>,
+    /// What the log comes from, as named in status messages and gap markers.
+    source: String,
+    source_capabilities: Capabilities,
+    line_rate: LineRate,
+    /// Lines the source's overflow policy has dropped since it started.
+    dropped_lines: u64,
 }
 
 impl AppState {
     pub fn new(filter_state: FilterState) -> Self {
-        let filter = LogFilter::from_state(&filter_state);
+        let rules = FilterRules::default();
+        let filter = LogFilter::from_state(&filter_state, &rules);
         Self {
             raw_buffer: Vec::new(),
+            raw_devices: Vec::new(),
             filtered_cache: Vec::new(),
             search_result: Vec::new(),
             filter_state,
+            rules,
             filter,
             scroll_offset: 0,
             follow: true,
@@ -64,64 +286,165 @@ impl AppState {
             search_query: String::new(),
             quit_pending: None,
             save_notice: None,
-            adb_connected: true,
-            last_was_crash: false,
+            devices: Vec::new(),
+            last_crash: None,
+            history: FilterHistory::new(),
+            search_snapshot: None,
+            presets_path: PathBuf::new(),
+            preset_menu: None,
+            preset_name_input: None,
+            action_menu: None,
+            tag_level_input: None,
+            pending_device_action: None,
+            processes: Vec::new(),
+            show_processes: false,
+            device_info: Vec::new(),
+            pending_device_info: Vec::new(),
+            device_info_open: false,
+            max_buffer: MAX_BUFFER,
+            keys: KeyBindings::default(),
+            crash_menu: None,
+            folds: FrameFolds::new(),
+            crash_tracker: CrashTracker::default(),
+            open_fold: None,
+            pending_prefix: None,
+            retracer: None,
+            original_view: None,
+            entries: EntryGrouper::new(),
+            search_entries: HashSet::new(),
+            newest_entry: None,
+            crash_capture: None,
+            device_filter: None,
+            pending_filterspecs: None,
+            source: "adb".to_owned(),
+            source_capabilities: Capabilities {
+                can_reconnect: true,
+                ..Capabilities::default()
+            },
+            line_rate: LineRate::new(),
+            dropped_lines: 0,
         }
     }
 
+    /// Sets the R8 mapping used to retrace crash frames and tags from now on.
+    pub fn set_retracer(&mut self, retracer: Option<Arc<Retracer>>) {
+        self.retracer = retracer;
+        self.rebuild_filter();
+    }
+
     fn rebuild_filter(&mut self) {
-        self.filter = LogFilter::from_state(&self.filter_state);
+        self.filter = LogFilter::from_state(&self.filter_state, &self.rules)
+            .with_retracer(self.retracer.clone());
         self.rebuild_filtered_cache();
+        self.update_device_filter();
+    }
+
+    /// Pushes filtering down to the device, starting from the filter logcat runs.
+    pub fn set_device_filter(&mut self, device_filter: Option<DeviceFilter>) {
+        self.device_filter = device_filter;
+    }
+
+    /// Narrowing leaves logcat running, since the host drops the extra lines; only a
+    /// filter that passes lines the device drops needs a restart.
+    fn update_device_filter(&mut self) {
+        let Some(current) = &self.device_filter else {
+            return;
+        };
+        let wanted = DeviceFilter::from_state(&self.filter_state, &self.rules.categories);
+        if wanted.widens(current) {
+            self.pending_filterspecs = Some(wanted.filterspecs());
+            self.device_filter = Some(wanted);
+            self.set_notice("  device filter widened — restarting logcat".to_owned());
+        }
+    }
+
+    /// Filterspecs logcat must be restarted with, once.
+    pub fn take_pending_filterspecs(&mut self) -> Option<Vec<String>> {
+        self.pending_filterspecs.take()
     }
 
     fn rebuild_filtered_cache(&mut self) {
         let mut cache = Vec::new();
-        let mut last_was_crash = false;
-        for line in &self.raw_buffer {
-            let is_crash = LogFilter::is_crash_line(line);
-            if !is_crash {
-                last_was_crash = false;
+        let mut open_fold = None;
+        let mut last_crash = None;
+        let mut entries = EntryGrouper::new();
+        for (line, &device) in self.raw_buffer.iter().zip(&self.raw_devices) {
+            let crash = LogFilter::crash_kind(line, last_crash);
+            if crash.is_none() {
+                last_crash = None;
             }
-            if let Some(filtered) = self.filter.matches(line) {
-                if is_crash && !last_was_crash {
-                    cache.push(StyledLine::crash_separator());
+            let process = self.filter_process(device, line);
+            if let Some(filtered) =
+                entries.filter_line(&self.filter, device, line, crash, process.as_deref())
+            {
+                if let Some(kind) = crash
+                    && last_crash != Some(kind)
+                {
+                    let mut separator = StyledLine::crash_separator(kind);
+                    separator.entry = filtered.entry;
+                    separator.device = device;
+                    append_line(&mut cache, &mut open_fold, separator);
                 }
-                cache.push(filtered);
-                if is_crash {
-                    last_was_crash = true;
+                append_line(&mut cache, &mut open_fold, filtered);
+                if crash.is_some() {
+                    last_crash = crash;
                 }
             }
         }
+        self.newest_entry = cache.iter().map(|l| l.entry).max();
         self.filtered_cache = cache;
-        self.last_was_crash = last_was_crash;
+        self.open_fold = open_fold;
+        self.last_crash = last_crash;
+        self.entries = entries;
         self.rebuild_search_result();
     }
 
+    /// Search matches whole entries: a hit on any line of a stack trace shows all of it.
     fn rebuild_search_result(&mut self) {
-        if self.search_query.is_empty() {
-            self.search_result = (0..self.filtered_cache.len()).collect();
-        } else {
-            let q = self.search_query.to_lowercase();
-            self.search_result = self
-                .filtered_cache
-                .iter()
-                .enumerate()
-                .filter(|(_, l)| l.content.to_lowercase().contains(&q))
-                .map(|(i, _)| i)
-                .collect();
-        }
+        let q = self.search_query.to_lowercase();
+        self.search_entries = self
+            .filtered_cache
+            .iter()
+            .filter(|l| !q.is_empty() && l.content.to_lowercase().contains(&q))
+            .map(|l| l.entry)
+            .collect();
+        let mut tracker = CrashTracker::default();
+        self.search_result = self
+            .filtered_cache
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| !self.folds.hides(l, tracker.advance(l)))
+            .filter(|(_, l)| q.is_empty() || self.search_entries.contains(&l.entry))
+            .map(|(i, _)| i)
+            .collect();
+        self.crash_tracker = tracker;
     }
 
     fn push_to_cache(&mut self, line: StyledLine) {
-        let matches = self.search_query.is_empty()
-            || line
-                .content
-                .to_lowercase()
-                .contains(&self.search_query.to_lowercase());
-        if matches {
-            self.search_result.push(self.filtered_cache.len());
+        let start = self.filtered_cache.len();
+        append_line(&mut self.filtered_cache, &mut self.open_fold, line);
+        let q = self.search_query.to_lowercase();
+        for i in start..self.filtered_cache.len() {
+            let line = &self.filtered_cache[i];
+            let entry = line.entry;
+            let seen = self.newest_entry.is_some_and(|newest| entry <= newest);
+            self.newest_entry = Some(self.newest_entry.map_or(entry, |n| n.max(entry)));
+            if !q.is_empty()
+                && line.content.to_lowercase().contains(&q)
+                && self.search_entries.insert(entry)
+                && seen
+            {
+                // Earlier lines of this entry were hidden by the search; bring them back.
+                self.rebuild_search_result();
+                return;
+            }
+            let crash = self.crash_tracker.advance(line);
+            if !self.folds.hides(line, crash)
+                && (q.is_empty() || self.search_entries.contains(&entry))
+            {
+                self.search_result.push(i);
+            }
         }
-        self.filtered_cache.push(line);
     }
 
     fn set_flash(&mut self, key: char) {
@@ -130,579 +453,1383 @@ impl AppState {
 
     fn is_flashing(&self, key: char) -> bool {
         self.flash
-            .map_or(false, |(until, k)| k == key && Instant::now() < until)
+            .is_some_and(|(until, k)| k == key && Instant::now() < until)
     }
 
-    pub fn toggle_navigation(&mut self) {
-        self.filter_state.navigation = !self.filter_state.navigation;
-        self.rebuild_filter();
-        self.set_flash('n');
+    fn set_notice(&mut self, msg: String) {
+        self.save_notice = Some((Instant::now() + Duration::from_millis(NOTICE_MS), msg));
     }
 
-    pub fn toggle_guidance(&mut self) {
-        self.filter_state.guidance = !self.filter_state.guidance;
-        self.rebuild_filter();
-        self.set_flash('g');
+    fn snapshot(&self) -> FilterSnapshot {
+        FilterSnapshot {
+            filter_state: self.filter_state.clone(),
+            search_query: self.search_query.clone(),
+        }
     }
 
-    pub fn toggle_routing(&mut self) {
-        self.filter_state.routing = !self.filter_state.routing;
+    /// Applies `change` to the filter state, recording the previous state in the
+    /// undo history when something actually changed.
+    fn change_filter_state(&mut self, change: impl FnOnce(&mut FilterState)) {
+        let before = self.snapshot();
+        change(&mut self.filter_state);
+        if before.filter_state != self.filter_state {
+            self.history.record(before);
+        }
         self.rebuild_filter();
-        self.set_flash('r');
     }
 
-    pub fn toggle_mapmatching(&mut self) {
-        self.filter_state.mapmatching = !self.filter_state.mapmatching;
+    fn restore_snapshot(&mut self, snapshot: FilterSnapshot) {
+        self.filter_state = snapshot.filter_state;
+        self.search_query = snapshot.search_query;
         self.rebuild_filter();
-        self.set_flash('m');
     }
 
-    pub fn toggle_hint(&mut self) {
-        self.show_hint = !self.show_hint;
+    pub fn undo(&mut self) {
+        let current = self.snapshot();
+        match self.history.undo(current.clone()) {
+            Some(previous) => {
+                self.flash_changed_category(&current, &previous);
+                let msg = format!("  undo: {}", describe_change(&current, &previous));
+                self.restore_snapshot(previous);
+                self.set_notice(msg);
+            }
+            None => self.set_notice("  nothing to undo".to_owned()),
+        }
     }
 
-    pub fn clear_filters(&mut self) {
-        self.filter_state.navigation = false;
-        self.filter_state.guidance = false;
-        self.filter_state.routing = false;
-        self.filter_state.mapmatching = false;
-        self.rebuild_filter();
+    pub fn redo(&mut self) {
+        let current = self.snapshot();
+        match self.history.redo(current.clone()) {
+            Some(next) => {
+                self.flash_changed_category(&current, &next);
+                let msg = format!("  redo: {}", describe_change(&current, &next));
+                self.restore_snapshot(next);
+                self.set_notice(msg);
+            }
+            None => self.set_notice("  nothing to redo".to_owned()),
+        }
     }
 
-    pub fn dump_to_file(&self) -> Result<String, std::io::Error> {
-        use std::io::Write;
-        let filename = {
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default();
-            format!("navcat_{}.txt", now.as_secs())
-        };
-        let mut file = std::fs::File::create(&filename)?;
-        for line in &self.raw_buffer {
-            writeln!(file, "{}", line)?;
+    fn flash_changed_category(&mut self, from: &FilterSnapshot, to: &FilterSnapshot) {
+        let (a, b) = (&from.filter_state, &to.filter_state);
+        let changed = [
+            ('n', a.navigation != b.navigation),
+            ('g', a.guidance != b.guidance),
+            ('r', a.routing != b.routing),
+            ('m', a.mapmatching != b.mapmatching),
+            ('K', a.kernel != b.kernel),
+        ];
+        if let Some(&(key, _)) = changed.iter().find(|(_, changed)| *changed) {
+            self.set_flash(key);
         }
-        Ok(filename)
     }
 
-    pub fn enter_search(&mut self) {
-        self.search_mode = true;
+    pub fn open_crash_index(&mut self) {
+        let entries = index_crashes(&self.filtered_cache);
+        if entries.is_empty() {
+            self.set_notice("  no crashes in buffer".to_owned());
+            return;
+        }
+        self.crash_menu = Some(CrashMenu {
+            selected: entries.len() - 1,
+            entries,
+        });
     }
 
-    pub fn exit_search(&mut self, clear: bool) {
-        self.search_mode = false;
-        if clear {
-            self.search_query.clear();
-            self.rebuild_search_result();
+    /// Opens the original logged text of every retraced line in view.
+    pub fn open_original_view(&mut self) {
+        let offset = self.view_offset();
+        let lines: Vec<(String, String)> = self
+            .search_result
+            .iter()
+            .skip(offset)
+            .take(self.visible_height.max(1))
+            .filter_map(|&i| {
+                let line = &self.filtered_cache[i];
+                Some((line.content.clone(), line.original.clone()?))
+            })
+            .collect();
+        if lines.is_empty() {
+            self.set_notice("  no retraced lines in view".to_owned());
+            return;
         }
+        self.original_view = Some(OriginalView { lines });
     }
 
-    pub fn search_push(&mut self, c: char) {
-        self.search_query.push(c);
-        self.rebuild_search_result();
+    pub fn close_original_view(&mut self) {
+        self.original_view = None;
     }
 
-    pub fn search_pop(&mut self) {
-        self.search_query.pop();
-        self.rebuild_search_result();
-        self.follow = true;
+    fn process_names(&mut self, device: usize) -> &mut ProcessNames {
+        if self.processes.len() <= device {
+            self.processes.resize_with(device + 1, ProcessNames::new);
+        }
+        &mut self.processes[device]
     }
 
-    pub fn clear_search(&mut self) {
-        self.search_query.clear();
-        self.rebuild_search_result();
+    /// `process/thread` of a line from `device`, if its pid has a known name.
+    fn process_label(&self, device: usize, line: &str) -> Option<String> {
+        self.processes.get(device)?.label(line)
     }
 
-    pub fn has_search(&self) -> bool {
-        !self.search_query.is_empty()
+    /// The line's process label for the process filter; skipped while there is none.
+    fn filter_process(&self, device: usize, line: &str) -> Option<String> {
+        if self.filter_state.processes.is_empty() {
+            return None;
+        }
+        self.process_label(device, line)
     }
 
-    pub fn push_line(&mut self, line: String) {
-        self.raw_buffer.push(line.clone());
-        let is_crash = LogFilter::is_crash_line(&line);
-        if !is_crash {
-            self.last_was_crash = false;
-        }
-        if let Some(filtered) = self.filter.matches(&line) {
-            if is_crash && !self.last_was_crash {
-                self.push_to_cache(StyledLine::crash_separator());
-            }
-            self.push_to_cache(filtered);
-            self.last_was_crash = is_crash;
-        }
-        if self.raw_buffer.len() > MAX_BUFFER {
-            self.raw_buffer.drain(..TRIM_SIZE);
-            let visible_before = self.search_result.len();
+    /// Takes in a device's `ps -A -T` listing. Lines of processes that were unknown
+    /// until now are filtered again when a process filter is set.
+    pub fn apply_process_table(&mut self, device: usize, output: &str) {
+        let changed = self.process_names(device).update_from_ps(output);
+        if changed && !self.filter_state.processes.is_empty() {
             self.rebuild_filtered_cache();
-            let visible_removed = visible_before.saturating_sub(self.search_result.len());
-            self.scroll_offset = self.scroll_offset.saturating_sub(visible_removed);
         }
     }
 
-    pub fn raw_count(&self) -> usize {
-        self.raw_buffer.len()
+    /// Whether anything shows or filters by process name, so devices are worth
+    /// asking for their process listing.
+    fn wants_process_names(&self) -> bool {
+        self.show_processes || !self.filter_state.processes.is_empty()
     }
 
-    pub fn filtered_lines(&self) -> &[StyledLine] {
-        &self.filtered_cache
+    pub fn toggle_process_column(&mut self) {
+        self.show_processes = !self.show_processes;
+        let state = if self.show_processes { "on" } else { "off" };
+        self.set_notice(format!("  process names: {}", state));
     }
 
-    fn leave_follow(&mut self) {
-        if self.follow {
-            self.scroll_offset = self.search_result.len().saturating_sub(self.visible_height);
-            self.follow = false;
+    /// Device of the newest line in view, which device actions act on.
+    fn active_device(&self) -> usize {
+        let end = (self.view_offset() + self.visible_height.max(1)).min(self.search_result.len());
+        end.checked_sub(1).map_or(0, |last| {
+            self.filtered_cache[self.search_result[last]].device
+        })
+    }
+
+    pub fn open_action_menu(&mut self) {
+        if !self.source_capabilities.device_actions {
+            self.set_notice(format!("  no device actions for {}", self.source));
+            return;
         }
+        self.action_menu = Some(ActionMenu {
+            device: self.active_device(),
+            selected: 0,
+        });
     }
 
-    pub fn scroll_up(&mut self) {
-        self.leave_follow();
-        self.scroll_offset = self.scroll_offset.saturating_sub(1);
+    pub fn close_action_menu(&mut self) {
+        self.action_menu = None;
     }
 
-    pub fn scroll_down(&mut self) {
-        self.leave_follow();
-        self.scroll_offset += 1; // clamped in render
+    pub fn action_menu_move(&mut self, down: bool) {
+        if let Some(menu) = &mut self.action_menu {
+            menu.selected = if down {
+                (menu.selected + 1).min(DEVICE_ACTION_ITEMS.len() - 1)
+            } else {
+                menu.selected.saturating_sub(1)
+            };
+        }
     }
 
-    pub fn scroll_page_up(&mut self) {
-        self.leave_follow();
-        let step = (self.visible_height / 2).max(1);
-        self.scroll_offset = self.scroll_offset.saturating_sub(step);
+    /// Queues the highlighted action, or opens the tag level prompt for `setprop`.
+    pub fn select_device_action(&mut self) {
+        let Some(menu) = self.action_menu.take() else {
+            return;
+        };
+        let action = match menu.selected {
+            0 => DeviceAction::ClearLog,
+            1 => DeviceAction::Screenshot,
+            2 => {
+                self.tag_level_input = Some((menu.device, String::new()));
+                return;
+            }
+            3 => DeviceAction::ForceStop,
+            _ => DeviceAction::StartApp,
+        };
+        self.pending_device_action = Some((menu.device, action));
     }
 
-    pub fn scroll_page_down(&mut self) {
-        self.leave_follow();
-        let step = (self.visible_height / 2).max(1);
-        self.scroll_offset += step; // clamped in render
+    pub fn cancel_tag_level(&mut self) {
+        self.tag_level_input = None;
     }
 
-    pub fn resume_follow(&mut self) {
-        self.follow = true;
+    pub fn tag_level_push(&mut self, c: char) {
+        if let Some((_, input)) = &mut self.tag_level_input {
+            input.push(c);
+        }
     }
 
-    pub fn clear_buffer(&mut self) {
-        self.raw_buffer.clear();
-        self.filtered_cache.clear();
-        self.search_result.clear();
-        self.scroll_offset = 0;
-        self.follow = true;
-        self.last_was_crash = false;
+    pub fn tag_level_pop(&mut self) {
+        if let Some((_, input)) = &mut self.tag_level_input {
+            input.pop();
+        }
     }
 
-    fn apply_logcat_event(&mut self, event: LogcatEvent) -> bool {
-        match event {
-            LogcatEvent::Line(line) => {
-                self.push_line(line);
-                true
-            }
-            LogcatEvent::Connected => {
-                self.adb_connected = true;
-                false
-            }
-            LogcatEvent::Disconnected => {
-                self.adb_connected = false;
-                false
+    /// Queues `setprop log.tag.<Tag> <LEVEL>` from a typed `Tag Level`, e.g. `Planner V`.
+    pub fn confirm_tag_level(&mut self) {
+        let Some((device, input)) = self.tag_level_input.take() else {
+            return;
+        };
+        let mut words = input.split_whitespace();
+        let parsed = match (words.next(), words.next(), words.next()) {
+            (Some(tag), Some(level), None) => log_tag_level(level).map(|level| (tag, level)),
+            _ => None,
+        };
+        match parsed {
+            Some((tag, level)) => {
+                self.pending_device_action = Some((
+                    device,
+                    DeviceAction::SetTagLevel {
+                        tag: tag.to_owned(),
+                        level: level.to_owned(),
+                    },
+                ));
             }
+            None => self.set_notice("  expected <tag> <V|D|I|W|E|F|S>".to_owned()),
         }
     }
 
-    pub fn toggle_level(&mut self, n: u8) {
-        let ls = &mut self.filter_state.level_state;
-        match n {
-            1 => ls.verbose = !ls.verbose,
-            2 => ls.debug = !ls.debug,
-            3 => ls.info = !ls.info,
-            4 => ls.warn = !ls.warn,
-            5 => ls.error = !ls.error,
-            6 => ls.fatal = !ls.fatal,
-            _ => {}
-        }
-        self.rebuild_filter();
+    pub fn take_pending_device_action(&mut self) -> Option<(usize, DeviceAction)> {
+        self.pending_device_action.take()
     }
 
-    pub fn reset_levels(&mut self) {
-        self.filter_state.level_state = LevelState::default_levels();
-        self.rebuild_filter();
+    /// Reports how a device action went in the status bar.
+    fn finish_device_action(&mut self, label: &str, result: Result<String, String>) {
+        let msg = match result {
+            Ok(msg) => format!("  {}", msg),
+            Err(e) => format!("  {} failed: {}", label, e),
+        };
+        self.set_notice(msg);
     }
 
-    pub fn all_levels_off(&mut self) {
-        self.filter_state.level_state = LevelState::all_off();
-        self.rebuild_filter();
+    pub fn close_crash_index(&mut self) {
+        self.crash_menu = None;
     }
 
-    pub fn all_categories_on(&mut self) {
-        self.filter_state.navigation = true;
-        self.filter_state.guidance = true;
-        self.filter_state.routing = true;
-        self.filter_state.mapmatching = true;
-        self.rebuild_filter();
+    pub fn crash_menu_move(&mut self, down: bool) {
+        if let Some(menu) = &mut self.crash_menu {
+            menu.selected = if down {
+                (menu.selected + 1).min(menu.entries.len().saturating_sub(1))
+            } else {
+                menu.selected.saturating_sub(1)
+            };
+        }
     }
-}
 
-pub fn run_tui(
-    mut logcat: Option<LogcatHandle>,
-    receiver: Option<Receiver<LogcatEvent>>,
-    filter_state: FilterState,
-    preloaded: Vec<String>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    pub fn jump_to_selected_crash(&mut self) {
+        let Some(menu) = self.crash_menu.take() else {
+            return;
+        };
+        if let Some(entry) = menu.entries.get(menu.selected) {
+            self.scroll_to_cache_line(entry.line);
+        }
+    }
 
-    let mut app = AppState::new(filter_state);
+    /// Scrolls so the crash after (or before) the top visible line is at the top.
+    pub fn jump_to_crash(&mut self, forward: bool) {
+        let top = self.top_cache_line();
+        let starts = self
+            .filtered_cache
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.is_crash_start())
+            .map(|(i, _)| i);
+        let target = if forward {
+            starts.filter(|&i| top.is_none_or(|top| i > top)).min()
+        } else {
+            starts.filter(|&i| top.is_some_and(|top| i < top)).max()
+        };
+        match target {
+            Some(line) => self.scroll_to_cache_line(line),
+            None if forward => self.set_notice("  no next crash".to_owned()),
+            None => self.set_notice("  no previous crash".to_owned()),
+        }
+    }
+
+    /// Position in search_result of the line at the top of the view.
+    fn view_offset(&self) -> usize {
+        let offset = if self.follow {
+            self.search_result.len().saturating_sub(self.visible_height)
+        } else {
+            self.scroll_offset
+        };
+        offset.min(self.search_result.len().saturating_sub(1))
+    }
 
-    if !preloaded.is_empty() {
-        for line in preloaded {
-            app.push_line(line);
+    /// Index into filtered_cache of the line at the top of the view.
+    fn top_cache_line(&self) -> Option<usize> {
+        self.search_result.get(self.view_offset()).copied()
+    }
+
+    /// The crash at the top of the view, or else the first one starting below it on screen.
+    fn crash_in_view(&self) -> Option<String> {
+        let offset = self.view_offset();
+        let top = *self.search_result.get(offset)?;
+        let bottom_pos =
+            (offset + self.visible_height.max(1) - 1).min(self.search_result.len() - 1);
+        let bottom = self.search_result[bottom_pos];
+        let mut tracker = CrashTracker::default();
+        for (i, line) in self.filtered_cache.iter().enumerate().take(bottom + 1) {
+            let crash = tracker.advance(line);
+            if i >= top
+                && let Some(crash) = crash
+            {
+                return Some(crash.to_owned());
+            }
         }
-        // follow stays true — start at bottom (most recent events) for file mode
+        None
     }
 
-    let result = run_loop(
-        &mut terminal,
-        &mut app,
-        receiver
-            .as_ref()
-            .or_else(|| logcat.as_ref().map(LogcatHandle::receiver)),
-    );
+    pub fn toggle_fold(&mut self) {
+        let Some(crash) = self.crash_in_view() else {
+            self.set_notice("  no crash in view".to_owned());
+            return;
+        };
+        self.folds.toggle(&crash);
+        self.refresh_folds();
+    }
 
-    // Always restore terminal, even on error
-    let _ = disable_raw_mode();
-    let _ = execute!(terminal.backend_mut(), LeaveAlternateScreen);
-    let _ = terminal.show_cursor();
-    if let Some(ref mut handle) = logcat {
-        handle.shutdown();
+    pub fn toggle_all_folds(&mut self) {
+        self.folds.toggle_all();
+        self.refresh_folds();
+        let state = if self.folds.is_folded(None) {
+            "folded"
+        } else {
+            "unfolded"
+        };
+        self.set_notice(format!("  framework frames {}", state));
     }
 
-    result
-}
+    /// Recomputes the visible lines after a fold change, keeping the top line in place.
+    fn refresh_folds(&mut self) {
+        let top = self.top_cache_line();
+        self.rebuild_search_result();
+        if let Some(top) = top
+            && !self.follow
+        {
+            self.scroll_to_cache_line(top);
+        }
+    }
 
-fn run_loop(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    app: &mut AppState,
-    receiver: Option<&Receiver<LogcatEvent>>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut dirty = true;
+    /// Pauses follow and puts the first displayed line at or after `line` at the top.
+    fn scroll_to_cache_line(&mut self, line: usize) {
+        self.follow = false;
+        self.scroll_offset = self
+            .search_result
+            .partition_point(|&i| i < line)
+            .min(self.search_result.len().saturating_sub(1));
+    }
 
-    loop {
-        // Drain new lines from the adb thread (live mode only)
-        if let Some(rx) = receiver {
-            while let Ok(event) = rx.try_recv() {
-                app.apply_logcat_event(event);
-                dirty = true;
-            }
+    fn run_prefix_action(&mut self, action: Action) {
+        match action {
+            Action::CategoriesOff => self.clear_filters(),
+            Action::CategoriesOn => self.all_categories_on(),
+            _ => {}
         }
+    }
 
-        // Expire toggle flash and trigger one final redraw when it ends
-        if let Some((until, _)) = app.flash {
-            if Instant::now() < until {
-                dirty = true;
-            } else {
-                app.flash = None;
-                dirty = true;
+    /// Holds a `[` / `]` category key for `PREFIX_MS`, so a following crashes key
+    /// (`c`) can jump to a crash without the filters changing in between.
+    pub fn press_category_prefix(&mut self, action: Action) {
+        self.pending_prefix = Some((Instant::now() + Duration::from_millis(PREFIX_MS), action));
+    }
+
+    /// Runs a held category key once its window passed without a `c`; true if it ran.
+    fn expire_prefix(&mut self, now: Instant) -> bool {
+        match self.pending_prefix {
+            Some((deadline, action)) if now >= deadline => {
+                self.pending_prefix = None;
+                self.run_prefix_action(action);
+                true
             }
+            _ => false,
         }
+    }
 
-        // Expire save notice
-        if let Some((deadline, _)) = app.save_notice {
-            if Instant::now() >= deadline {
-                app.save_notice = None;
-                dirty = true;
-            } else {
-                dirty = true;
-            }
+    /// Settles a held category key before `key` is handled. The crashes key within
+    /// the window jumps to the previous / next crash instead and consumes the key
+    /// (true); any other key runs the category key first.
+    fn resolve_prefix(&mut self, key: &KeyEvent, now: Instant) -> bool {
+        let Some((deadline, action)) = self.pending_prefix.take() else {
+            return false;
+        };
+        if now < deadline && self.keys.action(key) == Some(Action::Crashes) {
+            self.jump_to_crash(action == Action::CategoriesOn);
+            return true;
         }
+        self.run_prefix_action(action);
+        false
+    }
 
-        // Expire quit confirmation window
-        if let Some(deadline) = app.quit_pending {
-            if Instant::now() >= deadline {
-                app.quit_pending = None;
-                dirty = true;
-            } else {
-                dirty = true; // keep redrawing so status bar stays live
+    pub fn open_preset_menu(&mut self) {
+        match PresetFile::load(&self.presets_path) {
+            Ok(file) if file.names().is_empty() => {
+                self.set_notice(format!("  no presets in {}", self.presets_path.display()));
+            }
+            Ok(file) => {
+                self.preset_menu = Some(PresetMenu {
+                    names: file.names(),
+                    selected: 0,
+                });
+            }
+            Err(e) => self.set_notice(format!("  {}", e)),
+        }
+    }
+
+    pub fn close_preset_menu(&mut self) {
+        self.preset_menu = None;
+    }
+
+    pub fn preset_menu_move(&mut self, down: bool) {
+        if let Some(menu) = &mut self.preset_menu {
+            menu.selected = if down {
+                (menu.selected + 1).min(menu.names.len().saturating_sub(1))
+            } else {
+                menu.selected.saturating_sub(1)
+            };
+        }
+    }
+
+    pub fn load_selected_preset(&mut self) {
+        let Some(menu) = self.preset_menu.take() else {
+            return;
+        };
+        if let Some(name) = menu.names.get(menu.selected) {
+            self.load_preset(name);
+        }
+    }
+
+    pub fn load_preset(&mut self, name: &str) {
+        let preset = match PresetFile::load(&self.presets_path) {
+            Ok(file) => file.get(name).cloned(),
+            Err(e) => {
+                self.set_notice(format!("  {}", e));
+                return;
+            }
+        };
+        match preset {
+            Some(preset) => {
+                self.apply_preset(&preset);
+                self.set_notice(format!("  loaded preset '{}'", name));
+            }
+            None => self.set_notice(format!("  preset '{}' not found", name)),
+        }
+    }
+
+    fn apply_preset(&mut self, preset: &Preset) {
+        let before = self.snapshot();
+        self.search_query = preset.apply_to(&mut self.filter_state);
+        if before != self.snapshot() {
+            self.history.record(before);
+        }
+        self.rebuild_filter();
+    }
+
+    pub fn begin_save_preset(&mut self) {
+        self.preset_name_input = Some(String::new());
+    }
+
+    pub fn cancel_save_preset(&mut self) {
+        self.preset_name_input = None;
+    }
+
+    pub fn preset_name_push(&mut self, c: char) {
+        if let Some(name) = &mut self.preset_name_input {
+            name.push(c);
+        }
+    }
+
+    pub fn preset_name_pop(&mut self) {
+        if let Some(name) = &mut self.preset_name_input {
+            name.pop();
+        }
+    }
+
+    /// Saves the current filter state and search under the typed name,
+    /// replacing any preset with the same name.
+    pub fn confirm_save_preset(&mut self) {
+        let Some(name) = self.preset_name_input.take() else {
+            return;
+        };
+        let name = name.trim().to_owned();
+        if name.is_empty() {
+            return;
+        }
+        let result = PresetFile::load(&self.presets_path).and_then(|mut file| {
+            file.insert(
+                name.clone(),
+                Preset::from_state(&self.filter_state, &self.search_query),
+            );
+            file.save(&self.presets_path)
+        });
+        let msg = match result {
+            Ok(()) => format!(
+                "  saved preset '{}' to {}",
+                name,
+                self.presets_path.display()
+            ),
+            Err(e) => format!("  preset save failed: {}", e),
+        };
+        self.set_notice(msg);
+    }
+
+    pub fn toggle_navigation(&mut self) {
+        self.change_filter_state(|fs| fs.navigation = !fs.navigation);
+        self.set_flash('n');
+    }
+
+    pub fn toggle_guidance(&mut self) {
+        self.change_filter_state(|fs| fs.guidance = !fs.guidance);
+        self.set_flash('g');
+    }
+
+    pub fn toggle_routing(&mut self) {
+        self.change_filter_state(|fs| fs.routing = !fs.routing);
+        self.set_flash('r');
+    }
+
+    pub fn toggle_mapmatching(&mut self) {
+        self.change_filter_state(|fs| fs.mapmatching = !fs.mapmatching);
+        self.set_flash('m');
+    }
+
+    pub fn toggle_kernel(&mut self) {
+        self.change_filter_state(|fs| fs.kernel = !fs.kernel);
+        self.set_flash('K');
+    }
+
+    pub fn toggle_hint(&mut self) {
+        self.show_hint = !self.show_hint;
+    }
+
+    pub fn clear_filters(&mut self) {
+        self.change_filter_state(|fs| {
+            fs.navigation = false;
+            fs.guidance = false;
+            fs.routing = false;
+            fs.mapmatching = false;
+            fs.kernel = false;
+        });
+    }
+
+    pub fn dump_to_file(&self) -> Result<String, std::io::Error> {
+        let filename = {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            format!("navcat_{}.txt", now.as_secs())
+        };
+        let mut file = std::fs::File::create(&filename)?;
+        self.write_dump(&mut file)?;
+        Ok(filename)
+    }
+
+    /// Writes the raw buffer, after a `#` comment header naming the device it came from.
+    fn write_dump(&self, out: &mut impl io::Write) -> io::Result<()> {
+        let device_info = self.device_info_rows();
+        if !device_info.is_empty() {
+            writeln!(out, "# navcat dump")?;
+            for (key, value) in &device_info {
+                writeln!(out, "# device: {} = {}", key, value)?;
+            }
+        }
+        for line in &self.raw_buffer {
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    pub fn set_device_info(&mut self, device: usize, info: Vec<(String, String)>) {
+        if self.device_info.len() <= device {
+            self.device_info.resize_with(device + 1, Vec::new);
+        }
+        self.device_info[device] = info;
+    }
+
+    /// Takes in the info read again from `device` after a reconnect, which may be
+    /// another phone or build than before, and puts it in later crash captures too.
+    pub fn refresh_device_info(&mut self, device: usize, info: Vec<(String, String)>) {
+        self.set_device_info(device, info);
+        let rows = self.device_info_rows();
+        if let Some(capture) = &mut self.crash_capture {
+            capture.set_device(rows);
+        }
+    }
+
+    pub fn take_pending_device_info(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.pending_device_info)
+    }
+
+    /// Every device's info in one list, for the header pane and the dump.
+    fn device_info_rows(&self) -> Vec<(String, String)> {
+        let labels: Vec<String> = self.devices.iter().map(|d| d.label.clone()).collect();
+        labeled_device_info(&labels, &self.device_info)
+    }
+
+    pub fn toggle_device_info(&mut self) {
+        if self.device_info_rows().is_empty() {
+            self.set_notice(format!("  no device info for {}", self.source));
+            return;
+        }
+        self.device_info_open = !self.device_info_open;
+    }
+
+    /// Rows the device info pane takes above the log.
+    fn header_rows(&self) -> usize {
+        let rows = self.device_info_rows().len();
+        match (rows, self.device_info_open) {
+            (0, _) => 0,
+            (rows, true) => rows,
+            (_, false) => 1,
+        }
+    }
+
+    pub fn enter_search(&mut self) {
+        self.search_mode = true;
+        self.search_snapshot = Some(self.snapshot());
+    }
+
+    pub fn exit_search(&mut self, clear: bool) {
+        self.search_mode = false;
+        let before = self
+            .search_snapshot
+            .take()
+            .unwrap_or_else(|| self.snapshot());
+        if clear {
+            self.search_query.clear();
+            self.rebuild_search_result();
+        }
+        if before.search_query != self.search_query {
+            self.history.record(before);
+        }
+    }
+
+    pub fn search_push(&mut self, c: char) {
+        self.search_query.push(c);
+        self.rebuild_search_result();
+    }
+
+    pub fn search_pop(&mut self) {
+        self.search_query.pop();
+        self.rebuild_search_result();
+        self.follow = true;
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search_query.clear();
+        self.rebuild_search_result();
+    }
+
+    pub fn has_search(&self) -> bool {
+        !self.search_query.is_empty()
+    }
+
+    pub fn push_line(&mut self, line: String) {
+        self.push_device_line(0, line);
+    }
+
+    /// Appends a raw line from the device at index `device` of the device list.
+    pub fn push_device_line(&mut self, device: usize, line: String) {
+        self.raw_buffer.push(line.clone());
+        self.raw_devices.push(device);
+        let crash = LogFilter::crash_kind(&line, self.last_crash);
+        if let Some(capture) = &mut self.crash_capture {
+            let written = capture.push(
+                &self.raw_buffer,
+                &self.raw_devices,
+                crash,
+                &self.filter_state,
+                &self.search_query,
+            );
+            for result in written {
+                match result {
+                    Ok(path) => self.set_notice(format!("  crash saved to {}", path.display())),
+                    Err(e) => self.set_notice(format!("  crash capture failed: {}", e)),
+                }
+            }
+        }
+        if crash.is_none() {
+            self.last_crash = None;
+        }
+        self.process_names(device).observe_line(&line);
+        let process = self.filter_process(device, &line);
+        if let Some(filtered) =
+            self.entries
+                .filter_line(&self.filter, device, &line, crash, process.as_deref())
+        {
+            if let Some(kind) = crash
+                && self.last_crash != Some(kind)
+            {
+                let mut separator = StyledLine::crash_separator(kind);
+                separator.entry = filtered.entry;
+                separator.device = device;
+                self.push_to_cache(separator);
+            }
+            self.push_to_cache(filtered);
+            self.last_crash = crash;
+        }
+        if self.raw_buffer.len() > self.max_buffer {
+            // Never cut a stack trace off from its header.
+            let mut cut = self.max_buffer / TRIM_DIVISOR;
+            while self
+                .raw_buffer
+                .get(cut)
+                .is_some_and(|l| EntryGrouper::is_continuation(l))
+            {
+                cut += 1;
+            }
+            self.raw_buffer.drain(..cut);
+            self.raw_devices.drain(..cut);
+            let visible_before = self.search_result.len();
+            self.rebuild_filtered_cache();
+            let visible_removed = visible_before.saturating_sub(self.search_result.len());
+            self.scroll_offset = self.scroll_offset.saturating_sub(visible_removed);
+        }
+    }
+
+    pub fn raw_count(&self) -> usize {
+        self.raw_buffer.len()
+    }
+
+    pub fn filtered_lines(&self) -> &[StyledLine] {
+        &self.filtered_cache
+    }
+
+    fn leave_follow(&mut self) {
+        if self.follow {
+            self.scroll_offset = self.search_result.len().saturating_sub(self.visible_height);
+            self.follow = false;
+        }
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.leave_follow();
+        self.scroll_offset = self.scroll_offset.saturating_sub(1);
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.leave_follow();
+        self.scroll_offset += 1; // clamped in render
+    }
+
+    pub fn scroll_page_up(&mut self) {
+        self.leave_follow();
+        let step = (self.visible_height / 2).max(1);
+        self.scroll_offset = self.scroll_offset.saturating_sub(step);
+    }
+
+    pub fn scroll_page_down(&mut self) {
+        self.leave_follow();
+        let step = (self.visible_height / 2).max(1);
+        self.scroll_offset += step; // clamped in render
+    }
+
+    pub fn resume_follow(&mut self) {
+        self.follow = true;
+    }
+
+    pub fn clear_buffer(&mut self) {
+        self.raw_buffer.clear();
+        self.raw_devices.clear();
+        self.filtered_cache.clear();
+        self.search_result.clear();
+        self.scroll_offset = 0;
+        self.follow = true;
+        self.last_crash = None;
+        self.crash_tracker = CrashTracker::default();
+        self.open_fold = None;
+        self.entries = EntryGrouper::new();
+        self.search_entries.clear();
+        self.newest_entry = None;
+    }
+
+    fn apply_logcat_event(&mut self, device: usize, event: LogcatEvent) -> bool {
+        match event {
+            LogcatEvent::Line(line) => {
+                self.line_rate.lines += 1;
+                self.push_device_line(device, line);
+                true
+            }
+            LogcatEvent::Disconnected => {
+                let last_time = self.last_log_time(device);
+                if let Some(status) = self.devices.get_mut(device) {
+                    status.connected = false;
+                    status.lost.get_or_insert((Instant::now(), last_time));
+                }
+                false
+            }
+            LogcatEvent::Connected => {
+                let Some(status) = self.devices.get_mut(device) else {
+                    return false;
+                };
+                status.connected = true;
+                let Some((since, last_time)) = status.lost.take() else {
+                    return false;
+                };
+                let source = if self.devices.len() > 1 {
+                    self.devices[device].label.clone()
+                } else {
+                    self.source.clone()
+                };
+                let marker = reconnect_marker(&source, last_time.as_deref(), since.elapsed());
+                self.push_device_line(device, marker);
+                self.pending_device_info.push(device);
+                true
+            }
+            LogcatEvent::State(state) => {
+                if let Some(status) = self.devices.get_mut(device) {
+                    status.state = Some(state);
+                }
+                false
+            }
+        }
+    }
+
+    /// Time of day (`HH:MM:SS`) of the newest logcat line from `device`.
+    fn last_log_time(&self, device: usize) -> Option<String> {
+        self.raw_buffer
+            .iter()
+            .zip(&self.raw_devices)
+            .rev()
+            .filter(|&(_, &d)| d == device)
+            .find_map(|(line, _)| {
+                let header = LogFilter::parse_header(line)?;
+                Some(header.time.split('.').next()?.to_owned())
+            })
+    }
+
+    /// Names the log source and what it supports.
+    pub fn set_source(&mut self, description: String, capabilities: Capabilities) {
+        self.source = description;
+        self.source_capabilities = capabilities;
+    }
+
+    /// Names the live devices, in the order of their event indices.
+    pub fn set_devices(&mut self, labels: Vec<String>) {
+        self.devices = labels
+            .into_iter()
+            .enumerate()
+            .map(|(i, label)| DeviceStatus {
+                label,
+                color: DEVICE_COLORS[i % DEVICE_COLORS.len()],
+                connected: true,
+                state: None,
+                lost: None,
+            })
+            .collect();
+    }
+
+    /// Updates the lines/sec figure and the source's dropped-line count; true if
+    /// the status bar needs a redraw.
+    fn update_ingest(&mut self, now: Instant, dropped: u64) -> bool {
+        let rate_changed = self.line_rate.tick(now);
+        let dropped_changed = dropped != self.dropped_lines;
+        self.dropped_lines = dropped;
+        rate_changed || dropped_changed
+    }
+
+    /// True unless a live device has lost its adb connection.
+    pub fn adb_connected(&self) -> bool {
+        self.devices.iter().all(|d| d.connected)
+    }
+
+    pub fn toggle_level(&mut self, n: u8) {
+        self.change_filter_state(|fs| {
+            let ls = &mut fs.level_state;
+            match n {
+                1 => ls.verbose = !ls.verbose,
+                2 => ls.debug = !ls.debug,
+                3 => ls.info = !ls.info,
+                4 => ls.warn = !ls.warn,
+                5 => ls.error = !ls.error,
+                6 => ls.fatal = !ls.fatal,
+                _ => {}
+            }
+        });
+    }
+
+    pub fn reset_levels(&mut self) {
+        self.change_filter_state(|fs| fs.level_state = LevelState::default_levels());
+    }
+
+    pub fn all_levels_off(&mut self) {
+        self.change_filter_state(|fs| fs.level_state = LevelState::all_off());
+    }
+
+    pub fn all_categories_on(&mut self) {
+        self.change_filter_state(|fs| {
+            fs.navigation = true;
+            fs.guidance = true;
+            fs.routing = true;
+            fs.mapmatching = true;
+            fs.kernel = true;
+        });
+    }
+}
+
+/// The line marking a gap in the stream, e.g.
+/// `─── adb disconnected 10:31:02 (gap 14.2 s) ───`.
+fn reconnect_marker(source: &str, last_time: Option<&str>, gap: Duration) -> String {
+    let at = last_time.map(|t| format!(" {}", t)).unwrap_or_default();
+    format!(
+        "{}{} disconnected{} (gap {:.1} s) ───",
+        MARKER_PREFIX,
+        source,
+        at,
+        gap.as_secs_f64()
+    )
+}
+
+pub fn run_tui(
+    source: &mut dyn LogSource,
+    filter_state: FilterState,
+    options: TuiOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    source.start()?;
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let mut app = AppState::new(filter_state);
+    app.presets_path = options.presets_path;
+    app.max_buffer = options.buffer_size;
+    app.keys = options.keys;
+    app.rules = options.rules;
+    app.set_retracer(options.retracer);
+    app.crash_capture = options.crash_capture;
+    app.set_devices(options.devices);
+    for (device, info) in options.device_info.into_iter().enumerate() {
+        app.set_device_info(device, info);
+    }
+    let capabilities = source.capabilities();
+    app.set_source(source.description(), capabilities);
+    app.set_device_filter(options.device_filter.filter(|_| capabilities.device_filter));
+    app.search_query = options.initial_search;
+    app.rebuild_search_result();
+
+    // follow stays true, so a file opens at its bottom (most recent events)
+    let result = run_loop(&mut terminal, &mut app, source);
+
+    // Always restore terminal, even on error
+    let _ = disable_raw_mode();
+    let _ = execute!(terminal.backend_mut(), LeaveAlternateScreen);
+    let _ = terminal.show_cursor();
+    source.shutdown();
+    if let Some(capture) = &mut app.crash_capture {
+        for written in capture.finish() {
+            match written {
+                Ok(path) => eprintln!("crash saved to {}", path.display()),
+                Err(e) => eprintln!("crash capture failed: {}", e),
+            }
+        }
+    }
+
+    result
+}
+
+fn run_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut AppState,
+    source: &dyn LogSource,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut dirty = true;
+    // Device actions run on their own threads and report back here with their label.
+    let (action_sender, action_results) = mpsc::channel();
+    // Process listings likewise, one job per device, refreshed while none is running
+    // and only while the process column or filter needs them.
+    let (process_sender, process_results) = mpsc::channel();
+    let mut processes_running = 0;
+    let mut next_process_refresh = Instant::now();
+    // Device info too, read again for each device that comes back after a disconnect.
+    let (info_sender, info_results) = mpsc::channel();
+
+    loop {
+        if let Some(filterspecs) = app.take_pending_filterspecs() {
+            source.set_filterspecs(filterspecs);
+        }
+
+        if let Some((device, action)) = app.take_pending_device_action() {
+            let label = action.label();
+            match source.device_action(device, action) {
+                Some(job) => {
+                    app.set_notice(format!("  {}…", label));
+                    let sender = action_sender.clone();
+                    thread::spawn(move || {
+                        let _ = sender.send((label, job()));
+                    });
+                }
+                None => app.set_notice(format!("  {} is not available", label)),
+            }
+            dirty = true;
+        }
+        while let Ok((label, result)) = action_results.try_recv() {
+            app.finish_device_action(&label, result);
+            dirty = true;
+        }
+
+        if source.capabilities().process_names
+            && app.wants_process_names()
+            && processes_running == 0
+            && Instant::now() >= next_process_refresh
+        {
+            next_process_refresh = Instant::now() + PROCESS_REFRESH;
+            for device in 0..app.devices.len().max(1) {
+                let Some(job) = source.process_table(device) else {
+                    continue;
+                };
+                processes_running += 1;
+                let sender = process_sender.clone();
+                thread::spawn(move || {
+                    let _ = sender.send((device, job()));
+                });
+            }
+        }
+        while let Ok((device, result)) = process_results.try_recv() {
+            processes_running -= 1;
+            match result {
+                Ok(output) => app.apply_process_table(device, &output),
+                Err(e) => Logger::info_fmt("could not list device processes:", &[&e]),
+            }
+            dirty = true;
+        }
+
+        for device in app.take_pending_device_info() {
+            if let Some(job) = source.device_info(device) {
+                let sender = info_sender.clone();
+                thread::spawn(move || {
+                    let _ = sender.send((device, job()));
+                });
+            }
+        }
+        while let Ok((device, info)) = info_results.try_recv() {
+            app.refresh_device_info(device, info);
+            dirty = true;
+        }
+
+        // Drain new lines from the source's reader threads, leaving the rest for the next frame
+        if let Some(rx) = source.events() {
+            for DeviceEvent { device, event } in rx.try_iter().take(MAX_EVENTS_PER_FRAME) {
+                app.apply_logcat_event(device, event);
+                dirty = true;
+            }
+            if app.update_ingest(Instant::now(), rx.dropped()) {
+                dirty = true;
+            }
+        }
+
+        // Expire toggle flash and trigger one final redraw when it ends
+        if let Some((until, _)) = app.flash {
+            if Instant::now() < until {
+                dirty = true;
+            } else {
+                app.flash = None;
+                dirty = true;
+            }
+        }
+
+        // Expire save notice
+        if let Some((deadline, _)) = app.save_notice {
+            if Instant::now() >= deadline {
+                app.save_notice = None;
+                dirty = true;
+            } else {
+                dirty = true;
+            }
+        }
+
+        // A category key with no `c` after it runs once its window passes
+        if app.expire_prefix(Instant::now()) {
+            dirty = true;
+        }
+
+        // Expire quit confirmation window
+        if let Some(deadline) = app.quit_pending {
+            if Instant::now() >= deadline {
+                app.quit_pending = None;
+                dirty = true;
+            } else {
+                dirty = true; // keep redrawing so status bar stays live
             }
         }
 
         if dirty {
             // Update visible_height before render so page-scroll has correct size
             if let Ok(size) = terminal.size() {
-                app.visible_height = (size.height as usize).saturating_sub(1);
+                app.visible_height = (size.height as usize).saturating_sub(1 + app.header_rows());
             }
             terminal.draw(|frame| render(app, frame))?;
             dirty = false;
         }
 
-        if event::poll(Duration::from_millis(16))? {
-            if let Event::Key(key) = event::read()? {
-                dirty = true;
-                if app.search_mode {
-                    match key {
-                        KeyEvent {
-                            code: KeyCode::Esc, ..
-                        } => {
-                            if app.has_search() {
-                                app.clear_search();
-                            } else {
-                                app.exit_search(false);
-                            }
-                        }
-                        KeyEvent {
-                            code: KeyCode::Enter,
-                            ..
-                        } => {
+        if event::poll(Duration::from_millis(16))?
+            && let Event::Key(key) = event::read()?
+        {
+            dirty = true;
+            if app.resolve_prefix(&key, Instant::now()) {
+                continue;
+            }
+            if app.preset_name_input.is_some() {
+                match key.code {
+                    KeyCode::Esc => app.cancel_save_preset(),
+                    KeyCode::Enter => app.confirm_save_preset(),
+                    KeyCode::Backspace => app.preset_name_pop(),
+                    KeyCode::Char(c)
+                        if key.modifiers == KeyModifiers::NONE
+                            || key.modifiers == KeyModifiers::SHIFT =>
+                    {
+                        app.preset_name_push(c)
+                    }
+                    _ => dirty = false,
+                }
+            } else if app.tag_level_input.is_some() {
+                match key.code {
+                    KeyCode::Esc => app.cancel_tag_level(),
+                    KeyCode::Enter => app.confirm_tag_level(),
+                    KeyCode::Backspace => app.tag_level_pop(),
+                    KeyCode::Char(c)
+                        if key.modifiers == KeyModifiers::NONE
+                            || key.modifiers == KeyModifiers::SHIFT =>
+                    {
+                        app.tag_level_push(c)
+                    }
+                    _ => dirty = false,
+                }
+            } else if app.action_menu.is_some() {
+                match key.code {
+                    KeyCode::Esc => app.close_action_menu(),
+                    KeyCode::Char(c) if c == app.keys.device_actions => app.close_action_menu(),
+                    KeyCode::Enter => app.select_device_action(),
+                    KeyCode::Up => app.action_menu_move(false),
+                    KeyCode::Char(c) if c == app.keys.scroll_up => app.action_menu_move(false),
+                    KeyCode::Down => app.action_menu_move(true),
+                    KeyCode::Char(c) if c == app.keys.scroll_down => app.action_menu_move(true),
+                    _ => dirty = false,
+                }
+            } else if app.original_view.is_some() {
+                match key.code {
+                    KeyCode::Esc => app.close_original_view(),
+                    KeyCode::Char(c) if c == app.keys.original => app.close_original_view(),
+                    _ => dirty = false,
+                }
+            } else if app.crash_menu.is_some() {
+                match key.code {
+                    KeyCode::Esc => app.close_crash_index(),
+                    KeyCode::Char(c) if c == app.keys.crashes => app.close_crash_index(),
+                    KeyCode::Enter => app.jump_to_selected_crash(),
+                    KeyCode::Up => app.crash_menu_move(false),
+                    KeyCode::Char(c) if c == app.keys.scroll_up => app.crash_menu_move(false),
+                    KeyCode::Down => app.crash_menu_move(true),
+                    KeyCode::Char(c) if c == app.keys.scroll_down => app.crash_menu_move(true),
+                    _ => dirty = false,
+                }
+            } else if app.preset_menu.is_some() {
+                match key.code {
+                    KeyCode::Esc => app.close_preset_menu(),
+                    KeyCode::Char(c) if c == app.keys.presets => app.close_preset_menu(),
+                    KeyCode::Enter => app.load_selected_preset(),
+                    KeyCode::Up => app.preset_menu_move(false),
+                    KeyCode::Char(c) if c == app.keys.scroll_up => app.preset_menu_move(false),
+                    KeyCode::Down => app.preset_menu_move(true),
+                    KeyCode::Char(c) if c == app.keys.scroll_down => app.preset_menu_move(true),
+                    _ => dirty = false,
+                }
+            } else if app.search_mode {
+                match key {
+                    KeyEvent {
+                        code: KeyCode::Esc, ..
+                    } => {
+                        if app.has_search() {
+                            app.clear_search();
+                        } else {
                             app.exit_search(false);
                         }
-                        KeyEvent {
-                            code: KeyCode::Backspace,
-                            ..
-                        } => {
-                            app.search_pop();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('l'),
-                            modifiers: KeyModifiers::CONTROL,
-                            ..
-                        } => {
-                            app.clear_buffer();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Up, ..
-                        } => {
-                            app.scroll_up();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Down,
-                            ..
-                        } => {
-                            app.scroll_down();
-                        }
-                        KeyEvent {
-                            code: KeyCode::PageUp,
-                            ..
-                        }
-                        | KeyEvent {
-                            code: KeyCode::Char('u'),
-                            modifiers: KeyModifiers::CONTROL,
-                            ..
-                        } => {
-                            app.scroll_page_up();
-                        }
-                        KeyEvent {
-                            code: KeyCode::PageDown,
-                            ..
-                        }
-                        | KeyEvent {
-                            code: KeyCode::Char('d'),
-                            modifiers: KeyModifiers::CONTROL,
-                            ..
-                        } => {
-                            app.scroll_page_down();
-                        }
-                        KeyEvent {
-                            code: KeyCode::End, ..
-                        } => {
-                            app.resume_follow();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char(c),
-                            modifiers: KeyModifiers::NONE,
-                            ..
-                        }
-                        | KeyEvent {
-                            code: KeyCode::Char(c),
-                            modifiers: KeyModifiers::SHIFT,
-                            ..
-                        } => {
-                            app.search_push(c);
-                        }
-                        _ => {
-                            dirty = false;
-                        }
                     }
-                } else {
-                    match key {
-                        KeyEvent {
-                            code: KeyCode::Char('q'),
-                            ..
-                        } => {
-                            if app.quit_pending.map_or(false, |d| Instant::now() < d) {
-                                break;
-                            }
-                            app.quit_pending = Some(Instant::now() + Duration::from_millis(1500));
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('/'),
-                            ..
-                        } => {
-                            app.enter_search();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Esc, ..
-                        } if app.has_search() => {
-                            app.exit_search(true);
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('['),
-                            ..
-                        } => {
-                            app.clear_filters();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char(']'),
-                            ..
-                        } => {
-                            app.all_categories_on();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('1'),
-                            ..
-                        } => {
-                            app.toggle_level(1);
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('2'),
-                            ..
-                        } => {
-                            app.toggle_level(2);
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('3'),
-                            ..
-                        } => {
-                            app.toggle_level(3);
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('4'),
-                            ..
-                        } => {
-                            app.toggle_level(4);
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('5'),
-                            ..
-                        } => {
-                            app.toggle_level(5);
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('6'),
-                            ..
-                        } => {
-                            app.toggle_level(6);
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('0'),
-                            ..
-                        } => {
-                            app.reset_levels();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('-'),
-                            ..
-                        } => {
-                            app.all_levels_off();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('n'),
-                            ..
-                        } => {
-                            app.toggle_navigation();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('g'),
-                            ..
-                        } => {
-                            app.toggle_guidance();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('r'),
-                            ..
-                        } => {
-                            app.toggle_routing();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('m'),
-                            ..
-                        } => {
-                            app.toggle_mapmatching();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('w'),
-                            ..
-                        } => {
-                            let msg = match app.dump_to_file() {
-                                Ok(filename) => format!("  saved to {}", filename),
-                                Err(e) => format!("  save failed: {}", e),
-                            };
-                            app.save_notice =
-                                Some((Instant::now() + Duration::from_millis(3000), msg));
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('?'),
-                            ..
-                        } => {
-                            app.toggle_hint();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Up, ..
-                        }
-                        | KeyEvent {
-                            code: KeyCode::Char('k'),
-                            ..
-                        } => {
-                            app.scroll_up();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Down,
-                            ..
-                        }
-                        | KeyEvent {
-                            code: KeyCode::Char('j'),
-                            ..
-                        } => {
-                            app.scroll_down();
-                        }
-                        KeyEvent {
-                            code: KeyCode::PageUp,
-                            ..
-                        }
-                        | KeyEvent {
-                            code: KeyCode::Char('u'),
-                            modifiers: KeyModifiers::CONTROL,
-                            ..
-                        } => {
-                            app.scroll_page_up();
-                        }
-                        KeyEvent {
-                            code: KeyCode::PageDown,
-                            ..
-                        }
-                        | KeyEvent {
-                            code: KeyCode::Char('d'),
-                            modifiers: KeyModifiers::CONTROL,
-                            ..
-                        } => {
-                            app.scroll_page_down();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('f'),
-                            ..
-                        }
-                        | KeyEvent {
-                            code: KeyCode::End, ..
-                        } => {
-                            app.resume_follow();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char('l'),
-                            modifiers: KeyModifiers::CONTROL,
-                            ..
-                        } => {
-                            app.clear_buffer();
-                        }
-                        _ => {
-                            dirty = false;
+                    KeyEvent {
+                        code: KeyCode::Enter,
+                        ..
+                    } => {
+                        app.exit_search(false);
+                    }
+                    KeyEvent {
+                        code: KeyCode::Backspace,
+                        ..
+                    } => {
+                        app.search_pop();
+                    }
+                    KeyEvent {
+                        code: KeyCode::Char('l'),
+                        modifiers: KeyModifiers::CONTROL,
+                        ..
+                    } => {
+                        app.clear_buffer();
+                    }
+                    KeyEvent {
+                        code: KeyCode::Up, ..
+                    } => {
+                        app.scroll_up();
+                    }
+                    KeyEvent {
+                        code: KeyCode::Down,
+                        ..
+                    } => {
+                        app.scroll_down();
+                    }
+                    KeyEvent {
+                        code: KeyCode::PageUp,
+                        ..
+                    }
+                    | KeyEvent {
+                        code: KeyCode::Char('u'),
+                        modifiers: KeyModifiers::CONTROL,
+                        ..
+                    } => {
+                        app.scroll_page_up();
+                    }
+                    KeyEvent {
+                        code: KeyCode::PageDown,
+                        ..
+                    }
+                    | KeyEvent {
+                        code: KeyCode::Char('d'),
+                        modifiers: KeyModifiers::CONTROL,
+                        ..
+                    } => {
+                        app.scroll_page_down();
+                    }
+                    KeyEvent {
+                        code: KeyCode::End, ..
+                    } => {
+                        app.resume_follow();
+                    }
+                    KeyEvent {
+                        code: KeyCode::Char(c),
+                        modifiers: KeyModifiers::NONE,
+                        ..
+                    }
+                    | KeyEvent {
+                        code: KeyCode::Char(c),
+                        modifiers: KeyModifiers::SHIFT,
+                        ..
+                    } => {
+                        app.search_push(c);
+                    }
+                    _ => {
+                        dirty = false;
+                    }
+                }
+            } else if let Some(action) = app.keys.action(&key) {
+                match action {
+                    Action::Quit => {
+                        if app.quit_pending.is_some_and(|d| Instant::now() < d) {
+                            break;
                         }
+                        app.quit_pending = Some(Instant::now() + Duration::from_millis(1500));
+                    }
+                    Action::Search => app.enter_search(),
+                    Action::Undo => app.undo(),
+                    Action::Presets => app.open_preset_menu(),
+                    Action::SavePreset => app.begin_save_preset(),
+                    Action::CategoriesOff | Action::CategoriesOn => {
+                        app.press_category_prefix(action)
+                    }
+                    Action::Crashes => app.open_crash_index(),
+                    Action::Fold => app.toggle_fold(),
+                    Action::FoldAll => app.toggle_all_folds(),
+                    Action::Original => app.open_original_view(),
+                    Action::Processes => app.toggle_process_column(),
+                    Action::DeviceInfo => app.toggle_device_info(),
+                    Action::DeviceActions => app.open_action_menu(),
+                    Action::LevelsReset => app.reset_levels(),
+                    Action::LevelsOff => app.all_levels_off(),
+                    Action::Navigation => app.toggle_navigation(),
+                    Action::Guidance => app.toggle_guidance(),
+                    Action::Routing => app.toggle_routing(),
+                    Action::MapMatching => app.toggle_mapmatching(),
+                    Action::Kernel => app.toggle_kernel(),
+                    Action::Save => {
+                        let msg = match app.dump_to_file() {
+                            Ok(filename) => format!("  saved to {}", filename),
+                            Err(e) => format!("  save failed: {}", e),
+                        };
+                        app.set_notice(msg);
+                    }
+                    Action::Help => app.toggle_hint(),
+                    Action::ScrollUp => app.scroll_up(),
+                    Action::ScrollDown => app.scroll_down(),
+                    Action::Follow => app.resume_follow(),
+                }
+            } else {
+                match key {
+                    KeyEvent {
+                        code: KeyCode::Char('r'),
+                        modifiers: KeyModifiers::CONTROL,
+                        ..
+                    } => {
+                        app.redo();
+                    }
+                    KeyEvent {
+                        code: KeyCode::Esc, ..
+                    } if app.has_search() => {
+                        app.exit_search(true);
+                    }
+                    KeyEvent {
+                        code: KeyCode::Char(c @ '1'..='6'),
+                        ..
+                    } => {
+                        app.toggle_level(c as u8 - b'0');
+                    }
+                    KeyEvent {
+                        code: KeyCode::Up, ..
+                    } => {
+                        app.scroll_up();
+                    }
+                    KeyEvent {
+                        code: KeyCode::Down,
+                        ..
+                    } => {
+                        app.scroll_down();
+                    }
+                    KeyEvent {
+                        code: KeyCode::PageUp,
+                        ..
+                    }
+                    | KeyEvent {
+                        code: KeyCode::Char('u'),
+                        modifiers: KeyModifiers::CONTROL,
+                        ..
+                    } => {
+                        app.scroll_page_up();
+                    }
+                    KeyEvent {
+                        code: KeyCode::PageDown,
+                        ..
+                    }
+                    | KeyEvent {
+                        code: KeyCode::Char('d'),
+                        modifiers: KeyModifiers::CONTROL,
+                        ..
+                    } => {
+                        app.scroll_page_down();
+                    }
+                    KeyEvent {
+                        code: KeyCode::End, ..
+                    } => {
+                        app.resume_follow();
+                    }
+                    KeyEvent {
+                        code: KeyCode::Char('l'),
+                        modifiers: KeyModifiers::CONTROL,
+                        ..
+                    } => {
+                        app.clear_buffer();
+                    }
+                    _ => {
+                        dirty = false;
                     }
                 }
             }
@@ -715,25 +1842,30 @@ fn run_loop(
 fn render(app: &AppState, frame: &mut ratatui::Frame) {
     let area = frame.area();
 
-    let constraints: Vec<Constraint> = if app.search_mode {
+    let has_input_bar =
+        app.search_mode || app.preset_name_input.is_some() || app.tag_level_input.is_some();
+    let header = Constraint::Length(app.header_rows() as u16);
+    let constraints: Vec<Constraint> = if has_input_bar {
         vec![
+            header,
             Constraint::Min(1),
             Constraint::Length(1),
             Constraint::Length(1),
         ]
     } else {
-        vec![Constraint::Min(1), Constraint::Length(1)]
+        vec![header, Constraint::Min(1), Constraint::Length(1)]
     };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(constraints)
         .split(area);
 
-    let log_area = chunks[0];
-    let (search_area, status_area) = if app.search_mode {
-        (Some(chunks[1]), chunks[2])
+    let header_area = chunks[0];
+    let log_area = chunks[1];
+    let (input_area, status_area) = if has_input_bar {
+        (Some(chunks[2]), chunks[3])
     } else {
-        (None, chunks[1])
+        (None, chunks[2])
     };
     let height = log_area.height as usize;
 
@@ -741,14 +1873,196 @@ fn render(app: &AppState, frame: &mut ratatui::Frame) {
     let scroll_offset = if app.follow {
         display_len.saturating_sub(height)
     } else {
-        app.scroll_offset.min(display_len.saturating_sub(1).max(0))
+        app.scroll_offset.min(display_len.saturating_sub(1))
     };
 
+    if header_area.height > 0 {
+        frame.render_widget(
+            Paragraph::new(build_device_info_lines(app, &app.keys)),
+            header_area,
+        );
+    }
     render_log_list(app, scroll_offset, height, frame, log_area);
-    if let Some(area) = search_area {
-        render_search_bar(app, frame, area);
+    if let Some(area) = input_area {
+        match (&app.preset_name_input, &app.tag_level_input) {
+            (Some(name), _) => {
+                frame.render_widget(Paragraph::new(build_preset_name_line(name)), area)
+            }
+            (None, Some((_, input))) => {
+                frame.render_widget(Paragraph::new(build_tag_level_line(input)), area)
+            }
+            (None, None) => render_search_bar(app, frame, area),
+        }
     }
     render_status_bar(app, display_len, scroll_offset, height, frame, status_area);
+    if let Some(menu) = &app.preset_menu {
+        render_preset_menu(menu, frame, log_area);
+    }
+    if let Some(menu) = &app.crash_menu {
+        render_crash_index(menu, frame, log_area);
+    }
+    if let Some(menu) = &app.action_menu {
+        render_action_menu(app, menu, frame, log_area);
+    }
+    if let Some(view) = &app.original_view {
+        render_original_view(view, frame, log_area);
+    }
+}
+
+fn build_preset_name_line(name: &str) -> Line<'static> {
+    build_prompt_line(" save preset as: ", name, "  esc:cancel  enter:save")
+}
+
+fn build_tag_level_line(input: &str) -> Line<'static> {
+    build_prompt_line(
+        " set log.tag (tag level): ",
+        input,
+        "  e.g. Planner V  esc:cancel  enter:set",
+    )
+}
+
+/// A one-line text prompt: `label`, the typed `text` with a cursor, then `keys`.
+fn build_prompt_line(label: &str, text: &str, keys: &str) -> Line<'static> {
+    let bar_style = Style::default().bg(Color::DarkGray).fg(Color::White);
+    let cursor_style = Style::default().bg(Color::White).fg(Color::DarkGray);
+    Line::from(vec![
+        Span::styled(label.to_owned(), bar_style),
+        Span::styled(text.to_owned(), bar_style),
+        Span::styled("█", cursor_style),
+        Span::styled(
+            keys.to_owned(),
+            Style::default()
+                .bg(Color::DarkGray)
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ])
+}
+
+/// Returns a rect of at most `width` x `height` centered in `area`.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn render_preset_menu(menu: &PresetMenu, frame: &mut ratatui::Frame, area: Rect) {
+    let width = menu.names.iter().map(|n| n.len()).max().unwrap_or(0) as u16 + 8;
+    let popup = centered_rect(width.max(30), menu.names.len() as u16 + 2, area);
+    let items: Vec<ListItem> = menu
+        .names
+        .iter()
+        .map(|name| ListItem::new(format!(" {}", name)))
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" presets — enter:load  esc:close "),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::White)
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut state = ListState::default().with_selected(Some(menu.selected));
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut state);
+}
+
+fn render_action_menu(app: &AppState, menu: &ActionMenu, frame: &mut ratatui::Frame, area: Rect) {
+    let popup = centered_rect(40, DEVICE_ACTION_ITEMS.len() as u16 + 2, area);
+    let title = match app.devices.get(menu.device) {
+        Some(device) if app.devices.len() > 1 => {
+            format!(" {} — enter:run  esc:close ", device.label)
+        }
+        _ => " device — enter:run  esc:close ".to_owned(),
+    };
+    let items: Vec<ListItem> = DEVICE_ACTION_ITEMS
+        .iter()
+        .map(|item| ListItem::new(format!(" {}", item)))
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(
+            Style::default()
+                .bg(Color::White)
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut state = ListState::default().with_selected(Some(menu.selected));
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut state);
+}
+
+fn render_crash_index(menu: &CrashMenu, frame: &mut ratatui::Frame, area: Rect) {
+    let popup = centered_rect(
+        area.width.saturating_sub(8),
+        menu.entries.len() as u16 + 2,
+        area,
+    );
+    let items: Vec<ListItem> = menu
+        .entries
+        .iter()
+        .map(|entry| ListItem::new(crash_index_line(entry)))
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" crashes — enter:jump  esc:close "),
+        )
+        .highlight_style(Style::default().bg(Color::White).fg(Color::DarkGray));
+    let mut state = ListState::default().with_selected(Some(menu.selected));
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut state);
+}
+
+fn render_original_view(view: &OriginalView, frame: &mut ratatui::Frame, area: Rect) {
+    let popup = centered_rect(
+        area.width.saturating_sub(8),
+        view.lines.len() as u16 * 2 + 2,
+        area,
+    );
+    let dim = Style::default().fg(Color::DarkGray);
+    let lines: Vec<Line> = view
+        .lines
+        .iter()
+        .flat_map(|(shown, original)| {
+            [
+                Line::from(format!(" {}", shown)),
+                Line::from(Span::styled(format!("   {}", original), dim)),
+            ]
+        })
+        .collect();
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" retraced — original below each line  esc:close "),
+    );
+    frame.render_widget(Clear, popup);
+    frame.render_widget(paragraph, popup);
+}
+
+fn crash_index_line(entry: &CrashSummary) -> Line<'static> {
+    let dim = Style::default().fg(Color::DarkGray);
+    Line::from(vec![
+        Span::styled(format!(" {} ", entry.timestamp), dim),
+        Span::styled(
+            format!("{} ", entry.exception),
+            Style::default()
+                .fg(entry.kind.color())
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(format!("{} ", entry.top_frame.as_deref().unwrap_or("-"))),
+        Span::styled(entry.process.clone().unwrap_or_default(), dim),
+    ])
 }
 
 fn render_log_list(
@@ -759,7 +2073,7 @@ fn render_log_list(
     area: Rect,
 ) {
     if app.search_result.is_empty() && app.raw_count() == 0 {
-        frame.render_widget(splash(), area);
+        frame.render_widget(splash(&app.keys), area);
         return;
     }
     if app.search_result.is_empty() {
@@ -772,6 +2086,12 @@ fn render_log_list(
     }
 
     let search_q = app.search_query.to_lowercase();
+    let label_width = app
+        .devices
+        .iter()
+        .map(|d| d.label.chars().count())
+        .max()
+        .unwrap_or(0);
     let items: Vec<ListItem> = app
         .search_result
         .iter()
@@ -779,17 +2099,107 @@ fn render_log_list(
         .take(height)
         .map(|&idx| {
             let line = &app.filtered_cache[idx];
-            let spans = if search_q.is_empty() {
+            let mut spans = if search_q.is_empty() {
                 line.spans.clone()
             } else {
                 highlight_search_in_spans(line.spans.clone(), &search_q)
             };
+            if app.show_processes {
+                let label = app.process_label(line.device, &line.content);
+                spans.insert(
+                    0,
+                    Span::styled(
+                        format!(
+                            "{:<width$} ",
+                            fit_left(label.as_deref().unwrap_or(""), PROCESS_COLUMN_WIDTH),
+                            width = PROCESS_COLUMN_WIDTH
+                        ),
+                        Style::default().fg(Color::Gray),
+                    ),
+                );
+            }
+            if app.devices.len() > 1
+                && let Some(device) = app.devices.get(line.device)
+            {
+                spans.insert(
+                    0,
+                    Span::styled(
+                        format!("{:<width$} ", device.label, width = label_width),
+                        Style::default().fg(device.color),
+                    ),
+                );
+            }
             ListItem::new(Line::from(spans))
         })
         .collect();
     frame.render_widget(List::new(items), area);
 }
 
+/// The device info pane: every `key = value` when open, else a summary line of the
+/// model, Android release and app version.
+fn build_device_info_lines(app: &AppState, keys: &KeyBindings) -> Vec<Line<'static>> {
+    let style = Style::default().bg(Color::Black).fg(Color::Gray);
+    let key_style = style.fg(Color::DarkGray);
+    let device_info = app.device_info_rows();
+    if app.device_info_open {
+        return device_info
+            .into_iter()
+            .map(|(key, value)| {
+                Line::from(vec![
+                    Span::styled(format!(" {} = ", key), key_style),
+                    Span::styled(value, style),
+                ])
+            })
+            .collect();
+    }
+    let summary: Vec<String> = device_info
+        .iter()
+        .filter_map(|(key, value)| {
+            if key.ends_with("ro.product.model") {
+                Some(value.clone())
+            } else if key.ends_with("ro.build.version.release") {
+                Some(format!("Android {}", value))
+            } else {
+                key.strip_suffix(" version")
+                    .map(|package| format!("{} {}", package, value))
+            }
+        })
+        .collect();
+    vec![Line::from(vec![
+        Span::styled(format!(" {}", summary.join(" · ")), style),
+        Span::styled(format!("  {}:details", keys.device_info), key_style),
+    ])]
+}
+
+/// Every device's info in one list, keys prefixed by the device label when there
+/// are several devices.
+pub fn labeled_device_info(
+    labels: &[String],
+    info: &[Vec<(String, String)>],
+) -> Vec<(String, String)> {
+    let prefixed = info.len() > 1;
+    info.iter()
+        .enumerate()
+        .flat_map(|(device, info)| {
+            info.iter()
+                .map(move |(key, value)| match labels.get(device) {
+                    Some(label) if prefixed => (format!("{} {}", label, key), value.clone()),
+                    _ => (key.clone(), value.clone()),
+                })
+        })
+        .collect()
+}
+
+/// `text` cut to `width` characters from the left, marked with `…`.
+fn fit_left(text: &str, width: usize) -> String {
+    let len = text.chars().count();
+    if len <= width {
+        return text.to_owned();
+    }
+    let tail: String = text.chars().skip(len - width + 1).collect();
+    format!("…{}", tail)
+}
+
 fn build_search_bar_line(query: &str) -> Line<'static> {
     let bar_style = Style::default().bg(Color::DarkGray).fg(Color::White);
     let cursor_style = Style::default().bg(Color::White).fg(Color::DarkGray);
@@ -808,7 +2218,10 @@ fn build_search_bar_line(query: &str) -> Line<'static> {
 }
 
 fn render_search_bar(app: &AppState, frame: &mut ratatui::Frame, area: Rect) {
-    frame.render_widget(Paragraph::new(build_search_bar_line(&app.search_query)), area);
+    frame.render_widget(
+        Paragraph::new(build_search_bar_line(&app.search_query)),
+        area,
+    );
 }
 
 fn category_toggle_style(app: &AppState, on: bool, key: char) -> Style {
@@ -827,9 +2240,53 @@ fn category_toggle_style(app: &AppState, on: bool, key: char) -> Style {
             .fg(Color::Red)
             .add_modifier(Modifier::BOLD),
         'm' => Style::default().bg(Color::DarkGray).fg(Color::Yellow),
+        'K' => Style::default().bg(Color::DarkGray).fg(Color::LightBlue),
         _ => Style::default().bg(Color::DarkGray).fg(Color::White),
     };
-    if on { style } else { style.add_modifier(Modifier::DIM) }
+    if on {
+        style
+    } else {
+        style.add_modifier(Modifier::DIM)
+    }
+}
+
+fn category_label(key: char, on: bool) -> String {
+    format!("{}:{}", key, if on { "on " } else { "off" })
+}
+
+fn key_hint(k: &KeyBindings) -> String {
+    format!(
+        "  {}/{}/{}/{}:cat  {}:kernel  {}:cat off  {}:cat on  1-6:lvl  {}:lvl reset  {}:lvl off  {}/^r:undo/redo  {}/{}:preset load/save  {}:crashes  {}c/{}c:next/prev crash  {}/{}:fold frames/all  {}:original  {}:proc names  {}:device info  {}:device actions  {}:save  {}:search  ↑↓{}{}:scroll  PgUp/Dn ^u/d:page  {}:follow  ^l:clear  {}{}:quit  {}:hide",
+        k.navigation,
+        k.guidance,
+        k.routing,
+        k.mapmatching,
+        k.kernel,
+        k.categories_off,
+        k.categories_on,
+        k.levels_reset,
+        k.levels_off,
+        k.undo,
+        k.presets,
+        k.save_preset,
+        k.crashes,
+        k.categories_on,
+        k.categories_off,
+        k.fold,
+        k.fold_all,
+        k.original,
+        k.processes,
+        k.device_info,
+        k.device_actions,
+        k.save,
+        k.search,
+        k.scroll_down,
+        k.scroll_up,
+        k.follow,
+        k.quit,
+        k.quit,
+        k.help,
+    )
 }
 
 fn build_status_line(
@@ -861,20 +2318,44 @@ fn build_status_line(
         String::new()
     };
 
-    let quit_confirming = app.quit_pending.map_or(false, |d| Instant::now() < d);
+    let quit_confirming = app.quit_pending.is_some_and(|d| Instant::now() < d);
     let save_msg = app
         .save_notice
         .as_ref()
         .filter(|(d, _)| Instant::now() < *d)
         .map(|(_, msg)| msg.as_str());
-    let hint: String = if !app.adb_connected {
-        "  adb disconnected — reconnecting...".to_owned()
+    let away: Vec<&DeviceStatus> = app.devices.iter().filter(|d| !d.connected).collect();
+    let hint: String = if app.devices.len() > 1 && !away.is_empty() {
+        let names: Vec<String> = away
+            .iter()
+            .map(|d| format!("{} {}", d.label, d.away_reason()))
+            .collect();
+        format!("  {} — reconnecting...", names.join(", "))
+    } else if let Some(device) = away.first() {
+        match device.state {
+            Some(DeviceState::Unplugged) => {
+                "  device unplugged — reconnecting when it returns...".to_owned()
+            }
+            Some(DeviceState::Unauthorized) => {
+                "  device unauthorized — accept the USB debugging prompt".to_owned()
+            }
+            Some(DeviceState::Rebooting) => {
+                "  device rebooting — reconnecting when it is back...".to_owned()
+            }
+            Some(DeviceState::Unknown) => {
+                "  adb server lost — reconnecting when it is back...".to_owned()
+            }
+            _ if app.source_capabilities.can_reconnect => {
+                format!("  {} disconnected — reconnecting...", app.source)
+            }
+            _ => format!("  {} disconnected", app.source),
+        }
     } else if let Some(msg) = save_msg {
         msg.to_owned()
     } else if quit_confirming {
-        "  press q again to quit".to_owned()
+        format!("  press {} again to quit", app.keys.quit)
     } else if app.show_hint {
-        "  n/g/r/m:cat  [:cat off  ]:cat on  1-6:lvl  0:lvl reset  -:lvl off  w:save  /:search  ↑↓jk:scroll  PgUp/Dn ^u/d:page  f:follow  ^l:clear  qq:quit  ?:hide".to_owned()
+        key_hint(&app.keys)
     } else {
         "  ?".to_owned()
     };
@@ -886,27 +2367,36 @@ fn build_status_line(
         .add_modifier(Modifier::BOLD);
     let ls = &app.filter_state.level_state;
 
-    Line::from(vec![
+    let mut spans = vec![
         Span::styled(" [", base_style),
         Span::styled(
-            if app.filter_state.navigation { "n:on " } else { "n:off" },
+            category_label(app.keys.navigation, app.filter_state.navigation),
             category_toggle_style(app, app.filter_state.navigation, 'n'),
         ),
         Span::styled(" ", base_style),
         Span::styled(
-            if app.filter_state.guidance { "g:on " } else { "g:off" },
+            category_label(app.keys.guidance, app.filter_state.guidance),
             category_toggle_style(app, app.filter_state.guidance, 'g'),
         ),
         Span::styled(" ", base_style),
         Span::styled(
-            if app.filter_state.routing { "r:on " } else { "r:off" },
+            category_label(app.keys.routing, app.filter_state.routing),
             category_toggle_style(app, app.filter_state.routing, 'r'),
         ),
         Span::styled(" ", base_style),
         Span::styled(
-            if app.filter_state.mapmatching { "m:on " } else { "m:off" },
+            category_label(app.keys.mapmatching, app.filter_state.mapmatching),
             category_toggle_style(app, app.filter_state.mapmatching, 'm'),
         ),
+    ];
+    if app.source_capabilities.kernel {
+        spans.push(Span::styled(" ", base_style));
+        spans.push(Span::styled(
+            category_label(app.keys.kernel, app.filter_state.kernel),
+            category_toggle_style(app, app.filter_state.kernel, 'K'),
+        ));
+    }
+    spans.extend([
         Span::styled("] [", base_style),
         Span::styled("V", if ls.verbose { level_on } else { dim_style }),
         Span::styled("D", if ls.debug { level_on } else { dim_style }),
@@ -925,28 +2415,50 @@ fn build_status_line(
             ),
             base_style,
         ),
-        Span::styled(
-            hint,
-            if !app.adb_connected {
-                Style::default()
-                    .bg(Color::Red)
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD)
-            } else if quit_confirming {
-                Style::default()
-                    .bg(Color::Red)
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD)
-            } else if save_msg.is_some() {
-                Style::default()
-                    .bg(Color::Green)
-                    .fg(Color::Black)
-                    .add_modifier(Modifier::BOLD)
+    ]);
+    if !app.devices.is_empty() {
+        spans.push(Span::styled(
+            format!(" │ {}/s", app.line_rate.per_sec),
+            base_style,
+        ));
+        if app.dropped_lines > 0 {
+            spans.push(Span::styled(
+                format!(" {} dropped", app.dropped_lines),
+                base_style.fg(Color::LightRed).add_modifier(Modifier::BOLD),
+            ));
+        }
+    }
+    if app.devices.len() > 1 {
+        spans.push(Span::styled(" │", base_style));
+        for device in &app.devices {
+            let (mark, style) = if device.connected {
+                ("●".to_owned(), base_style.fg(device.color))
             } else {
-                base_style
-            },
-        ),
-    ])
+                (
+                    format!("○ {}", device.away_reason()),
+                    base_style.fg(Color::Black),
+                )
+            };
+            spans.push(Span::styled(format!(" {}{}", device.label, mark), style));
+        }
+    }
+    spans.push(Span::styled(
+        hint,
+        if !app.adb_connected() || quit_confirming {
+            Style::default()
+                .bg(Color::Red)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else if save_msg.is_some() {
+            Style::default()
+                .bg(Color::Green)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            base_style
+        },
+    ));
+    Line::from(spans)
 }
 
 fn render_status_bar(
@@ -1001,7 +2513,7 @@ fn highlight_search_in_spans(spans: Vec<Span<'static>>, query: &str) -> Vec<Span
     result
 }
 
-fn splash() -> Paragraph<'static> {
+fn splash(keys: &KeyBindings) -> Paragraph<'static> {
     let red = Style::default().fg(Color::Red);
     let bold_white = Style::default()
         .fg(Color::White)
@@ -1025,89 +2537,337 @@ fn splash() -> Paragraph<'static> {
         Line::from(vec![Span::styled(" > ^ <", red)]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("  n", key),
+            Span::styled(format!("  {}", keys.navigation), key),
             Span::styled("  navigation  ", dim),
-            Span::styled("g", key),
+            Span::styled(keys.guidance.to_string(), key),
             Span::styled("  guidance   ", dim),
-            Span::styled("r", key),
+            Span::styled(keys.routing.to_string(), key),
             Span::styled("  routing    ", dim),
-            Span::styled("m", key),
+            Span::styled(keys.mapmatching.to_string(), key),
             Span::styled("  map-matching", dim),
         ]),
         Line::from(vec![
             Span::styled("  ↑↓", key),
             Span::styled(" scroll   ", dim),
-            Span::styled("f", key),
+            Span::styled(keys.follow.to_string(), key),
             Span::styled("  follow  ", dim),
-            Span::styled("[", key),
+            Span::styled(keys.categories_off.to_string(), key),
             Span::styled("  cat off  ", dim),
-            Span::styled("]", key),
+            Span::styled(keys.categories_on.to_string(), key),
             Span::styled("  cat on", dim),
         ]),
         Line::from(vec![
             Span::styled("  PgUp/Dn", key),
             Span::styled(" page     ", dim),
-            Span::styled("/", key),
+            Span::styled(keys.search.to_string(), key),
             Span::styled("  search  ", dim),
             Span::styled("1-6", key),
             Span::styled("  lvl toggle  ", dim),
-            Span::styled("0", key),
+            Span::styled(keys.levels_reset.to_string(), key),
             Span::styled("  lvl reset  ", dim),
-            Span::styled("-", key),
+            Span::styled(keys.levels_off.to_string(), key),
             Span::styled("  lvl off", dim),
         ]),
         Line::from(vec![
             Span::styled("  ^l", key),
             Span::styled(" clear    ", dim),
-            Span::styled("q", key),
+            Span::styled(keys.quit.to_string(), key),
             Span::styled("  quit    ", dim),
-            Span::styled("?", key),
+            Span::styled(keys.help.to_string(), key),
             Span::styled("  help", dim),
         ]),
         Line::from(""),
         Line::from(vec![Span::styled("  waiting for logs...", dim)]),
     ]);
 
-    Paragraph::new(text).alignment(Alignment::Left)
-}
+    Paragraph::new(text).alignment(Alignment::Left)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::cli::Args;
+    use crate::domain::crash_capture::CaptureWindow;
+    use crate::domain::filter::LineRole;
+
+    fn app_no_tag_filter() -> AppState {
+        let args = Args {
+            logcat_levels: Some("VDIWEF".to_string()),
+            tags: Some(String::new()),
+            no_tag_filter: true,
+            ..Args::default()
+        };
+        AppState::new(FilterState::from_args(&args))
+    }
+
+    fn app_with_show_item(show_item: &str) -> AppState {
+        let args = Args {
+            show_items: vec![show_item.to_string()],
+            ..app_args("SomeTag")
+        };
+        AppState::new(FilterState::from_args(&args))
+    }
+
+    fn app_args(tags: &str) -> Args {
+        Args {
+            logcat_levels: Some("I".to_string()),
+            tags: Some(tags.to_string()),
+            ..Args::default()
+        }
+    }
+
+    fn app_with_crash_levels() -> AppState {
+        let args = Args {
+            logcat_levels: Some("I,E,F".to_string()),
+            show_items: vec![],
+            ..app_args("SomeTag")
+        };
+        AppState::new(FilterState::from_args(&args))
+    }
+
+    #[test]
+    fn each_crash_kind_gets_its_own_separator() {
+        let mut app = app_with_crash_levels();
+        for line in [
+            "2024-01-15 10:30:45 900 950 E ActivityManager: ANR in com.example.nav",
+            "2024-01-15 10:30:45 900 950 E ActivityManager: Reason: Input dispatching timed out",
+            "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: FATAL EXCEPTION: main",
+            "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: java.lang.IllegalStateException: boom",
+            "2024-01-15 10:30:47 1234 1234 I SomeTag: recovered",
+            "2024-01-15 10:30:48 1234 1260 F libc    : Fatal signal 11 (SIGSEGV)",
+        ] {
+            app.push_line(line.to_string());
+        }
+        let separators: Vec<&str> = app
+            .filtered_lines()
+            .iter()
+            .map(|l| l.content.as_str())
+            .filter(|c| c.starts_with("───"))
+            .collect();
+        assert_eq!(separators.len(), 3);
+        assert!(separators[0].starts_with("─── ANR "));
+        assert!(separators[1].starts_with("─── crash "));
+        assert!(separators[2].starts_with("─── native crash "));
+        assert_eq!(app.filtered_lines().len(), 9);
+
+        let pushed: Vec<String> = app
+            .filtered_lines()
+            .iter()
+            .map(|l| l.content.clone())
+            .collect();
+        app.rebuild_filtered_cache();
+        let rebuilt: Vec<String> = app
+            .filtered_lines()
+            .iter()
+            .map(|l| l.content.clone())
+            .collect();
+        assert_eq!(pushed, rebuilt);
+    }
+
+    fn app_with_two_crashes() -> AppState {
+        let mut app = app_with_crash_levels();
+        app.visible_height = 2;
+        for i in 0..5 {
+            app.push_line(format!(
+                "2024-01-15 10:30:4{} 1 1 I SomeTag: before {}",
+                i, i
+            ));
+        }
+        app.push_line(
+            "2024-01-15 10:30:50 900 950 E ActivityManager: ANR in com.example.nav".into(),
+        );
+        for i in 0..5 {
+            app.push_line(format!(
+                "2024-01-15 10:30:5{} 1 1 I SomeTag: between {}",
+                i, i
+            ));
+        }
+        app.push_line(
+            "2024-01-15 10:31:00 1234 1234 E AndroidRuntime: FATAL EXCEPTION: main".into(),
+        );
+        app.push_line("2024-01-15 10:31:01 1 1 I SomeTag: after".into());
+        app
+    }
+
+    #[test]
+    fn original_view_lists_retraced_lines_in_view() {
+        let mut app = app_with_crash_levels();
+        app.open_original_view();
+        assert!(app.original_view.is_none());
+
+        app.set_retracer(Some(Arc::new(Retracer::parse(
+            "com.tomtom.nav.Router -> a.b:\n    1:3:void plan(int):40:42 -> c\n",
+        ))));
+        let frame = "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: \tat a.b.c(SourceFile:2)";
+        for line in [
+            "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: FATAL EXCEPTION: main",
+            frame,
+        ] {
+            app.push_line(line.to_string());
+        }
+
+        app.open_original_view();
+        let view = app.original_view.as_ref().unwrap();
+        assert_eq!(view.lines.len(), 1);
+        assert!(
+            view.lines[0]
+                .0
+                .contains("com.tomtom.nav.Router.plan(Router.java:41)")
+        );
+        assert_eq!(view.lines[0].1, frame);
+    }
+
+    #[test]
+    fn crash_index_lists_crashes_and_jumps_to_selected() {
+        let mut app = app_with_two_crashes();
+
+        app.open_crash_index();
+        let menu = app.crash_menu.as_ref().unwrap();
+        assert_eq!(menu.entries.len(), 2);
+        assert_eq!(menu.selected, 1);
+
+        app.crash_menu_move(false);
+        app.jump_to_selected_crash();
+
+        assert!(app.crash_menu.is_none());
+        assert!(!app.follow);
+        let top = &app.filtered_cache[app.search_result[app.scroll_offset]];
+        assert!(top.is_crash_start());
+        assert_eq!(top.crash, Some(CrashKind::Anr));
+    }
+
+    #[test]
+    fn crash_index_without_crashes_shows_notice() {
+        let mut app = app_with_crash_levels();
+        app.push_line("2024-01-15 10:30:40 1 1 I SomeTag: calm".into());
+        app.open_crash_index();
+        assert!(app.crash_menu.is_none());
+        assert!(app.save_notice.is_some());
+    }
+
+    #[test]
+    fn jump_to_crash_moves_between_separators() {
+        let mut app = app_with_two_crashes();
+
+        app.jump_to_crash(false);
+        assert_eq!(app.top_cache_line(), Some(12));
+
+        app.jump_to_crash(false);
+        assert_eq!(app.top_cache_line(), Some(5));
+
+        app.jump_to_crash(false);
+        assert_eq!(app.top_cache_line(), Some(5));
+        assert!(app.save_notice.is_some());
+
+        app.jump_to_crash(true);
+        assert_eq!(app.top_cache_line(), Some(12));
+    }
+
+    fn plain_key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::application::cli::{Args, VerbosityLevel};
+    #[test]
+    fn category_key_waits_out_its_window_before_changing_filters() {
+        let mut app = app_with_two_crashes();
+        let before = app.filter_state.clone();
+
+        app.press_category_prefix(Action::CategoriesOff);
+        assert_eq!(app.filter_state, before);
+        assert!(!app.expire_prefix(Instant::now()));
+        assert_eq!(app.filter_state, before);
+
+        assert!(app.expire_prefix(Instant::now() + Duration::from_millis(PREFIX_MS)));
+        assert!(!app.filter_state.routing && !app.filter_state.navigation);
+        assert!(app.pending_prefix.is_none());
+    }
 
-    fn app_no_tag_filter() -> AppState {
-        let args = Args {
-            file: None,
-            logcat_levels: "VDIWEF".to_string(),
-            tags: String::new(),
-            add_tag: vec![],
-            no_tag_filter: true,
-            serial: None,
-            debug_level: VerbosityLevel::None,
-            highlighted_items: vec![],
-            show_items: vec![],
-            completions: None,
-            version: false,
-        };
-        AppState::new(FilterState::from_args(&args))
+    #[test]
+    fn c_after_a_category_key_jumps_without_touching_filters() {
+        let mut app = app_with_two_crashes();
+        let before = app.filter_state.clone();
+
+        app.press_category_prefix(Action::CategoriesOff);
+        assert!(app.resolve_prefix(&plain_key('c'), Instant::now()));
+
+        assert_eq!(app.filter_state, before);
+        assert_eq!(app.top_cache_line(), Some(12));
+        assert!(app.pending_prefix.is_none());
+        app.undo();
+        assert_eq!(app.save_notice.as_ref().unwrap().1, "  nothing to undo");
     }
 
-    fn app_with_show_item(show_item: &str) -> AppState {
-        let args = Args {
-            file: None,
-            logcat_levels: "I".to_string(),
-            tags: "SomeTag".to_string(),
-            add_tag: vec![],
-            no_tag_filter: false,
-            serial: None,
-            debug_level: VerbosityLevel::None,
-            highlighted_items: vec![],
-            show_items: vec![show_item.to_string()],
-            completions: None,
-            version: false,
-        };
-        AppState::new(FilterState::from_args(&args))
+    #[test]
+    fn crash_jump_after_a_category_key_follows_the_crashes_binding() {
+        let mut app = app_with_two_crashes();
+        app.keys.crashes = 'x';
+        app.press_category_prefix(Action::CategoriesOff);
+        assert!(!app.resolve_prefix(&plain_key('c'), Instant::now()));
+        assert!(!app.filter_state.routing);
+
+        app.press_category_prefix(Action::CategoriesOn);
+        assert!(app.resolve_prefix(&plain_key('x'), Instant::now()));
+        assert!(!app.filter_state.routing);
+    }
+
+    #[test]
+    fn other_key_after_a_category_key_runs_it_first() {
+        let mut app = app_with_two_crashes();
+        app.press_category_prefix(Action::CategoriesOff);
+        assert!(!app.resolve_prefix(&plain_key('j'), Instant::now()));
+        assert!(!app.filter_state.routing && !app.filter_state.navigation);
+
+        app.press_category_prefix(Action::CategoriesOn);
+        let late = Instant::now() + Duration::from_millis(PREFIX_MS);
+        assert!(!app.resolve_prefix(&plain_key('c'), late));
+        assert!(app.filter_state.routing && app.filter_state.navigation);
+    }
+
+    fn displayed(app: &AppState) -> Vec<String> {
+        app.search_result
+            .iter()
+            .map(|&i| app.filtered_cache[i].content.clone())
+            .collect()
+    }
+
+    #[test]
+    fn framework_frames_fold_and_unfold_for_crash_in_view() {
+        let mut app = app_with_crash_levels();
+        for line in [
+            "2024-01-15 10:31:00 1234 1234 E AndroidRuntime: FATAL EXCEPTION: main",
+            "2024-01-15 10:31:00 1234 1234 E AndroidRuntime: java.lang.IllegalStateException: boom",
+            "2024-01-15 10:31:00 1234 1234 E AndroidRuntime: \tat com.tomtom.nav.Router.plan(Router.kt:42)",
+            "2024-01-15 10:31:00 1234 1234 E AndroidRuntime: \tat android.os.Handler.dispatch(Handler.java:1)",
+            "2024-01-15 10:31:00 1234 1234 E AndroidRuntime: \tat android.os.Looper.loop(Looper.java:2)",
+            "2024-01-15 10:31:00 1234 1234 E AndroidRuntime: \tat java.lang.Thread.run(Thread.java:3)",
+        ] {
+            app.push_line(line.to_string());
+        }
+
+        let folded = displayed(&app);
+        assert_eq!(folded.len(), 5);
+        assert!(folded[4].contains("… 3 framework frames"));
+
+        app.toggle_fold();
+        let unfolded = displayed(&app);
+        assert_eq!(unfolded.len(), 7);
+        assert!(unfolded[6].contains("java.lang.Thread.run"));
+
+        app.rebuild_filter();
+        assert_eq!(displayed(&app), unfolded);
+
+        app.toggle_all_folds();
+        app.toggle_all_folds();
+        assert_eq!(displayed(&app), folded);
+    }
+
+    #[test]
+    fn pending_category_prefix_runs_its_action() {
+        let mut app = app_with_crash_levels();
+        app.run_prefix_action(Action::CategoriesOff);
+        assert!(!app.filter_state.routing && !app.filter_state.navigation);
+        app.run_prefix_action(Action::CategoriesOn);
+        assert!(app.filter_state.routing && app.filter_state.navigation);
     }
 
     #[test]
@@ -1120,6 +2880,64 @@ mod tests {
         assert!(app.filtered_lines().is_empty());
     }
 
+    #[test]
+    fn crash_capture_saves_file_and_reports_it() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut app = app_with_crash_levels();
+        app.crash_capture = Some(CrashCapture::new(
+            dir.path().to_path_buf(),
+            CaptureWindow::Lines(1),
+            vec![],
+        ));
+        for line in [
+            "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: FATAL EXCEPTION: main",
+            "2024-01-15 10:30:47 1234 1234 I SomeTag: recovered",
+        ] {
+            app.push_line(line.to_string());
+        }
+
+        let (_, notice) = app.save_notice.as_ref().unwrap();
+        assert!(notice.starts_with("  crash saved to "));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn stack_traces_follow_their_header_through_filter_and_search() {
+        let mut app = AppState::new(FilterState::from_args(&app_args("SomeTag")));
+        for line in [
+            "2024-01-15 10:30:45 1234 5678 I OtherTag: request failed",
+            "2024-01-15 10:30:45 1234 5678 I OtherTag: \tat com.other.Client.send(Client.java:5)",
+            "2024-01-15 10:30:46 1234 5679 I SomeTag: retry failed",
+            "    at com.tomtom.nav.Planner.plan(Planner.kt:9)",
+        ] {
+            app.push_line(line.to_string());
+        }
+        assert_eq!(app.filtered_lines().len(), 2);
+        assert!(
+            !app.filtered_lines()
+                .iter()
+                .any(|l| l.content.contains("Client"))
+        );
+
+        app.search_push('P');
+        app.search_push('l');
+        assert_eq!(app.search_result.len(), 2);
+
+        app.clear_search();
+        for c in "y.z".chars() {
+            app.search_push(c);
+        }
+        app.push_line("2024-01-15 10:30:47 1234 5679 I SomeTag: next".to_string());
+        app.push_line("    at com.tomtom.x.Y.z(Y.kt:1)".to_string());
+        let shown: Vec<&str> = app
+            .search_result
+            .iter()
+            .map(|&i| app.filtered_cache[i].content.as_str())
+            .collect();
+        assert_eq!(shown.len(), 2);
+        assert!(shown[0].ends_with("SomeTag: next"));
+    }
+
     #[test]
     fn rebuild_filter_recovers_previously_hidden_lines() {
         let mut app = app_with_show_item("match");
@@ -1144,25 +2962,148 @@ mod tests {
     #[test]
     fn disconnect_event_updates_status_without_dropping_buffer() {
         let mut app = app_with_show_item("match");
+        app.set_devices(vec!["device".to_owned()]);
         app.push_line("2024-01-15 10:30:45 1234 5678 I SomeTag: match".to_string());
 
-        let consumed_line = app.apply_logcat_event(LogcatEvent::Disconnected);
+        let consumed_line = app.apply_logcat_event(0, LogcatEvent::Disconnected);
 
         assert!(!consumed_line);
-        assert!(!app.adb_connected);
+        assert!(!app.adb_connected());
         assert_eq!(app.raw_count(), 1);
     }
 
     #[test]
     fn connect_event_restores_status_before_new_lines_arrive() {
         let mut app = app_with_show_item("match");
-        app.adb_connected = false;
+        app.set_devices(vec!["device".to_owned()]);
+        app.apply_logcat_event(0, LogcatEvent::Disconnected);
 
-        let consumed_line = app.apply_logcat_event(LogcatEvent::Connected);
+        let consumed_line = app.apply_logcat_event(0, LogcatEvent::Connected);
 
-        assert!(!consumed_line);
-        assert!(app.adb_connected);
-        assert_eq!(app.raw_count(), 0);
+        assert!(consumed_line);
+        assert!(app.adb_connected());
+        assert_eq!(app.raw_count(), 1);
+        assert!(app.raw_buffer[0].starts_with("─── adb disconnected (gap "));
+    }
+
+    #[test]
+    fn reconnect_marks_the_gap_in_the_buffer_and_display() {
+        let mut app = app_with_show_item("match");
+        app.set_devices(vec!["device".to_owned()]);
+        app.push_line("01-15 10:31:02.481 1234 5678 I SomeTag: match".to_string());
+        app.apply_logcat_event(0, LogcatEvent::Disconnected);
+        app.apply_logcat_event(0, LogcatEvent::Disconnected);
+        app.apply_logcat_event(0, LogcatEvent::Connected);
+        app.apply_logcat_event(0, LogcatEvent::Connected);
+
+        assert_eq!(app.raw_count(), 2);
+        let marker = &app.filtered_cache[1];
+        assert!(
+            marker
+                .content
+                .starts_with("─── adb disconnected 10:31:02 (gap 0.0 s) ───")
+        );
+        assert_eq!(marker.role, LineRole::Separator);
+
+        app.toggle_level(4);
+        app.toggle_level(4);
+        assert_eq!(app.filtered_cache.len(), 2);
+
+        for c in "disconnected".chars() {
+            app.search_push(c);
+        }
+        assert_eq!(app.search_result, vec![1]);
+    }
+
+    #[test]
+    fn disconnect_hint_and_marker_name_the_source() {
+        let mut app = app_with_show_item("match");
+        app.set_devices(vec!["cmd".to_owned()]);
+        app.set_source(
+            "ssh".to_owned(),
+            Capabilities {
+                can_reconnect: true,
+                ..Capabilities::default()
+            },
+        );
+        app.apply_logcat_event(0, LogcatEvent::Disconnected);
+        let text = spans_text(&build_status_line(&app, 0, 0, 10));
+        assert!(text.contains("ssh disconnected — reconnecting"));
+
+        app.apply_logcat_event(0, LogcatEvent::Connected);
+        assert!(app.raw_buffer[0].starts_with("─── ssh disconnected (gap "));
+
+        app.set_source("channel".to_owned(), Capabilities::default());
+        app.apply_logcat_event(0, LogcatEvent::Disconnected);
+        let text = spans_text(&build_status_line(&app, 0, 0, 10));
+        assert!(text.contains("channel disconnected"));
+        assert!(!text.contains("reconnecting"));
+    }
+
+    #[test]
+    fn reconnect_marker_names_the_device_of_several() {
+        let mut app = app_with_show_item("match");
+        app.set_devices(vec!["Pixel 7".to_owned(), "emulator-5554".to_owned()]);
+        app.apply_logcat_event(1, LogcatEvent::Disconnected);
+        app.apply_logcat_event(1, LogcatEvent::Connected);
+
+        assert!(app.raw_buffer[0].starts_with("─── emulator-5554 disconnected (gap "));
+        assert_eq!(app.filtered_cache[0].device, 1);
+    }
+
+    #[test]
+    fn clearing_the_buffer_keeps_lines_with_their_device() {
+        let mut app = app_with_show_item("match");
+        app.set_devices(vec!["Pixel 7".to_owned(), "emulator-5554".to_owned()]);
+        for device in [0, 0, 1] {
+            app.apply_logcat_event(
+                device,
+                LogcatEvent::Line("01-15 10:30:45.123 1234 5678 I SomeTag: match".to_owned()),
+            );
+        }
+        app.clear_buffer();
+        // Its entry went with the clear, so the trace line is judged on its own.
+        app.apply_logcat_event(
+            1,
+            LogcatEvent::Line(
+                "01-15 10:30:45.200 1234 5678 E AndroidRuntime: \tat com.example.Planner.plan(Planner.java:42)"
+                    .to_owned(),
+            ),
+        );
+        assert!(app.filtered_cache.is_empty());
+        assert_eq!(app.newest_entry, None);
+        for _ in 0..2 {
+            app.apply_logcat_event(
+                1,
+                LogcatEvent::Line("01-15 10:30:46.123 4321 8765 I SomeTag: match".to_owned()),
+            );
+        }
+        app.rebuild_filtered_cache();
+
+        let labels: Vec<&str> = app
+            .filtered_cache
+            .iter()
+            .map(|l| app.devices[l.device].label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["emulator-5554", "emulator-5554"]);
+        assert_eq!(app.raw_devices, vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn widening_the_filter_asks_for_a_logcat_restart_once() {
+        let mut app = app_with_show_item("match");
+        app.set_device_filter(Some(DeviceFilter::from_state(
+            &app.filter_state,
+            &app.rules.categories,
+        )));
+
+        app.toggle_level(4);
+        assert_eq!(app.take_pending_filterspecs(), None);
+
+        app.toggle_level(1);
+        let specs = app.take_pending_filterspecs().unwrap();
+        assert!(specs.contains(&"SomeTag:V".to_owned()));
+        assert_eq!(app.take_pending_filterspecs(), None);
     }
 
     fn spans_text(line: &Line) -> String {
@@ -1185,6 +3126,24 @@ mod tests {
         assert!(text.contains("█"));
     }
 
+    #[test]
+    fn status_line_shows_kernel_toggle_only_with_a_kernel_stream() {
+        let mut app = app_no_tag_filter();
+        assert!(!spans_text(&build_status_line(&app, 0, 0, 10)).contains("K:"));
+
+        app.set_source(
+            "adb".to_owned(),
+            Capabilities {
+                kernel: true,
+                ..Capabilities::default()
+            },
+        );
+        assert!(spans_text(&build_status_line(&app, 0, 0, 10)).contains("K:on"));
+        app.toggle_kernel();
+        assert!(spans_text(&build_status_line(&app, 0, 0, 10)).contains("K:off"));
+        assert!(!app.filter_state.kernel);
+    }
+
     #[test]
     fn status_line_shows_follow_by_default() {
         let app = app_no_tag_filter();
@@ -1207,11 +3166,85 @@ mod tests {
     #[test]
     fn status_line_shows_disconnected_hint_when_adb_disconnected() {
         let mut app = app_no_tag_filter();
-        app.adb_connected = false;
+        app.set_devices(vec!["device".to_owned()]);
+        app.apply_logcat_event(0, LogcatEvent::Disconnected);
         let line = build_status_line(&app, 0, 0, 10);
         assert!(spans_text(&line).contains("adb disconnected"));
     }
 
+    #[test]
+    fn status_line_shows_tracked_device_state() {
+        let mut app = app_no_tag_filter();
+        app.set_devices(vec!["device".to_owned()]);
+        app.apply_logcat_event(0, LogcatEvent::State(DeviceState::Online));
+        app.apply_logcat_event(0, LogcatEvent::Disconnected);
+        app.apply_logcat_event(0, LogcatEvent::State(DeviceState::Unplugged));
+        let text = spans_text(&build_status_line(&app, 0, 0, 10));
+        assert!(text.contains("device unplugged"));
+
+        app.apply_logcat_event(0, LogcatEvent::State(DeviceState::Rebooting));
+        let text = spans_text(&build_status_line(&app, 0, 0, 10));
+        assert!(text.contains("device rebooting"));
+
+        app.apply_logcat_event(0, LogcatEvent::State(DeviceState::Unknown));
+        let text = spans_text(&build_status_line(&app, 0, 0, 10));
+        assert!(text.contains("adb server lost"));
+
+        app.apply_logcat_event(0, LogcatEvent::State(DeviceState::Online));
+        app.apply_logcat_event(0, LogcatEvent::Connected);
+        assert!(app.adb_connected());
+    }
+
+    #[test]
+    fn status_line_names_the_disconnected_device_of_several() {
+        let mut app = app_with_show_item("match");
+        app.set_devices(vec!["Pixel 7".to_owned(), "emulator-5554".to_owned()]);
+        app.apply_logcat_event(
+            1,
+            LogcatEvent::Line("2024-01-15 10:30:45 1234 5678 I SomeTag: match".to_owned()),
+        );
+        app.apply_logcat_event(1, LogcatEvent::Disconnected);
+
+        let text = spans_text(&build_status_line(&app, 0, 0, 10));
+        assert!(text.contains("Pixel 7●"));
+        assert!(text.contains("emulator-5554 disconnected"));
+
+        app.apply_logcat_event(1, LogcatEvent::State(DeviceState::Unauthorized));
+        let text = spans_text(&build_status_line(&app, 0, 0, 10));
+        assert!(text.contains("emulator-5554 unauthorized — reconnecting"));
+        assert!(!app.adb_connected());
+        assert_eq!(app.filtered_cache[0].device, 1);
+    }
+
+    #[test]
+    fn status_line_shows_line_rate_and_dropped_lines() {
+        let mut app = app_no_tag_filter();
+        app.set_devices(vec!["device".to_owned()]);
+        let start = app.line_rate.since;
+        for _ in 0..30 {
+            app.apply_logcat_event(
+                0,
+                LogcatEvent::Line("2024-01-15 10:30:45 1234 5678 I SomeTag: match".to_owned()),
+            );
+        }
+        assert!(!app.update_ingest(start + Duration::from_millis(500), 0));
+        assert!(app.update_ingest(start + Duration::from_secs(2), 0));
+        let text = spans_text(&build_status_line(&app, 0, 0, 10));
+        assert!(text.contains("│ 15/s"));
+        assert!(!text.contains("dropped"));
+
+        assert!(app.update_ingest(start + Duration::from_millis(2500), 120));
+        let text = spans_text(&build_status_line(&app, 0, 0, 10));
+        assert!(text.contains("120 dropped"));
+    }
+
+    #[test]
+    fn status_line_has_no_line_rate_for_a_file() {
+        let app = app_no_tag_filter();
+        let text = spans_text(&build_status_line(&app, 0, 0, 10));
+        assert!(!text.contains("/s"));
+    }
+
     #[test]
     fn status_line_scroll_position_shows_percentage() {
         let mut app = app_no_tag_filter();
@@ -1250,6 +3283,394 @@ mod tests {
         assert_eq!(app.scroll_offset, offset_before);
     }
 
+    #[test]
+    fn undo_restores_category_toggle_and_redo_reapplies_it() {
+        let mut app = app_no_tag_filter();
+
+        app.toggle_routing();
+        assert!(!app.filter_state.routing);
+
+        app.undo();
+        assert!(app.filter_state.routing);
+        assert!(app.save_notice.as_ref().unwrap().1.contains("r:on"));
+
+        app.redo();
+        assert!(!app.filter_state.routing);
+    }
+
+    #[test]
+    fn undo_restores_levels_after_all_levels_off() {
+        let mut app = app_no_tag_filter();
+        let before = app.filter_state.level_state.clone();
+
+        app.all_levels_off();
+        app.undo();
+
+        assert_eq!(app.filter_state.level_state, before);
+    }
+
+    #[test]
+    fn search_session_is_one_undo_step() {
+        let mut app = app_with_show_item("tag");
+        app.push_line("2024-01-15 10:30:45 1234 5678 I SomeTag: timeout".to_string());
+        app.push_line("2024-01-15 10:30:46 1234 5678 I SomeTag: other".to_string());
+
+        app.enter_search();
+        for c in "timeout".chars() {
+            app.search_push(c);
+        }
+        app.exit_search(false);
+        assert_eq!(app.search_result.len(), 1);
+
+        app.undo();
+
+        assert!(!app.has_search());
+        assert_eq!(app.search_result.len(), 2);
+    }
+
+    #[test]
+    fn unchanged_state_is_not_recorded() {
+        let mut app = app_no_tag_filter();
+
+        app.all_categories_on();
+        app.undo();
+
+        assert_eq!(app.save_notice.as_ref().unwrap().1, "  nothing to undo");
+    }
+
+    #[test]
+    fn saved_preset_can_be_loaded_and_undone() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut app = app_no_tag_filter();
+        app.presets_path = dir.path().join("presets.toml");
+
+        app.toggle_guidance();
+        app.begin_save_preset();
+        for c in "no-guidance".chars() {
+            app.preset_name_push(c);
+        }
+        app.confirm_save_preset();
+        assert!(app.preset_name_input.is_none());
+
+        app.toggle_guidance();
+        assert!(app.filter_state.guidance);
+
+        app.open_preset_menu();
+        assert_eq!(app.preset_menu.as_ref().unwrap().names, vec!["no-guidance"]);
+        app.load_selected_preset();
+        assert!(!app.filter_state.guidance);
+        assert!(app.preset_menu.is_none());
+
+        app.undo();
+        assert!(app.filter_state.guidance);
+    }
+
+    #[test]
+    fn preset_menu_does_not_open_without_presets() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut app = app_no_tag_filter();
+        app.presets_path = dir.path().join("presets.toml");
+
+        app.open_preset_menu();
+
+        assert!(app.preset_menu.is_none());
+        assert!(app.save_notice.as_ref().unwrap().1.contains("no presets"));
+    }
+
+    fn app_with_device_actions() -> AppState {
+        let mut app = app_with_show_item("match");
+        app.set_source(
+            "adb".to_owned(),
+            Capabilities {
+                can_reconnect: true,
+                device_actions: true,
+                ..Capabilities::default()
+            },
+        );
+        app
+    }
+
+    #[test]
+    fn action_menu_acts_on_the_device_of_the_newest_line_in_view() {
+        let mut app = app_with_device_actions();
+        app.set_devices(vec!["Pixel 7".to_owned(), "emulator-5554".to_owned()]);
+        app.push_device_line(
+            0,
+            "2024-01-15 10:30:45 1234 5678 I SomeTag: match".to_owned(),
+        );
+        app.push_device_line(
+            1,
+            "2024-01-15 10:30:46 1234 5678 I SomeTag: match".to_owned(),
+        );
+
+        app.open_action_menu();
+        app.action_menu_move(true);
+        app.select_device_action();
+
+        assert!(app.action_menu.is_none());
+        assert_eq!(
+            app.take_pending_device_action(),
+            Some((1, DeviceAction::Screenshot))
+        );
+        assert!(app.take_pending_device_action().is_none());
+    }
+
+    #[test]
+    fn tag_level_prompt_queues_setprop_with_the_level_word() {
+        let mut app = app_with_device_actions();
+        app.open_action_menu();
+        app.action_menu_move(true);
+        app.action_menu_move(true);
+        app.select_device_action();
+        for c in "Planner v".chars() {
+            app.tag_level_push(c);
+        }
+        app.confirm_tag_level();
+
+        assert!(app.tag_level_input.is_none());
+        assert_eq!(
+            app.take_pending_device_action(),
+            Some((
+                0,
+                DeviceAction::SetTagLevel {
+                    tag: "Planner".to_owned(),
+                    level: "VERBOSE".to_owned()
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn tag_level_prompt_rejects_an_unknown_level() {
+        let mut app = app_with_device_actions();
+        app.tag_level_input = Some((0, "Planner loud".to_owned()));
+        app.confirm_tag_level();
+
+        assert!(app.take_pending_device_action().is_none());
+        assert!(app.save_notice.as_ref().unwrap().1.contains("expected"));
+    }
+
+    #[test]
+    fn action_menu_needs_a_source_with_device_actions() {
+        let mut app = app_with_show_item("match");
+        app.set_source("ssh".to_owned(), Capabilities::default());
+        app.open_action_menu();
+
+        assert!(app.action_menu.is_none());
+        assert_eq!(
+            app.save_notice.as_ref().unwrap().1,
+            "  no device actions for ssh"
+        );
+    }
+
+    #[test]
+    fn device_action_result_lands_in_the_status_bar() {
+        let mut app = app_with_device_actions();
+        app.finish_device_action("screenshot", Ok("screenshot saved to x.png".to_owned()));
+        assert_eq!(
+            app.save_notice.as_ref().unwrap().1,
+            "  screenshot saved to x.png"
+        );
+
+        app.finish_device_action("force-stop", Err("no app package".to_owned()));
+        assert_eq!(
+            app.save_notice.as_ref().unwrap().1,
+            "  force-stop failed: no app package"
+        );
+    }
+
+    fn app_with_process_filter(process: &str) -> AppState {
+        let args = Args {
+            process: vec![process.to_string()],
+            ..app_args("SomeTag")
+        };
+        AppState::new(FilterState::from_args(&args))
+    }
+
+    fn cached_messages(app: &AppState) -> Vec<&str> {
+        app.filtered_cache
+            .iter()
+            .map(|l| l.content.rsplit(": ").next().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn process_filter_shows_lines_once_ps_names_their_process() {
+        let mut app = app_with_process_filter(":Navigation");
+        app.push_line("01-15 10:30:45.123 12345 12360 I SomeTag: route".to_owned());
+        app.push_line("01-15 10:30:45.200 23456 23456 I SomeTag: ui".to_owned());
+        assert!(app.filtered_cache.is_empty());
+
+        app.apply_process_table(
+            0,
+            "  PID   TID NAME                       CMD\n\
+             12345 12360 com.example.nav:navigation Planner\n\
+             23456 23456 com.example.nav            com.example.nav\n",
+        );
+
+        assert_eq!(cached_messages(&app), vec!["route"]);
+        assert_eq!(
+            app.process_label(0, &app.filtered_cache[0].content)
+                .as_deref(),
+            Some("com.example.nav:navigation/Planner")
+        );
+    }
+
+    #[test]
+    fn start_proc_line_names_the_process_for_the_lines_after_it() {
+        let mut app = app_with_process_filter("navigation");
+        app.push_line(
+            "01-15 10:30:45.123  1500  1530 I ActivityManager: Start proc 12345:com.example.nav:navigation/u0a123 for service {com.example.nav/.NavService}"
+                .to_owned(),
+        );
+        app.push_line("01-15 10:30:45.300 12345 12345 I SomeTag: started".to_owned());
+        app.push_line("01-15 10:30:45.400 23456 23456 I SomeTag: other".to_owned());
+
+        assert_eq!(cached_messages(&app), vec!["started"]);
+    }
+
+    #[test]
+    fn devices_are_listed_only_while_process_names_are_used() {
+        assert!(!app_no_tag_filter().wants_process_names());
+        assert!(app_with_process_filter("navigation").wants_process_names());
+
+        let mut app = app_no_tag_filter();
+        app.toggle_process_column();
+        assert!(app.wants_process_names());
+    }
+
+    #[test]
+    fn process_column_toggles_and_keeps_the_end_of_long_names() {
+        let mut app = app_no_tag_filter();
+        app.toggle_process_column();
+        assert!(app.show_processes);
+        assert_eq!(app.save_notice.as_ref().unwrap().1, "  process names: on");
+
+        assert_eq!(fit_left("com.example.nav", 20), "com.example.nav");
+        assert_eq!(fit_left("com.example.nav:navigation", 12), "…:navigation");
+    }
+
+    fn pixel_info() -> Vec<(String, String)> {
+        vec![
+            ("serial".to_owned(), "emulator-5554".to_owned()),
+            ("ro.product.model".to_owned(), "Pixel 7".to_owned()),
+            ("ro.build.version.release".to_owned(), "14".to_owned()),
+            (
+                "com.example.nav version".to_owned(),
+                "4.2.0 (4200)".to_owned(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn dump_starts_with_a_device_comment_header() {
+        let mut app = app_no_tag_filter();
+        app.set_device_info(0, pixel_info());
+        app.push_line("01-15 10:30:45.123 1234 5678 I SomeTag: match".to_owned());
+
+        let mut out = Vec::new();
+        app.write_dump(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "# navcat dump\n\
+             # device: serial = emulator-5554\n\
+             # device: ro.product.model = Pixel 7\n\
+             # device: ro.build.version.release = 14\n\
+             # device: com.example.nav version = 4.2.0 (4200)\n\
+             01-15 10:30:45.123 1234 5678 I SomeTag: match\n"
+        );
+    }
+
+    #[test]
+    fn dump_of_a_file_has_no_header() {
+        let mut app = app_no_tag_filter();
+        app.push_line("01-15 10:30:45.123 1234 5678 I SomeTag: match".to_owned());
+
+        let mut out = Vec::new();
+        app.write_dump(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "01-15 10:30:45.123 1234 5678 I SomeTag: match\n"
+        );
+    }
+
+    #[test]
+    fn device_info_pane_collapses_to_a_summary_line() {
+        let mut app = app_no_tag_filter();
+        assert_eq!(app.header_rows(), 0);
+        app.set_device_info(0, pixel_info());
+        assert_eq!(app.header_rows(), 1);
+        let lines = build_device_info_lines(&app, &app.keys);
+        assert!(
+            spans_text(&lines[0])
+                .starts_with(" Pixel 7 · Android 14 · com.example.nav 4.2.0 (4200)")
+        );
+
+        app.toggle_device_info();
+        assert_eq!(app.header_rows(), 4);
+        let lines = build_device_info_lines(&app, &app.keys);
+        assert_eq!(spans_text(&lines[1]), " ro.product.model = Pixel 7");
+    }
+
+    #[test]
+    fn device_info_toggle_says_when_there_is_none() {
+        let mut app = app_no_tag_filter();
+        app.set_source("trace.txt".to_owned(), Capabilities::default());
+        app.toggle_device_info();
+        assert_eq!(app.header_rows(), 0);
+        assert_eq!(
+            app.save_notice.as_ref().unwrap().1,
+            "  no device info for trace.txt"
+        );
+    }
+
+    #[test]
+    fn device_back_after_a_disconnect_has_its_info_read_again() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut app = app_with_crash_levels();
+        app.set_devices(vec!["Pixel 7".to_owned(), "R58M123ABC".to_owned()]);
+        app.set_device_info(0, pixel_info());
+        let model = |name: &str| vec![("ro.product.model".to_owned(), name.to_owned())];
+        app.set_device_info(1, model("SM-G991B"));
+        app.crash_capture = Some(CrashCapture::new(
+            dir.path().to_path_buf(),
+            CaptureWindow::Lines(1),
+            vec![],
+        ));
+
+        app.apply_logcat_event(1, LogcatEvent::Connected);
+        assert!(app.take_pending_device_info().is_empty());
+        app.apply_logcat_event(1, LogcatEvent::Disconnected);
+        app.apply_logcat_event(1, LogcatEvent::Connected);
+        assert_eq!(app.take_pending_device_info(), vec![1]);
+
+        app.refresh_device_info(1, model("Pixel 8"));
+        app.toggle_device_info();
+        let lines: Vec<String> = build_device_info_lines(&app, &app.keys)
+            .iter()
+            .map(spans_text)
+            .collect();
+        assert!(lines.contains(&" Pixel 7 ro.product.model = Pixel 7".to_owned()));
+        assert!(lines.contains(&" R58M123ABC ro.product.model = Pixel 8".to_owned()));
+
+        for line in [
+            "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: FATAL EXCEPTION: main",
+            "2024-01-15 10:30:47 1234 1234 I SomeTag: recovered",
+        ] {
+            app.apply_logcat_event(1, LogcatEvent::Line(line.to_owned()));
+        }
+        let file = std::fs::read_dir(dir.path())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let text = std::fs::read_to_string(file.path()).unwrap();
+        assert!(text.contains("# device: R58M123ABC ro.product.model = Pixel 8\n"));
+        assert!(!text.contains("SM-G991B"));
+    }
+
     #[test]
     fn search_mode_can_store_letter_f() {
         let mut app = app_with_show_item("match");
@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::domain::crash_index::crash_exception;
+use crate::domain::filter::{CrashKind, LogFilter};
+use crate::domain::filter_config::FilterState;
+use crate::domain::preset::Preset;
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+/// How much raw log is kept on each side of a crash: a number of lines or of seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaptureWindow {
+    Lines(usize),
+    Seconds(f64),
+}
+
+impl FromStr for CaptureWindow {
+    type Err = String;
+
+    /// `200` is 200 lines, `30s` is 30 seconds.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let window = match s.strip_suffix('s') {
+            Some(secs) => secs
+                .parse::<f64>()
+                .ok()
+                .filter(|n| *n > 0.0)
+                .map(Self::Seconds),
+            None => s.parse::<usize>().ok().filter(|n| *n > 0).map(Self::Lines),
+        };
+        window.ok_or_else(|| {
+            format!(
+                "invalid crash window '{}': expected lines (e.g. 200) or seconds (e.g. 30s)",
+                s
+            )
+        })
+    }
+}
+
+/// A crash whose trailing window is still being collected.
+#[derive(Debug, Clone)]
+struct PendingCapture {
+    /// Device the crash came from; only its lines go into the capture.
+    device: usize,
+    kind: CrashKind,
+    /// `date time` of the crash's first line, if it has a logcat header.
+    timestamp: Option<String>,
+    /// Time of day of the crash's first line, in seconds.
+    time: Option<f64>,
+    /// Filters and search at the time of the crash, as preset TOML.
+    filters: String,
+    lines: Vec<String>,
+    /// The crash report lines, to name the file by exception class.
+    report: Vec<String>,
+    in_report: bool,
+    after: usize,
+}
+
+/// Writes the raw log around every crash to a file in `dir`, together with the
+/// filters that were active and the device it came from. With several devices,
+/// each crash is captured from its own device's lines.
+#[derive(Debug, Clone)]
+pub struct CrashCapture {
+    dir: PathBuf,
+    window: CaptureWindow,
+    device: Vec<(String, String)>,
+    pending: Vec<PendingCapture>,
+    /// Crash kind of each device's last line, if it was part of a crash.
+    last_crash: HashMap<usize, CrashKind>,
+}
+
+impl CrashCapture {
+    pub fn new(dir: PathBuf, window: CaptureWindow, device: Vec<(String, String)>) -> Self {
+        Self {
+            dir,
+            window,
+            device,
+            pending: Vec::new(),
+            last_crash: HashMap::new(),
+        }
+    }
+
+    /// Replaces the device header of the captures written from now on, e.g. once
+    /// the device info was read again after a reconnect.
+    pub fn set_device(&mut self, device: Vec<(String, String)>) {
+        self.device = device;
+    }
+
+    /// Feeds the newest raw line, the last of `buffer`, with its crash kind.
+    /// `devices` holds the device index of each line in `buffer`.
+    /// Returns the files of captures whose window closed with this line.
+    pub fn push(
+        &mut self,
+        buffer: &[String],
+        devices: &[usize],
+        crash: Option<CrashKind>,
+        filters: &FilterState,
+        search: &str,
+    ) -> Vec<Result<PathBuf, Box<dyn Error>>> {
+        let (Some(line), Some(&device)) = (buffer.last(), devices.last()) else {
+            return vec![];
+        };
+        let time = time_of_day(line);
+
+        let mut complete = Vec::new();
+        let mut open = Vec::new();
+        for mut capture in std::mem::take(&mut self.pending) {
+            if capture.device != device {
+                open.push(capture);
+                continue;
+            }
+            if let (CaptureWindow::Seconds(secs), Some(start), Some(now)) =
+                (self.window, capture.time, time)
+                && elapsed(start, now) > secs
+            {
+                complete.push(capture);
+                continue;
+            }
+            if capture.in_report && crash == Some(capture.kind) {
+                capture.report.push(line.clone());
+            } else {
+                capture.in_report = false;
+            }
+            capture.lines.push(line.clone());
+            capture.after += 1;
+            match self.window {
+                CaptureWindow::Lines(n) if capture.after >= n => complete.push(capture),
+                _ => open.push(capture),
+            }
+        }
+        self.pending = open;
+
+        if let Some(kind) = crash
+            && self.last_crash.get(&device) != Some(&kind)
+        {
+            let earlier: Vec<&String> = buffer[..buffer.len() - 1]
+                .iter()
+                .zip(devices)
+                .filter(|(_, d)| **d == device)
+                .map(|(l, _)| l)
+                .collect();
+            let before = earlier.len() - self.lines_before(&earlier, time);
+            let mut lines: Vec<String> = earlier[before..].iter().map(|l| (*l).clone()).collect();
+            lines.push(line.clone());
+            self.pending.push(PendingCapture {
+                device,
+                kind,
+                timestamp: LogFilter::parse_header(line).map(|h| format!("{} {}", h.date, h.time)),
+                time,
+                filters: toml::to_string(&Preset::from_state(filters, search)).unwrap_or_default(),
+                lines,
+                report: vec![line.clone()],
+                in_report: true,
+                after: 0,
+            });
+        }
+        match crash {
+            Some(kind) => self.last_crash.insert(device, kind),
+            None => self.last_crash.remove(&device),
+        };
+
+        complete.iter().map(|c| self.write(c)).collect()
+    }
+
+    /// Writes every capture still collecting its trailing window, e.g. on quit.
+    pub fn finish(&mut self) -> Vec<Result<PathBuf, Box<dyn Error>>> {
+        std::mem::take(&mut self.pending)
+            .iter()
+            .map(|c| self.write(c))
+            .collect()
+    }
+
+    /// Number of lines at the end of `before` that fall inside the window.
+    fn lines_before(&self, before: &[&String], crash_time: Option<f64>) -> usize {
+        match (self.window, crash_time) {
+            (CaptureWindow::Lines(n), _) => n.min(before.len()),
+            (CaptureWindow::Seconds(secs), Some(crash_time)) => before
+                .iter()
+                .rev()
+                .take_while(|l| time_of_day(l).is_none_or(|t| elapsed(t, crash_time) <= secs))
+                .count(),
+            (CaptureWindow::Seconds(_), None) => 0,
+        }
+    }
+
+    fn write(&self, capture: &PendingCapture) -> Result<PathBuf, Box<dyn Error>> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("cannot create {}: {}", self.dir.display(), e))?;
+        let report: Vec<&str> = capture.report.iter().map(String::as_str).collect();
+        let exception = crash_exception(capture.kind, &report)
+            .unwrap_or_else(|| capture.kind.label().to_owned());
+        let class = match capture.kind {
+            CrashKind::Java | CrashKind::Coroutine => {
+                exception.rsplit('.').next().unwrap_or(&exception)
+            }
+            CrashKind::Anr | CrashKind::Native => &exception,
+        };
+        let stamp = capture.timestamp.clone().unwrap_or_else(|| {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            now.as_secs().to_string()
+        });
+        let path = unused_path(
+            &self.dir,
+            &format!("crash_{}_{}", file_name_part(&stamp), file_name_part(class)),
+        );
+
+        let mut text = String::from("# navcat crash capture\n");
+        text.push_str(&format!("# crash: {}\n", capture.kind.label()));
+        text.push_str(&format!("# exception: {}\n", exception));
+        if let Some(timestamp) = &capture.timestamp {
+            text.push_str(&format!("# time: {}\n", timestamp));
+        }
+        for (key, value) in &self.device {
+            text.push_str(&format!("# device: {} = {}\n", key, value));
+        }
+        text.push_str("# filters:\n");
+        for line in capture.filters.lines() {
+            text.push_str(&format!("#   {}\n", line));
+        }
+        for line in &capture.lines {
+            text.push_str(line);
+            text.push('\n');
+        }
+        fs::write(&path, text).map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+/// `dir/<stem>.txt`, or `dir/<stem>_2.txt` and so on when that file exists, so
+/// two crashes of one class within a second keep both captures.
+fn unused_path(dir: &Path, stem: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.txt", stem));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}_{}.txt", stem, n));
+        n += 1;
+    }
+    path
+}
+
+/// Seconds since midnight of the line's `HH:MM:SS.mmm` timestamp.
+fn time_of_day(line: &str) -> Option<f64> {
+    let header = LogFilter::parse_header(line)?;
+    let mut fields = header.time.split(':');
+    let hours: f64 = fields.next()?.parse().ok()?;
+    let minutes: f64 = fields.next()?.parse().ok()?;
+    let seconds: f64 = fields.next()?.parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// Seconds from `from` to `to`, across midnight if `to` is earlier.
+fn elapsed(from: f64, to: f64) -> f64 {
+    (to - from).rem_euclid(SECONDS_PER_DAY)
+}
+
+/// `text` with every run of characters unsafe in file names replaced by `-`.
+fn file_name_part(text: &str) -> String {
+    let mut part = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' {
+            part.push(c);
+        } else if !part.ends_with('-') {
+            part.push('-');
+        }
+    }
+    part.trim_matches('-').chars().take(48).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::cli::Args;
+
+    fn state() -> FilterState {
+        FilterState::from_args(&Args {
+            tags: Some("Planner".to_owned()),
+            ..Args::default()
+        })
+    }
+
+    fn feed(capture: &mut CrashCapture, lines: &[&str]) -> Vec<PathBuf> {
+        let lines: Vec<(usize, &str)> = lines.iter().map(|l| (0, *l)).collect();
+        feed_devices(capture, &lines)
+    }
+
+    /// Feeds `(device, line)` pairs as the TUI does, with crash kinds tracked per device.
+    fn feed_devices(capture: &mut CrashCapture, lines: &[(usize, &str)]) -> Vec<PathBuf> {
+        let mut buffer = Vec::new();
+        let mut devices = Vec::new();
+        let mut written = Vec::new();
+        let mut last = HashMap::new();
+        for &(device, line) in lines {
+            buffer.push(line.to_string());
+            devices.push(device);
+            let crash = LogFilter::crash_kind(line, last.get(&device).copied());
+            match crash {
+                Some(kind) => last.insert(device, kind),
+                None => last.remove(&device),
+            };
+            for result in capture.push(&buffer, &devices, crash, &state(), "") {
+                written.push(result.unwrap());
+            }
+        }
+        written
+    }
+
+    #[test]
+    fn parses_lines_and_seconds_windows() {
+        assert_eq!("200".parse(), Ok(CaptureWindow::Lines(200)));
+        assert_eq!("2.5s".parse(), Ok(CaptureWindow::Seconds(2.5)));
+        assert!("0".parse::<CaptureWindow>().is_err());
+        assert!("soon".parse::<CaptureWindow>().is_err());
+    }
+
+    #[test]
+    fn writes_lines_around_crash_named_by_time_and_exception() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut capture = CrashCapture::new(
+            dir.path().to_path_buf(),
+            CaptureWindow::Lines(2),
+            vec![("ro.product.model".to_owned(), "Pixel 7".to_owned())],
+        );
+        let written = feed(
+            &mut capture,
+            &[
+                "2024-01-15 10:30:44 1234 1234 I Planner: too early",
+                "2024-01-15 10:30:45 1234 1234 I Planner: planning",
+                "2024-01-15 10:30:45 1234 1234 I Planner: retry",
+                "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: FATAL EXCEPTION: main",
+                "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: java.lang.IllegalStateException: boom",
+                "2024-01-15 10:30:47 1234 1234 I Planner: recovered",
+                "2024-01-15 10:30:48 1234 1234 I Planner: too late",
+            ],
+        );
+
+        assert_eq!(written.len(), 1);
+        assert_eq!(
+            written[0].file_name().unwrap().to_str().unwrap(),
+            "crash_2024-01-15-10-30-46_IllegalStateException.txt"
+        );
+        let text = fs::read_to_string(&written[0]).unwrap();
+        assert!(text.contains("# exception: java.lang.IllegalStateException"));
+        assert!(text.contains("# device: ro.product.model = Pixel 7"));
+        assert!(text.contains("#   tags = [\"Planner\"]"));
+        assert!(text.contains("planning") && text.contains("recovered"));
+        assert!(!text.contains("too early") && !text.contains("too late"));
+    }
+
+    #[test]
+    fn each_device_crash_is_captured_from_its_own_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut capture =
+            CrashCapture::new(dir.path().to_path_buf(), CaptureWindow::Lines(1), vec![]);
+        let written = feed_devices(
+            &mut capture,
+            &[
+                (0, "2024-01-15 10:30:45 1234 1234 I Planner: pixel before"),
+                (
+                    1,
+                    "2024-01-15 10:30:45 4321 4321 I Planner: emulator before",
+                ),
+                (
+                    0,
+                    "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: FATAL EXCEPTION: main",
+                ),
+                (
+                    1,
+                    "2024-01-15 10:30:46 4321 4321 E AndroidRuntime: FATAL EXCEPTION: main",
+                ),
+                (0, "2024-01-15 10:30:47 1234 1234 I Planner: pixel after"),
+                (1, "2024-01-15 10:30:47 4321 4321 I Planner: emulator after"),
+            ],
+        );
+
+        assert_eq!(written.len(), 2);
+        let pixel = fs::read_to_string(&written[0]).unwrap();
+        assert!(pixel.contains("pixel before") && pixel.contains("pixel after"));
+        assert!(!pixel.contains("emulator"));
+        let emulator = fs::read_to_string(&written[1]).unwrap();
+        assert!(emulator.contains("emulator before") && emulator.contains("emulator after"));
+        assert!(!emulator.contains("pixel"));
+    }
+
+    #[test]
+    fn crashes_of_one_class_in_the_same_second_keep_both_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut capture =
+            CrashCapture::new(dir.path().to_path_buf(), CaptureWindow::Lines(1), vec![]);
+        let crash = [
+            "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: FATAL EXCEPTION: main",
+            "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: java.lang.IllegalStateException: boom",
+            "2024-01-15 10:30:46 1234 1234 I Planner: done",
+        ];
+        let mut written = feed(&mut capture, &crash);
+        written.extend(feed(&mut capture, &crash));
+
+        assert_eq!(written.len(), 2);
+        assert_ne!(written[0], written[1]);
+        let second = written[1].file_name().unwrap().to_str().unwrap();
+        assert!(
+            second.ends_with("_IllegalStateException_2.txt"),
+            "{}",
+            second
+        );
+    }
+
+    #[test]
+    fn seconds_window_closes_on_first_later_line_or_finish() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut capture = CrashCapture::new(
+            dir.path().to_path_buf(),
+            CaptureWindow::Seconds(1.0),
+            vec![],
+        );
+        let lines = [
+            "01-15 10:30:44.000  1234  1234 I Planner: too early",
+            "01-15 10:30:45.500  1234  1234 I Planner: planning",
+            "01-15 10:30:46.000  1234  1260 F libc    : Fatal signal 11 (SIGSEGV), code 1",
+            "01-15 10:30:46.900  1234  1234 I Planner: after",
+        ];
+        assert!(feed(&mut capture, &lines).is_empty());
+
+        let written: Vec<PathBuf> = capture.finish().into_iter().map(Result::unwrap).collect();
+        assert_eq!(written.len(), 1);
+        let name = written[0].file_name().unwrap().to_str().unwrap();
+        assert!(name.ends_with("_signal-11-SIGSEGV.txt"), "{}", name);
+        let text = fs::read_to_string(&written[0]).unwrap();
+        assert!(text.contains("planning") && text.contains("after"));
+        assert!(!text.contains("too early"));
+    }
+}
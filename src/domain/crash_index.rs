@@ -0,0 +1,225 @@
+use crate::domain::filter::{CrashKind, LineRole, LogFilter, StyledLine};
+
+/// One crash report in the filtered lines, as listed in the crash index overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashSummary {
+    pub kind: CrashKind,
+    /// Index of the crash separator in the filtered lines.
+    pub line: usize,
+    pub timestamp: String,
+    /// Exception class, ANR reason or native signal.
+    pub exception: String,
+    /// First stack frame outside the framework, if the report has one.
+    pub top_frame: Option<String>,
+    pub process: Option<String>,
+}
+
+/// Lists every crash report in `lines`, oldest first.
+pub fn index_crashes(lines: &[StyledLine]) -> Vec<CrashSummary> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.is_crash_start())
+        .filter_map(|(i, separator)| {
+            let kind = separator.crash?;
+            let report: Vec<&StyledLine> = lines[i + 1..]
+                .iter()
+                .take_while(|l| l.role != LineRole::Separator && l.crash == Some(kind))
+                .collect();
+            Some(CrashSummary::from_report(kind, i, &report))
+        })
+        .collect()
+}
+
+impl CrashSummary {
+    fn from_report(kind: CrashKind, line: usize, report: &[&StyledLine]) -> Self {
+        let headers: Vec<_> = report
+            .iter()
+            .filter_map(|l| LogFilter::parse_header(&l.content))
+            .collect();
+        let messages: Vec<&str> = headers.iter().map(|h| h.message.as_str()).collect();
+        let timestamp = headers
+            .first()
+            .map(|h| h.time.to_owned())
+            .unwrap_or_default();
+        let pid = headers.first().and_then(|h| h.pid);
+
+        let (exception, top_frame, process) = details(kind, &messages);
+
+        Self {
+            kind,
+            line,
+            timestamp,
+            exception: exception.unwrap_or_else(|| "unknown".to_owned()),
+            top_frame,
+            process: process.or_else(|| pid.map(|pid| format!("pid {}", pid))),
+        }
+    }
+}
+
+/// Exception class, ANR reason or native signal of a crash report given as raw log lines.
+pub fn crash_exception(kind: CrashKind, lines: &[&str]) -> Option<String> {
+    let headers: Vec<_> = lines
+        .iter()
+        .filter_map(|l| LogFilter::parse_header(l))
+        .collect();
+    let messages: Vec<&str> = headers.iter().map(|h| h.message.as_str()).collect();
+    details(kind, &messages).0
+}
+
+type Details = (Option<String>, Option<String>, Option<String>);
+
+fn details(kind: CrashKind, messages: &[&str]) -> Details {
+    match kind {
+        CrashKind::Java | CrashKind::Coroutine => java_details(messages),
+        CrashKind::Anr => anr_details(messages),
+        CrashKind::Native => native_details(messages),
+    }
+}
+
+fn java_details(messages: &[&str]) -> Details {
+    let exception = messages
+        .iter()
+        .find(|m| LogFilter::is_crash_exception_line(m) && !m.starts_with("FATAL EXCEPTION"))
+        .map(|m| {
+            let m = m.trim_start_matches("Caused by:").trim_start();
+            m.split(':').next().unwrap_or(m).to_owned()
+        });
+    let top_frame = messages
+        .iter()
+        .find(|m| m.starts_with("at ") && !LogFilter::is_crash_framework_frame(m))
+        .map(|m| m["at ".len()..].to_owned());
+    let process = messages
+        .iter()
+        .find_map(|m| m.strip_prefix("Process: "))
+        .map(|p| p.split(',').next().unwrap_or(p).to_owned());
+    (exception, top_frame, process)
+}
+
+fn anr_details(messages: &[&str]) -> Details {
+    let exception = messages
+        .iter()
+        .find_map(|m| m.strip_prefix("Reason: "))
+        .map(str::to_owned)
+        .or_else(|| Some("ANR".to_owned()));
+    let process = messages
+        .iter()
+        .find_map(|m| m.strip_prefix("ANR in "))
+        .and_then(|p| p.split_whitespace().next())
+        .map(str::to_owned);
+    (exception, None, process)
+}
+
+fn native_details(messages: &[&str]) -> Details {
+    // `Fatal signal 11 (SIGSEGV), code 1 ...` or `signal 11 (SIGSEGV), code 1 ...`
+    let exception = messages.iter().find_map(|m| {
+        let start = m.find("signal ")?;
+        let end = m[start..].find(')')? + start + 1;
+        Some(m[start..end].to_owned())
+    });
+    // `#00 pc 000000000004f2a8  /data/app/.../libnav.so (Router::plan()+40)`
+    let top_frame = messages
+        .iter()
+        .find(|m| {
+            m.starts_with('#') && m.contains(" pc ") && !LogFilter::is_crash_framework_frame(m)
+        })
+        .map(|m| m.split_whitespace().skip(3).collect::<Vec<_>>().join(" "));
+    // `pid: 1234, tid: 1260, name: RenderThread  >>> com.example.nav <<<`
+    let process = messages.iter().find_map(|m| {
+        let start = m.find(">>> ")? + ">>> ".len();
+        let end = m[start..].find(" <<<")? + start;
+        Some(m[start..end].to_owned())
+    });
+    (exception, top_frame, process)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crash_lines(kind: CrashKind, messages: &[&str]) -> Vec<StyledLine> {
+        let mut lines = vec![StyledLine::crash_separator(kind)];
+        for message in messages {
+            let mut line = filter().matches(message).unwrap();
+            line.crash = Some(kind);
+            lines.push(line);
+        }
+        lines
+    }
+
+    fn filter() -> LogFilter {
+        use crate::domain::filter_config::{FilterConfig, HighlightConfig, TagCategories};
+        LogFilter::new(FilterConfig {
+            levels: vec!["V", "D", "I", "W", "E", "F"],
+            tags: TagCategories::new(vec![]),
+            blacklisted_items: vec![],
+            highlighted_items: vec![],
+            show_items: vec![],
+            processes: vec![],
+            no_tag_filter: true,
+            kernel: true,
+            highlight: HighlightConfig::default(),
+        })
+    }
+
+    #[test]
+    fn java_crash_summary_has_exception_frame_and_process() {
+        let lines = crash_lines(
+            CrashKind::Java,
+            &[
+                "2024-01-15 10:30:46.100 +0000 1234 1234 E AndroidRuntime: FATAL EXCEPTION: main",
+                "2024-01-15 10:30:46.100 +0000 1234 1234 E AndroidRuntime: Process: com.example.nav, PID: 1234",
+                "2024-01-15 10:30:46.100 +0000 1234 1234 E AndroidRuntime: java.lang.IllegalStateException: boom",
+                "2024-01-15 10:30:46.100 +0000 1234 1234 E AndroidRuntime: \tat android.os.Handler.dispatch(Handler.java:1)",
+                "2024-01-15 10:30:46.100 +0000 1234 1234 E AndroidRuntime: \tat com.example.nav.Router.plan(Router.kt:42)",
+            ],
+        );
+
+        let index = index_crashes(&lines);
+
+        assert_eq!(index.len(), 1);
+        let crash = &index[0];
+        assert_eq!(crash.line, 0);
+        assert_eq!(crash.timestamp, "10:30:46.100");
+        assert_eq!(crash.exception, "java.lang.IllegalStateException");
+        assert_eq!(
+            crash.top_frame.as_deref(),
+            Some("com.example.nav.Router.plan(Router.kt:42)")
+        );
+        assert_eq!(crash.process.as_deref(), Some("com.example.nav"));
+    }
+
+    #[test]
+    fn anr_and_native_summaries() {
+        let mut lines = crash_lines(
+            CrashKind::Anr,
+            &[
+                "2024-01-15 10:30:45 900 950 E ActivityManager: ANR in com.example.nav (com.example.nav/.MainActivity)",
+                "2024-01-15 10:30:45 900 950 E ActivityManager: Reason: Input dispatching timed out",
+            ],
+        );
+        lines.extend(crash_lines(
+            CrashKind::Native,
+            &[
+                "2024-01-15 10:31:00 4321 4321 F DEBUG   : pid: 1234, tid: 1260, name: RenderThread  >>> com.example.nav <<<",
+                "2024-01-15 10:31:00 4321 4321 F DEBUG   : signal 11 (SIGSEGV), code 1 (SEGV_MAPERR)",
+                "2024-01-15 10:31:00 4321 4321 F DEBUG   : #00 pc 000000000004f2a8 /system/lib64/libc.so (abort+12)",
+                "2024-01-15 10:31:00 4321 4321 F DEBUG   : #01 pc 0000000000012345 /data/app/lib/arm64/libnav.so (Router::plan()+40)",
+            ],
+        ));
+
+        let index = index_crashes(&lines);
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(index[0].exception, "Input dispatching timed out");
+        assert_eq!(index[0].process.as_deref(), Some("com.example.nav"));
+        assert_eq!(index[0].top_frame, None);
+        assert_eq!(index[1].line, 3);
+        assert_eq!(index[1].exception, "signal 11 (SIGSEGV)");
+        assert_eq!(
+            index[1].top_frame.as_deref(),
+            Some("/data/app/lib/arm64/libnav.so (Router::plan()+40)")
+        );
+        assert_eq!(index[1].process.as_deref(), Some("com.example.nav"));
+    }
+}
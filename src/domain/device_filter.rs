@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use crate::domain::filter_config::{CategoryPatterns, FilterState};
+
+/// Logcat priorities from most to least verbose; `S` silences a tag.
+const PRIORITIES: [char; 7] = ['V', 'D', 'I', 'W', 'E', 'F', 'S'];
+
+/// Tags whose error lines start or continue a crash report, which the filter shows
+/// whatever the tag list says.
+const CRASH_TAGS: &[&str] = &["AndroidRuntime", "ActivityManager", "DEBUG"];
+
+/// The part of a `FilterState` logcat can apply on the device, as filterspecs
+/// (`Tag:Level ... *:Level`), so hidden lines never cross USB.
+///
+/// Tags are matched exactly and case-sensitively by logcat, unlike the substring
+/// match of `LogFilter`, so a tag only reaches the device filter by its full name.
+/// Everything else (search, show items, blacklists) stays on the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceFilter {
+    /// Lowest priority logcat passes per tag.
+    tags: BTreeMap<String, char>,
+    /// Lowest priority for every other tag.
+    default: char,
+}
+
+impl DeviceFilter {
+    /// `categories` decide which base tags the category toggles leave enabled.
+    pub fn from_state(state: &FilterState, categories: &CategoryPatterns) -> Self {
+        let levels = state.level_state.to_levels();
+        let Some(lowest) = levels.first().and_then(|level| level.chars().next()) else {
+            return Self {
+                tags: BTreeMap::new(),
+                default: 'S',
+            };
+        };
+        if state.no_tag_filter {
+            return Self {
+                tags: BTreeMap::new(),
+                default: lowest,
+            };
+        }
+
+        let mut tags = BTreeMap::new();
+        for tag in state.enabled_tags(categories) {
+            if tag.chars().all(|c| !c.is_whitespace() && c != ':') {
+                tags.insert(tag, lowest);
+            }
+        }
+        let crash_level = max_priority(lowest, 'E');
+        for tag in CRASH_TAGS {
+            tags.entry(tag.to_string()).or_insert(crash_level);
+        }
+        // Fatal lines bypass the tag filter, so every tag keeps its fatal lines.
+        Self { tags, default: 'F' }
+    }
+
+    /// The filterspec arguments for `adb logcat`.
+    pub fn filterspecs(&self) -> Vec<String> {
+        self.tags
+            .iter()
+            .map(|(tag, level)| format!("{}:{}", tag, level))
+            .chain(std::iter::once(format!("*:{}", self.default)))
+            .collect()
+    }
+
+    /// True if this filter passes some line `previous` drops, so a logcat started
+    /// with `previous` must be restarted to show everything this one shows.
+    pub fn widens(&self, previous: &Self) -> bool {
+        let level_of = |filter: &Self, tag: &str| *filter.tags.get(tag).unwrap_or(&filter.default);
+        rank(self.default) < rank(previous.default)
+            || self
+                .tags
+                .iter()
+                .any(|(tag, &level)| rank(level) < rank(level_of(previous, tag)))
+            || previous
+                .tags
+                .keys()
+                .any(|tag| rank(level_of(self, tag)) < rank(previous.tags[tag]))
+    }
+}
+
+fn rank(priority: char) -> usize {
+    PRIORITIES
+        .iter()
+        .position(|&p| p == priority)
+        .unwrap_or(PRIORITIES.len())
+}
+
+fn max_priority(a: char, b: char) -> char {
+    if rank(a) >= rank(b) { a } else { b }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::cli::Args;
+
+    fn state(levels: &str, tags: &[&str]) -> FilterState {
+        FilterState::from_args(&Args {
+            logcat_levels: Some(levels.to_owned()),
+            tags: Some(tags.join(",")),
+            ..Args::default()
+        })
+    }
+
+    fn device_filter(state: &FilterState) -> DeviceFilter {
+        DeviceFilter::from_state(state, &CategoryPatterns::default())
+    }
+
+    #[test]
+    fn tags_and_lowest_level_become_filterspecs() {
+        let filter = device_filter(&state("I,W,E", &["Planner", "Lane Guidance"]));
+        assert_eq!(
+            filter.filterspecs(),
+            [
+                "ActivityManager:E",
+                "AndroidRuntime:E",
+                "DEBUG:E",
+                "Planner:I",
+                "*:F"
+            ]
+        );
+    }
+
+    #[test]
+    fn no_tag_filter_or_no_levels_only_set_the_default() {
+        let mut all_tags = state("D,I", &["Planner"]);
+        all_tags.no_tag_filter = true;
+        assert_eq!(device_filter(&all_tags).filterspecs(), ["*:D"]);
+        assert_eq!(
+            device_filter(&state("", &["Planner"])).filterspecs(),
+            ["*:S"]
+        );
+    }
+
+    #[test]
+    fn widening_is_a_lower_level_or_an_added_tag() {
+        let base = device_filter(&state("I", &["Planner"]));
+        let lower = device_filter(&state("D,I", &["Planner"]));
+        let more_tags = device_filter(&state("I", &["Planner", "Router"]));
+        let mut fewer = state("I", &["Planner"]);
+        fewer.routing = false;
+        let fewer = device_filter(&fewer);
+
+        assert!(lower.widens(&base));
+        assert!(more_tags.widens(&base));
+        assert!(!base.widens(&lower));
+        assert!(!fewer.widens(&base));
+        assert!(!base.widens(&base));
+        assert!(base.widens(&fewer));
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use crate::domain::filter::{CrashKind, LogFilter, StyledLine};
+
+/// A log entry started by a header line, and whether that line passed the filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    id: usize,
+    shown: bool,
+}
+
+/// Groups stack-trace continuation lines (`at ...`, `Caused by:`, …) with the entry
+/// whose header line precedes them on the same device and pid/tid, so the group is
+/// filtered as one: continuation lines show exactly when their header does.
+#[derive(Debug, Clone, Default)]
+pub struct EntryGrouper {
+    next_id: usize,
+    /// Last entry started on each device and pid/tid.
+    threads: HashMap<(usize, String, String), Entry>,
+    /// Last entry started on any thread of each device, for continuation lines
+    /// without a logcat header.
+    last: HashMap<usize, Entry>,
+}
+
+impl EntryGrouper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// True if `line` continues the entry before it rather than starting one.
+    pub fn is_continuation(line: &str) -> bool {
+        if LogFilter::looks_like_stack_trace(line) {
+            return true;
+        }
+        LogFilter::parse_header(line)
+            .is_some_and(|header| LogFilter::looks_like_stack_trace(&header.message))
+    }
+
+    /// Filters `line` from `device` as part of its entry and tags the result with
+    /// the entry id and device. A continuation line whose entry is unknown (at the
+    /// start of a file, after a buffer trim or a reconnect) is dropped, together with
+    /// the lines that continue it. `process` names the line's process for the process
+    /// filter, when known.
+    pub fn filter_line(
+        &mut self,
+        filter: &LogFilter,
+        device: usize,
+        line: &str,
+        crash: Option<CrashKind>,
+        process: Option<&str>,
+    ) -> Option<StyledLine> {
+        let header = LogFilter::parse_header(line);
+        let thread = header
+            .as_ref()
+            .and_then(|h| Some((device, h.pid?.to_owned(), h.tid?.to_owned())));
+        let continuation = match &header {
+            Some(h) => LogFilter::looks_like_stack_trace(&h.message),
+            None => LogFilter::looks_like_stack_trace(line),
+        };
+
+        if continuation {
+            let parent = match &thread {
+                Some(thread) => self.threads.get(thread).copied(),
+                None => self.last.get(&device).copied(),
+            };
+            if let Some(parent) = parent {
+                if !parent.shown {
+                    return None;
+                }
+                let mut styled = filter.style_continuation(line, crash)?;
+                styled.entry = parent.id;
+                styled.device = device;
+                return Some(styled);
+            }
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let styled = if continuation {
+            None
+        } else if filter.matches_process(process) || LogFilter::is_marker(line) {
+            filter.matches_crash(line, crash)
+        } else {
+            None
+        };
+        let entry = Entry {
+            id,
+            shown: styled.is_some(),
+        };
+        if let Some(thread) = thread {
+            self.threads.insert(thread, entry);
+        }
+        self.last.insert(device, entry);
+        styled.map(|mut styled| {
+            styled.entry = id;
+            styled.device = device;
+            styled
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::filter_config::{FilterConfig, HighlightConfig, TagCategories};
+
+    fn filter(tags: &[&str]) -> LogFilter {
+        LogFilter::new(FilterConfig {
+            levels: vec!["I", "W"],
+            tags: TagCategories::new(tags.iter().map(|t| t.to_string()).collect()),
+            blacklisted_items: vec![],
+            highlighted_items: vec![],
+            show_items: vec![],
+            processes: vec![],
+            no_tag_filter: false,
+            kernel: true,
+            highlight: HighlightConfig::default(),
+        })
+    }
+
+    fn run(filter: &LogFilter, lines: &[&str]) -> Vec<(usize, String)> {
+        let mut grouper = EntryGrouper::new();
+        lines
+            .iter()
+            .filter_map(|line| grouper.filter_line(filter, 0, line, None, None))
+            .map(|l| (l.entry, l.content))
+            .collect()
+    }
+
+    #[test]
+    fn continuation_follows_header_on_same_thread() {
+        let lines = [
+            "2024-01-15 10:30:45 100 101 W Planner: retry failed",
+            "2024-01-15 10:30:45 200 201 W Other: unrelated failure",
+            "2024-01-15 10:30:45 200 201 W Other: \tat com.other.App.run(App.java:3)",
+            "2024-01-15 10:30:45 100 101 W Planner: \tat com.tomtom.nav.Planner.plan(Planner.kt:9)",
+        ];
+        let shown = run(&filter(&["Planner"]), &lines);
+        assert_eq!(shown.len(), 2);
+        assert_eq!(shown[0].0, shown[1].0);
+        assert!(shown[1].1.contains("Planner.plan"));
+    }
+
+    #[test]
+    fn raw_frames_follow_the_last_header() {
+        let lines = [
+            "2024-01-15 10:30:45 200 201 W Other: unrelated failure",
+            "    at com.other.App.run(App.java:3)",
+            "    ... 4 more",
+            "2024-01-15 10:30:46 100 101 I Planner: planning",
+            "Caused by: java.io.IOException",
+        ];
+        let shown = run(&filter(&["Planner"]), &lines);
+        let contents: Vec<&str> = shown.iter().map(|(_, c)| c.as_str()).collect();
+        assert_eq!(contents.len(), 2);
+        assert!(contents[1].starts_with("Caused by:"));
+    }
+
+    #[test]
+    fn same_thread_on_another_device_is_another_entry() {
+        let mut grouper = EntryGrouper::new();
+        let filter = filter(&["Planner"]);
+        let header = "2024-01-15 10:30:45 100 101 W Planner: retry failed";
+        let frame = "2024-01-15 10:30:45 100 101 W Other: \tat com.other.App.run(App.java:3)";
+
+        assert!(
+            grouper
+                .filter_line(&filter, 0, header, None, None)
+                .is_some()
+        );
+        assert!(grouper.filter_line(&filter, 1, frame, None, None).is_none());
+        assert_eq!(
+            grouper
+                .filter_line(&filter, 0, frame, None, None)
+                .unwrap()
+                .device,
+            0
+        );
+    }
+
+    #[test]
+    fn orphan_frames_before_any_header_are_dropped() {
+        let lines = [
+            "    at com.other.App.run(App.java:3)",
+            "Caused by: java.io.IOException",
+            "2024-01-15 10:30:45 100 101 W Planner: \tat com.tomtom.nav.Planner.plan(Planner.kt:9)",
+            "2024-01-15 10:30:46 100 101 W Planner: retry failed",
+            "2024-01-15 10:30:46 100 101 W Planner: \tat com.tomtom.nav.Planner.plan(Planner.kt:9)",
+        ];
+        let shown = run(&filter(&["Planner"]), &lines);
+        let contents: Vec<&str> = shown.iter().map(|(_, c)| c.as_str()).collect();
+        assert_eq!(contents.len(), 2);
+        assert!(contents[0].ends_with("Planner: retry failed"));
+        assert_eq!(shown[0].0, shown[1].0);
+        assert!(EntryGrouper::is_continuation(
+            "    at com.other.App.run(App.java:3)"
+        ));
+        assert!(!EntryGrouper::is_continuation(
+            "2024-01-15 10:30:46 100 101 I Planner: planning"
+        ));
+    }
+}
@@ -1,8 +1,14 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::Span;
 
-use crate::domain::filter_config::{FilterConfig, FilterState, TagCategories, TagCategory};
-use crate::domain::message_highlighter::MessageHighlighter;
+use crate::domain::filter_config::{
+    FilterConfig, FilterRules, FilterState, HighlightConfig, TagCategories, TagCategory,
+};
+use crate::domain::message_highlighter::{HighlightPriority, MessageHighlighter};
+use crate::domain::retrace::Retracer;
 
 struct HighlightBuilder {
     red_words: std::collections::HashSet<String>,
@@ -21,30 +27,30 @@ impl HighlightBuilder {
         }
     }
 
-    fn add_red_words(mut self, words: &[&str]) -> Self {
+    fn add_red_words<S: AsRef<str>>(mut self, words: &[S]) -> Self {
         for word in words {
-            self.red_words.insert(word.to_lowercase());
+            self.red_words.insert(word.as_ref().to_lowercase());
         }
         self
     }
 
-    fn add_green_words(mut self, words: &[&str]) -> Self {
+    fn add_green_words<S: AsRef<str>>(mut self, words: &[S]) -> Self {
         for word in words {
-            self.green_words.insert(word.to_lowercase());
+            self.green_words.insert(word.as_ref().to_lowercase());
         }
         self
     }
 
-    fn add_yellow_words(mut self, words: &[&str]) -> Self {
+    fn add_yellow_words<S: AsRef<str>>(mut self, words: &[S]) -> Self {
         for word in words {
-            self.yellow_words.insert(word.to_lowercase());
+            self.yellow_words.insert(word.as_ref().to_lowercase());
         }
         self
     }
 
-    fn add_custom_words(mut self, words: &[&str]) -> Self {
+    fn add_custom_words<S: AsRef<str>>(mut self, words: &[S]) -> Self {
         for word in words {
-            self.custom_words.insert(word.to_lowercase());
+            self.custom_words.insert(word.as_ref().to_lowercase());
         }
         self
     }
@@ -59,65 +65,31 @@ impl HighlightBuilder {
     }
 }
 
-fn create_default_highlighter() -> HighlightBuilder {
-    HighlightBuilder::new()
-        .add_red_words(&[
-            "error",
-            "removed",
-            "unfollowed",
-            "not followed",
-            "unvisited",
-            "deviation",
-            "off-road",
-            "off-route",
-        ])
-        .add_green_words(&[
-            "success",
-            "created",
-            "added",
-            "followed",
-            "following",
-            "visited",
-            "planned",
-            "arrived",
-            "departed",
-            "started",
-            "starting",
-            "resumed",
-            "resuming",
-            "stopped",
-            "stopping",
-        ])
-        .add_yellow_words(&[
-            "warning",
-            "updated",
-            "changed",
-            "segment",
-            "old",
-            "new",
-            "map matching",
-            "projected",
-            "matchlocation",
-            "matched",
-            "replan",
-            "should replan",
-            "refresh",
-            "back to route",
-            "replanning",
-            "language change",
-            "increment",
-            "progress",
-            "current location",
-            "distancealongroute",
-            "traffic jam",
-            "instruction",
-            "guidance",
-            "lane guidance",
-            "lane level guidance",
-            "route",
-            "waypoint",
-            "planning route",
-        ])
+fn create_highlighter(config: &HighlightConfig, custom_words: &[String]) -> MessageHighlighter {
+    let mut highlighter = HighlightBuilder::new()
+        .add_red_words(&config.red)
+        .add_green_words(&config.green)
+        .add_yellow_words(&config.yellow)
+        .add_custom_words(custom_words)
+        .build();
+    let colors = &config.colors;
+    for (priority, name) in [
+        (HighlightPriority::Red, &colors.red),
+        (HighlightPriority::Green, &colors.green),
+        (HighlightPriority::Yellow, &colors.yellow),
+        (HighlightPriority::Custom, &colors.custom),
+    ] {
+        if let Ok(color) = Color::from_str(name) {
+            highlighter.set_color(priority, color);
+        }
+    }
+    // Rules are validated when the config is loaded.
+    for rule in &config.rules {
+        if let (Ok(pattern), Ok(style)) = (rule.pattern(), rule.style()) {
+            highlighter.add_rule(pattern, rule.priority, style);
+        }
+    }
+    highlighter
 }
 
 #[derive(Debug)]
@@ -128,26 +100,159 @@ enum LogFormat {
     Short,          // MM-DD HH:MM:SS PID TID LEVEL TAG
 }
 
+/// Kinds of crash report recognised in the log. Crash lines bypass the tag
+/// filter, and each kind gets its own separator and colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashKind {
+    /// Uncaught exception reported by `E AndroidRuntime:`.
+    Java,
+    /// Uncaught exception on a coroutine dispatcher thread.
+    Coroutine,
+    /// `E ActivityManager: ANR in ...` and the report lines that follow it.
+    Anr,
+    /// Native tombstone (`DEBUG: *** *** ***`) or `libc: Fatal signal`.
+    Native,
+}
+
+impl CrashKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Java => "crash",
+            Self::Coroutine => "coroutine crash",
+            Self::Anr => "ANR",
+            Self::Native => "native crash",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Self::Java => Color::Red,
+            Self::Coroutine => Color::LightRed,
+            Self::Anr => Color::Yellow,
+            Self::Native => Color::Magenta,
+        }
+    }
+}
+
 /// A filtered log line ready for display. `content` is plain text for search;
 /// `spans` are pre-styled ratatui spans for rendering.
 #[derive(Debug, Clone)]
 pub struct StyledLine {
     pub content: String,
     pub spans: Vec<Span<'static>>,
+    /// Set on crash report lines and on the separator that opens each report.
+    pub crash: Option<CrashKind>,
+    pub role: LineRole,
+    /// The line as logged, when retracing changed what is displayed.
+    pub original: Option<String>,
+    /// Log entry the line belongs to; stack-trace continuation lines share their header's.
+    pub entry: usize,
+    /// Index of the device the line came from; always 0 with a single source.
+    pub device: usize,
+}
+
+/// Level given to lines from the kernel log.
+pub const KERNEL_LEVEL: &str = "K";
+
+/// Start of every line navcat inserts into the raw log.
+pub const MARKER_PREFIX: &str = "─── ";
+
+/// How a filtered line takes part in the display beyond its text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineRole {
+    Log,
+    /// Inserted by navcat rather than read from the log.
+    Separator,
+    /// A stack frame in framework code; hidden while its crash is folded.
+    FrameworkFrame,
+    /// Stands in for the run of framework frames that follows it while folded.
+    FoldedFrames(usize),
 }
 
 impl StyledLine {
     fn new(content: String, spans: Vec<Span<'static>>) -> Self {
-        Self { content, spans }
+        Self {
+            content,
+            spans,
+            crash: None,
+            role: LineRole::Log,
+            original: None,
+            entry: 0,
+            device: 0,
+        }
+    }
+
+    /// The `… N framework frames` row shown in place of a folded run.
+    pub fn folded_frames(frames: usize, crash: Option<CrashKind>) -> Self {
+        let text = format!(
+            "    … {} framework frame{}",
+            frames,
+            if frames == 1 { "" } else { "s" }
+        );
+        Self {
+            content: text.clone(),
+            spans: vec![Span::styled(
+                text,
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::ITALIC),
+            )],
+            crash,
+            role: LineRole::FoldedFrames(frames),
+            original: None,
+            entry: 0,
+            device: 0,
+        }
+    }
+
+    pub fn crash_separator(kind: CrashKind) -> Self {
+        let label = format!("─── {} ", kind.label());
+        let text = format!("{:─<61}", label);
+        Self {
+            content: text.clone(),
+            spans: vec![Span::styled(text, Style::default().fg(kind.color()))],
+            crash: Some(kind),
+            role: LineRole::Separator,
+            original: None,
+            entry: 0,
+            device: 0,
+        }
     }
 
-    pub fn crash_separator() -> Self {
-        let text = "─── crash ───────────────────────────────────────────────────";
+    /// A line navcat wrote into the log itself, such as
+    /// `─── adb disconnected 10:31:02 (gap 14.2 s) ───`.
+    pub fn marker(line: &str) -> Self {
         Self {
-            content: text.to_owned(),
-            spans: vec![Span::styled(text, Style::default().fg(Color::Red))],
+            content: line.to_owned(),
+            spans: vec![Span::styled(
+                line.to_owned(),
+                Style::default().fg(Color::Yellow),
+            )],
+            crash: None,
+            role: LineRole::Separator,
+            original: None,
+            entry: 0,
+            device: 0,
         }
     }
+
+    /// True for the separator that opens a crash report.
+    pub fn is_crash_start(&self) -> bool {
+        self.role == LineRole::Separator && self.crash.is_some()
+    }
+}
+
+/// Header fields of a logcat line. `pid` and `tid` are missing in the compact format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogHeader<'a> {
+    pub date: &'a str,
+    pub time: &'a str,
+    pub pid: Option<&'a str>,
+    pub tid: Option<&'a str>,
+    pub level: &'a str,
+    pub tag: &'a str,
+    /// Words after the tag joined by single spaces.
+    pub message: String,
 }
 
 #[derive(Clone, Debug)]
@@ -156,38 +261,40 @@ pub struct LogFilter {
     tags: TagCategories,
     blacklisted_items: Vec<String>,
     show_items: Vec<String>,
+    processes: Vec<String>,
     no_tag_filter: bool,
+    kernel: bool,
     message_highlighter: MessageHighlighter,
+    retracer: Option<Arc<Retracer>>,
 }
 
 impl LogFilter {
-    pub fn from_state(state: &FilterState) -> Self {
-        Self::new(state.to_filter_config())
+    pub fn from_state(state: &FilterState, rules: &FilterRules) -> Self {
+        Self::new(state.to_filter_config(rules))
     }
 
     pub(crate) fn new(config: FilterConfig) -> Self {
-        let mut builder = create_default_highlighter();
-
-        if !config.highlighted_items.is_empty() {
-            builder = builder.add_custom_words(
-                &config
-                    .highlighted_items
-                    .iter()
-                    .map(|s| s.as_str())
-                    .collect::<Vec<_>>(),
-            );
-        }
+        let message_highlighter = create_highlighter(&config.highlight, &config.highlighted_items);
 
         Self {
             levels: config.levels,
             tags: config.tags,
             blacklisted_items: config.blacklisted_items,
             show_items: config.show_items,
+            processes: config.processes,
             no_tag_filter: config.no_tag_filter,
-            message_highlighter: builder.build(),
+            kernel: config.kernel,
+            message_highlighter,
+            retracer: None,
         }
     }
 
+    /// Retraces obfuscated tags and crash frames with an R8 mapping.
+    pub fn with_retracer(mut self, retracer: Option<Arc<Retracer>>) -> Self {
+        self.retracer = retracer;
+        self
+    }
+
     fn get_level_style(level: &str) -> Style {
         match level {
             "V" => Style::default().fg(Color::Gray),
@@ -199,14 +306,12 @@ impl LogFilter {
                 .fg(Color::White)
                 .bg(Color::Red)
                 .add_modifier(Modifier::BOLD),
+            KERNEL_LEVEL => Style::default().fg(Color::LightBlue),
             _ => Style::default(),
         }
     }
 
     fn get_tag_style(&self, tag: &str) -> Style {
-        if tag == "AndroidRuntime" {
-            return Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
-        }
         match self.tags.category_of(tag) {
             TagCategory::Routing => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
             TagCategory::MapMatching => Style::default().fg(Color::Yellow),
@@ -215,35 +320,93 @@ impl LogFilter {
         }
     }
 
-    fn is_crash_tag(tag: &str) -> bool {
-        tag == "AndroidRuntime"
-    }
-
-    pub fn is_crash_line(line: &str) -> bool {
-        line.contains(" E AndroidRuntime:")
+    /// Classifies a logcat line as part of a crash report. `previous` is the kind of
+    /// the crash line before it, so report lines that carry no marker of their own
+    /// (ANR details, coroutine stack frames) stay with the crash they belong to.
+    pub fn crash_kind(line: &str, previous: Option<CrashKind>) -> Option<CrashKind> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let (level_idx, tag_idx) = Self::get_level_and_tag_indices(&parts)?;
+        let level = *parts.get(level_idx)?;
+        let tag = parts.get(tag_idx)?.trim_end_matches(':');
+        // Padded tags (`DEBUG   : ...`) leave a lone colon before the message.
+        let words: Vec<&str> = parts[tag_idx + 1..]
+            .iter()
+            .copied()
+            .skip_while(|w| *w == ":")
+            .collect();
+        let starts_with = |prefix: &[&str]| words.starts_with(prefix);
+        let is_error = level.eq_ignore_ascii_case("E");
+        let is_fatal = level.eq_ignore_ascii_case("F");
+
+        match tag {
+            "AndroidRuntime" if is_error => {
+                if starts_with(&["FATAL", "EXCEPTION:"]) {
+                    let on_coroutine = words.iter().any(|w| {
+                        w.starts_with("DefaultDispatcher-worker") || w.contains("Coroutine")
+                    });
+                    Some(if on_coroutine {
+                        CrashKind::Coroutine
+                    } else {
+                        CrashKind::Java
+                    })
+                } else if previous == Some(CrashKind::Coroutine) {
+                    Some(CrashKind::Coroutine)
+                } else {
+                    Some(CrashKind::Java)
+                }
+            }
+            "ActivityManager"
+                if is_error
+                    && (starts_with(&["ANR", "in"]) || previous == Some(CrashKind::Anr)) =>
+            {
+                Some(CrashKind::Anr)
+            }
+            // Apps log under `DEBUG` too; only the tombstone banner starts a native crash.
+            "DEBUG"
+                if (is_error || is_fatal)
+                    && (starts_with(&["***", "***", "***"])
+                        || previous == Some(CrashKind::Native)) =>
+            {
+                Some(CrashKind::Native)
+            }
+            "libc" if starts_with(&["Fatal", "signal"]) => Some(CrashKind::Native),
+            _ => None,
+        }
     }
 
-    fn colorize_crash_message(message: &str) -> Span<'static> {
-        let style = if Self::is_crash_exception_line(message.trim_start()) {
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
-        } else if Self::is_crash_framework_frame(message.trim_start()) {
+    fn colorize_crash_message(message: &str, kind: CrashKind) -> Span<'static> {
+        let trimmed = message.trim_start_matches(':').trim_start();
+        let color = kind.color();
+        let headline = match kind {
+            CrashKind::Java | CrashKind::Coroutine => Self::is_crash_exception_line(trimmed),
+            CrashKind::Anr => trimmed.starts_with("ANR in") || trimmed.starts_with("Reason:"),
+            CrashKind::Native => {
+                trimmed.starts_with("*** ***")
+                    || trimmed.starts_with("Fatal signal")
+                    || trimmed.starts_with("signal ")
+                    || trimmed.starts_with("Abort message")
+            }
+        };
+        let style = if headline {
+            Style::default().fg(color).add_modifier(Modifier::BOLD)
+        } else if Self::is_crash_framework_frame(trimmed) {
             Style::default().fg(Color::DarkGray)
         } else {
-            Style::default().fg(Color::Red)
+            Style::default().fg(color)
         };
         Span::styled(message.to_owned(), style)
     }
 
-    fn is_crash_exception_line(trimmed: &str) -> bool {
+    pub(crate) fn is_crash_exception_line(trimmed: &str) -> bool {
         trimmed.starts_with("FATAL EXCEPTION")
             || trimmed.starts_with("Caused by:")
             // Java exception: package.ClassName: message — no space before the colon
             || trimmed
                 .find(':')
-                .map_or(false, |pos| trimmed[..pos].contains('.') && !trimmed[..pos].contains(' '))
+                .is_some_and(|pos| trimmed[..pos].contains('.') && !trimmed[..pos].contains(' '))
     }
 
-    fn is_crash_framework_frame(trimmed: &str) -> bool {
+    pub(crate) fn is_crash_framework_frame(trimmed: &str) -> bool {
         trimmed.starts_with("...")
             || [
                 "at android.",
@@ -256,20 +419,88 @@ impl LogFilter {
             ]
             .iter()
             .any(|p| trimmed.starts_with(p))
+            // Native backtrace frame in a system library: `#03 pc 000... /system/lib64/...`
+            || (trimmed.starts_with('#')
+                && (trimmed.contains(" /system/") || trimmed.contains(" /apex/")))
+    }
+
+    /// `line` from the kernel log with its level replaced by `K`, so it reads like
+    /// any other logcat line but filters and colors as kernel output.
+    pub fn kernel_line(line: &str) -> String {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let Some((level_idx, _)) = Self::get_level_and_tag_indices(&parts) else {
+            return line.to_owned();
+        };
+        let Some(level) = parts.get(level_idx) else {
+            return line.to_owned();
+        };
+        let start = level.as_ptr() as usize - line.as_ptr() as usize;
+        format!(
+            "{}{}{}",
+            &line[..start],
+            KERNEL_LEVEL,
+            &line[start + level.len()..]
+        )
+    }
+
+    /// True for lines navcat inserts into the log, like connection gap markers.
+    pub fn is_marker(line: &str) -> bool {
+        line.starts_with(MARKER_PREFIX)
+    }
+
+    /// Splits a logcat line into its header fields; `None` for lines without a header.
+    pub fn parse_header(line: &str) -> Option<LogHeader<'_>> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let format = Self::detect_format(&parts)?;
+        let (level_idx, tag_idx) = Self::get_level_and_tag_indices(&parts)?;
+        let tag = parts.get(tag_idx)?.trim_end_matches(':');
+        let (pid, tid) = match format {
+            LogFormat::Compact => (None, None),
+            _ => (Some(parts[level_idx - 2]), Some(parts[level_idx - 1])),
+        };
+        let message = parts[tag_idx + 1..]
+            .iter()
+            .copied()
+            .skip_while(|w| *w == ":")
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(LogHeader {
+            date: parts[0],
+            time: parts[1],
+            pid,
+            tid,
+            level: parts[level_idx],
+            tag,
+            message,
+        })
+    }
+
+    /// Whether a line from the process/thread named `label` (see `ProcessNames::label`)
+    /// passes the process filter. Lines of unknown processes pass only without one.
+    pub fn matches_process(&self, label: Option<&str>) -> bool {
+        if self.processes.is_empty() {
+            return true;
+        }
+        let Some(label) = label else {
+            return false;
+        };
+        let label = label.to_ascii_lowercase();
+        self.processes.iter().any(|term| label.contains(term))
     }
 
     pub fn matches(&self, line: &str) -> Option<StyledLine> {
+        self.matches_crash(line, Self::crash_kind(line, None))
+    }
+
+    /// Like `matches`, with the line's crash kind already known from `crash_kind`.
+    pub fn matches_crash(&self, line: &str, crash: Option<CrashKind>) -> Option<StyledLine> {
         if line.trim().is_empty() {
             return None;
         }
 
-        // Raw stack trace lines (no logcat header) — pass through with dim red.
-        if Self::looks_like_stack_trace(line) {
-            let style = Style::default().fg(Color::Red).add_modifier(Modifier::DIM);
-            return Some(StyledLine::new(
-                line.to_owned(),
-                vec![Span::styled(line.to_owned(), style)],
-            ));
+        // navcat's own markers always show; they have no level or tag to filter on.
+        if Self::is_marker(line) {
+            return Some(StyledLine::marker(line));
         }
 
         let line_lower = line.to_ascii_lowercase();
@@ -299,11 +530,18 @@ impl LogFilter {
             return None;
         }
 
+        // Kernel lines have their own toggle; their tags are driver names, not app tags.
+        let line_level = parts[level_idx];
+        if line_level == KERNEL_LEVEL {
+            return self
+                .kernel
+                .then(|| self.style_line(line, &parts, level_idx, tag_idx, crash));
+        }
+
         // Empty levels list means all levels are off — block everything.
         if self.levels.is_empty() {
             return None;
         }
-        let line_level = parts[level_idx];
         if !self
             .levels
             .iter()
@@ -312,20 +550,66 @@ impl LogFilter {
             return None;
         }
 
-        // Check tag filter. FATAL lines bypass tag filtering so crashes always show.
+        // Check tag filter. FATAL and crash lines bypass tag filtering so crashes always show.
         // When no_tag_filter is set, empty tag list means "show all".
         // Otherwise empty tag list means all category toggles are off → show nothing.
-        let line_tag = parts[tag_idx].trim_end_matches(':');
+        let raw_tag = parts[tag_idx].trim_end_matches(':');
+        let retraced_tag = self.retracer.as_ref().and_then(|r| r.retrace_tag(raw_tag));
+        let line_tag = retraced_tag.as_deref().unwrap_or(raw_tag);
         let is_fatal = line_level.eq_ignore_ascii_case("F");
-        let is_crash = line_level.eq_ignore_ascii_case("E") && Self::is_crash_tag(line_tag);
-        if !self.no_tag_filter && !is_fatal && !is_crash {
-            if self.tags.is_empty() || !self.tags.contains_tag(line_tag) {
-                return None;
-            }
+        if !self.no_tag_filter
+            && !is_fatal
+            && crash.is_none()
+            && (self.tags.is_empty() || !self.tags.contains_tag(line_tag))
+        {
+            return None;
+        }
+
+        Some(self.style_line(line, &parts, level_idx, tag_idx, crash))
+    }
+
+    /// Styles a stack-trace continuation line whose entry header already passed the
+    /// filter; the line itself is not filtered again.
+    pub fn style_continuation(&self, line: &str, crash: Option<CrashKind>) -> Option<StyledLine> {
+        if Self::looks_like_stack_trace(line) {
+            return Some(self.style_raw_frame(line));
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let (level_idx, tag_idx) = Self::get_level_and_tag_indices(&parts)?;
+        if parts.len() <= tag_idx {
+            return None;
+        }
+        Some(self.style_line(line, &parts, level_idx, tag_idx, crash))
+    }
+
+    /// Dim red, as a raw stack trace line has no level or tag to color by.
+    fn style_raw_frame(&self, line: &str) -> StyledLine {
+        let style = Style::default().fg(Color::Red).add_modifier(Modifier::DIM);
+        let retraced = self.retracer.as_ref().and_then(|r| r.retrace_message(line));
+        let shown = retraced.clone().unwrap_or_else(|| line.to_owned());
+        let mut styled = StyledLine::new(shown.clone(), vec![Span::styled(shown, style)]);
+        if Self::is_crash_framework_frame(styled.content.trim_start()) {
+            styled.role = LineRole::FrameworkFrame;
         }
+        styled.original = retraced.map(|_| line.to_owned());
+        styled
+    }
 
+    fn style_line(
+        &self,
+        line: &str,
+        parts: &[&str],
+        level_idx: usize,
+        tag_idx: usize,
+        crash: Option<CrashKind>,
+    ) -> StyledLine {
+        let raw_tag = parts[tag_idx].trim_end_matches(':');
+        let retraced_tag = self.retracer.as_ref().and_then(|r| r.retrace_tag(raw_tag));
+        let line_tag = retraced_tag.as_deref().unwrap_or(raw_tag);
         let dim_gray = Style::default().fg(Color::DarkGray);
         let mut spans: Vec<Span<'static>> = Vec::new();
+        let mut is_frame = false;
+        let mut retraced = retraced_tag.is_some();
 
         for (i, part) in parts.iter().enumerate() {
             if i < level_idx {
@@ -336,13 +620,30 @@ impl LogFilter {
                     Self::get_level_style(part),
                 ));
             } else if i == tag_idx {
-                spans.push(Span::styled(
-                    format!("{} ", part),
-                    self.get_tag_style(line_tag),
-                ));
-                let message = parts[tag_idx + 1..].join(" ");
-                if is_crash {
-                    spans.push(Self::colorize_crash_message(&message));
+                let tag_style = match crash {
+                    Some(kind) => Style::default()
+                        .fg(kind.color())
+                        .add_modifier(Modifier::BOLD),
+                    None => self.get_tag_style(line_tag),
+                };
+                let shown_tag = match &retraced_tag {
+                    Some(tag) if part.ends_with(':') => format!("{}: ", tag),
+                    Some(tag) => format!("{} ", tag),
+                    None => format!("{} ", part),
+                };
+                spans.push(Span::styled(shown_tag, tag_style));
+                let mut message = parts[tag_idx + 1..].join(" ");
+                if crash.is_some()
+                    && let Some(r) = &self.retracer
+                    && let Some(frame) = r.retrace_message(message.trim_start_matches(':'))
+                {
+                    message = frame;
+                    retraced = true;
+                }
+                is_frame = crash.is_some()
+                    && Self::is_crash_framework_frame(message.trim_start_matches(':').trim_start());
+                if let Some(kind) = crash {
+                    spans.push(Self::colorize_crash_message(&message, kind));
                 } else {
                     spans.extend(self.message_highlighter.highlight_message(&message));
                 }
@@ -351,12 +652,24 @@ impl LogFilter {
         }
 
         let content: String = spans.iter().map(|s| s.content.as_ref()).collect::<String>();
-        Some(StyledLine::new(content.trim().to_string(), spans))
+        let mut styled = StyledLine::new(content.trim().to_string(), spans);
+        styled.crash = crash;
+        if is_frame {
+            styled.role = LineRole::FrameworkFrame;
+        }
+        if retraced {
+            styled.original = Some(line.to_owned());
+        }
+        styled
     }
 
-    fn looks_like_stack_trace(line: &str) -> bool {
+    /// `at ...`, `Caused by:`, `Suppressed:` and `... N more` lines of a Java stack trace.
+    pub(crate) fn looks_like_stack_trace(line: &str) -> bool {
         let t = line.trim_start();
-        t.starts_with("at ") || t.starts_with("Caused by:") || t.starts_with("Suppressed:")
+        t.starts_with("at ")
+            || t.starts_with("Caused by:")
+            || t.starts_with("Suppressed:")
+            || (t.starts_with("... ") && t.ends_with(" more"))
     }
 
     fn get_level_and_tag_indices(parts: &[&str]) -> Option<(usize, usize)> {
@@ -427,7 +740,10 @@ mod tests {
             blacklisted_items: blacklist.into_iter().map(String::from).collect(),
             highlighted_items: vec![],
             show_items: show.into_iter().map(String::from).collect(),
+            processes: vec![],
             no_tag_filter,
+            kernel: true,
+            highlight: HighlightConfig::default(),
         })
     }
 
@@ -588,34 +904,208 @@ mod tests {
     }
 
     #[test]
-    fn matches_stack_trace_lines_pass_through() {
+    fn headerless_stack_trace_lines_do_not_match_on_their_own() {
         let filter = make_filter(vec![], vec![], vec![], vec![]);
         assert!(
             filter
                 .matches("at com.example.Foo.bar(Foo.kt:42)")
-                .is_some()
+                .is_none()
         );
         assert!(
             filter
                 .matches("\tat com.example.Foo.bar(Foo.kt:42)")
-                .is_some()
+                .is_none()
         );
         assert!(
             filter
                 .matches("Caused by: java.lang.NullPointerException")
-                .is_some()
+                .is_none()
         );
         assert!(filter.matches("--------- beginning of main").is_none());
     }
 
     #[test]
-    fn matches_stack_trace_lines_are_dim_red() {
+    fn stack_trace_continuations_are_dim_red() {
         let filter = make_filter(vec![], vec![], vec![], vec![]);
-        let result = filter.matches("at com.example.Foo.bar(Foo.kt:42)").unwrap();
+        let result = filter
+            .style_continuation("at com.example.Foo.bar(Foo.kt:42)", None)
+            .unwrap();
         let expected = Style::default().fg(Color::Red).add_modifier(Modifier::DIM);
         assert!(result.spans.iter().any(|s| s.style == expected));
     }
 
+    // --- crash kinds ---
+
+    #[test]
+    fn crash_kind_recognises_each_report() {
+        let kind = |line: &str| LogFilter::crash_kind(line, None);
+        assert_eq!(
+            kind("2024-01-15 10:30:45 1234 1234 E AndroidRuntime: FATAL EXCEPTION: main"),
+            Some(CrashKind::Java)
+        );
+        assert_eq!(
+            kind(
+                "2024-01-15 10:30:45 1234 1250 E AndroidRuntime: FATAL EXCEPTION: DefaultDispatcher-worker-2"
+            ),
+            Some(CrashKind::Coroutine)
+        );
+        assert_eq!(
+            kind("2024-01-15 10:30:45 900 950 E ActivityManager: ANR in com.example.nav"),
+            Some(CrashKind::Anr)
+        );
+        assert_eq!(
+            kind("2024-01-15 10:30:45 4321 4321 F DEBUG   : *** *** *** *** *** ***"),
+            Some(CrashKind::Native)
+        );
+        assert_eq!(
+            kind("2024-01-15 10:30:45 1234 1260 F libc    : Fatal signal 11 (SIGSEGV)"),
+            Some(CrashKind::Native)
+        );
+        assert_eq!(
+            kind("2024-01-15 10:30:45 900 950 E ActivityManager: Failure sending broadcast"),
+            None
+        );
+        assert_eq!(kind("2024-01-15 10:30:45 1234 1234 I Planner: ok"), None);
+    }
+
+    #[test]
+    fn crash_kind_continues_from_previous_line() {
+        let anr_detail =
+            "2024-01-15 10:30:45 900 950 E ActivityManager: Reason: Input dispatching timed out";
+        assert_eq!(
+            LogFilter::crash_kind(anr_detail, Some(CrashKind::Anr)),
+            Some(CrashKind::Anr)
+        );
+        let frame = "2024-01-15 10:30:45 1234 1250 E AndroidRuntime: \tat kotlinx.coroutines.DispatchedTask.run";
+        assert_eq!(
+            LogFilter::crash_kind(frame, Some(CrashKind::Coroutine)),
+            Some(CrashKind::Coroutine)
+        );
+        assert_eq!(LogFilter::crash_kind(frame, None), Some(CrashKind::Java));
+
+        let backtrace = "2024-01-15 10:30:45 4321 4321 F DEBUG   : #00 pc 000000000004f2a8 /system/lib64/libc.so (abort+12)";
+        assert_eq!(
+            LogFilter::crash_kind(backtrace, Some(CrashKind::Native)),
+            Some(CrashKind::Native)
+        );
+        assert_eq!(LogFilter::crash_kind(backtrace, None), None);
+    }
+
+    #[test]
+    fn app_error_under_debug_tag_is_not_a_native_crash() {
+        let line = "2024-01-15 10:30:45 1234 1234 E DEBUG   : request failed, retrying";
+        assert_eq!(LogFilter::crash_kind(line, None), None);
+        assert_eq!(LogFilter::crash_kind(line, Some(CrashKind::Java)), None);
+    }
+
+    #[test]
+    fn crash_lines_bypass_tag_filter() {
+        let filter = make_filter(vec!["E", "F"], vec!["Planner"], vec![], vec![]);
+        let anr = "2024-01-15 10:30:45 900 950 E ActivityManager: ANR in com.example.nav";
+        let native = "2024-01-15 10:30:45 1234 1260 F libc    : Fatal signal 11 (SIGSEGV)";
+        let other = "2024-01-15 10:30:45 900 950 E ActivityManager: Failure sending broadcast";
+        assert!(filter.matches(anr).is_some());
+        assert!(filter.matches(native).is_some());
+        assert!(filter.matches(other).is_none());
+    }
+
+    #[test]
+    fn crash_lines_use_kind_color() {
+        let filter = make_filter(vec!["E"], vec![], vec![], vec![]);
+        let line = "2024-01-15 10:30:45 900 950 E ActivityManager: ANR in com.example.nav";
+        let result = filter.matches(line).unwrap();
+        let headline = Style::default()
+            .fg(CrashKind::Anr.color())
+            .add_modifier(Modifier::BOLD);
+        assert!(
+            result
+                .spans
+                .iter()
+                .any(|s| s.style == headline && s.content.starts_with("ANR in"))
+        );
+    }
+
+    #[test]
+    fn retraces_crash_frames_and_tags_keeping_original() {
+        let retracer =
+            Retracer::parse("com.tomtom.nav.Router -> a.b:\n    1:3:void plan(int):40:42 -> c\n");
+        let filter = make_filter(vec!["E", "D"], vec!["Router"], vec![], vec![])
+            .with_retracer(Some(Arc::new(retracer)));
+
+        let frame = "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: \tat a.b.c(SourceFile:2)";
+        let result = filter.matches_crash(frame, Some(CrashKind::Java)).unwrap();
+        assert!(
+            result
+                .content
+                .ends_with("at com.tomtom.nav.Router.plan(Router.java:41)")
+        );
+        assert_eq!(result.original.as_deref(), Some(frame));
+
+        let tagged = filter
+            .matches("2024-01-15 10:30:46 1234 1234 D b: planning")
+            .unwrap();
+        assert!(tagged.content.contains("Router: planning"));
+        assert!(tagged.original.is_some());
+
+        let plain = "2024-01-15 10:30:46 1234 1234 E AndroidRuntime: \tat android.os.Looper.loop(Looper.java:1)";
+        let unchanged = filter.matches_crash(plain, Some(CrashKind::Java)).unwrap();
+        assert!(unchanged.original.is_none());
+    }
+
+    #[test]
+    fn crash_separator_names_the_kind() {
+        let separator = StyledLine::crash_separator(CrashKind::Native);
+        assert!(separator.content.starts_with("─── native crash ───"));
+        assert_eq!(
+            StyledLine::crash_separator(CrashKind::Java)
+                .content
+                .chars()
+                .count(),
+            61
+        );
+    }
+
+    #[test]
+    fn kernel_lines_get_level_k_and_their_own_toggle() {
+        let line = LogFilter::kernel_line("01-15 10:30:45.500     0     0 I gnss    : fix lost");
+        assert_eq!(line, "01-15 10:30:45.500     0     0 K gnss    : fix lost");
+        assert_eq!(LogFilter::kernel_line("no header"), "no header");
+
+        let mut filter = make_filter(vec!["E"], vec!["Nav"], vec![], vec![]);
+        let styled = filter.matches(&line).unwrap();
+        assert!(styled.content.contains("K gnss"));
+
+        filter.kernel = false;
+        assert!(filter.matches(&line).is_none());
+    }
+
+    #[test]
+    fn markers_pass_every_filter_as_separators() {
+        let filter = make_filter(vec!["E"], vec!["Nav"], vec!["adb"], vec!["crash"]);
+        let line = "─── adb disconnected 10:31:02 (gap 14.2 s) ───";
+        assert!(LogFilter::is_marker(line));
+        let styled = filter.matches(line).unwrap();
+        assert_eq!(styled.role, LineRole::Separator);
+        assert_eq!(styled.content, line);
+        assert!(!LogFilter::is_marker(
+            "2024-01-15 10:30:45 1234 5678 E Nav: ───"
+        ));
+    }
+
+    #[test]
+    fn parse_header_splits_threadtime_line() {
+        let header = LogFilter::parse_header(
+            "2024-01-15 10:30:45.123 +0000 1234 5678 E DEBUG   : *** *** ***",
+        )
+        .unwrap();
+        assert_eq!(header.time, "10:30:45.123");
+        assert_eq!(header.pid, Some("1234"));
+        assert_eq!(header.tid, Some("5678"));
+        assert_eq!(header.tag, "DEBUG");
+        assert_eq!(header.message, "*** *** ***");
+        assert!(LogFilter::parse_header("    at com.example.Foo.bar(Foo.kt:3)").is_none());
+    }
+
     #[test]
     fn matches_fatal_level_uses_background_red() {
         let filter = make_filter(vec!["F"], vec![], vec![], vec![]);
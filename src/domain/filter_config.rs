@@ -1,6 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use ratatui::style::{Color, Modifier, Style};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 use crate::application::cli::Args;
+use crate::domain::message_highlighter::HighlightPattern;
 use crate::shared::logger::Logger;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -12,21 +19,229 @@ pub enum TagCategory {
 }
 
 impl TagCategory {
+    /// Classifies `tag` with the built-in category patterns.
     pub fn classify(tag: &str) -> Self {
+        DEFAULT_CATEGORY_PATTERNS.classify(tag)
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT_CATEGORY_PATTERNS: CategoryPatterns = CategoryPatterns::default();
+}
+
+/// Case-insensitive tag substrings that put a tag into a category. Checked in
+/// priority order Routing > MapMatching > Guidance; anything else is Navigation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryPatterns {
+    pub routing: Vec<String>,
+    pub mapmatching: Vec<String>,
+    pub guidance: Vec<String>,
+}
+
+impl Default for CategoryPatterns {
+    fn default() -> Self {
+        let to_vec = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+        Self {
+            routing: to_vec(&["planner", "replan"]),
+            mapmatching: to_vec(&["match", "project"]),
+            guidance: to_vec(&["guidance", "warning"]),
+        }
+    }
+}
+
+impl CategoryPatterns {
+    pub fn classify(&self, tag: &str) -> TagCategory {
         let tag = tag.to_ascii_lowercase();
-        if tag.contains("planner") || tag.contains("replan") {
-            Self::Routing
-        } else if tag.contains("match") || tag.contains("project") {
-            Self::MapMatching
-        } else if tag.contains("guidance") || tag.contains("warning") {
-            Self::Guidance
+        let hit = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|p| tag.contains(&p.to_ascii_lowercase()))
+        };
+        if hit(&self.routing) {
+            TagCategory::Routing
+        } else if hit(&self.mapmatching) {
+            TagCategory::MapMatching
+        } else if hit(&self.guidance) {
+            TagCategory::Guidance
         } else {
-            Self::Navigation
+            TagCategory::Navigation
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Highlight word lists for the message body, plus the color of each bucket.
+/// Words are matched case-insensitively on word boundaries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HighlightConfig {
+    pub red: Vec<String>,
+    pub green: Vec<String>,
+    pub yellow: Vec<String>,
+    pub colors: HighlightColors,
+    /// Extra rules with their own style, e.g. `[[highlight.rules]]` in the config.
+    #[serde(default)]
+    pub rules: Vec<HighlightRuleConfig>,
+}
+
+/// A configured highlight rule. Exactly one of `term`, `phrase` or `regex` is set.
+/// `priority` decides overlaps against other rules and the built-in buckets
+/// (custom 0, green 10, yellow 20, red 30); the higher one wins.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HighlightRuleConfig {
+    /// Matched case-insensitively on word boundaries, like the word lists.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+    /// Matched case-insensitively anywhere, even inside words.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phrase: Option<String>,
+    /// Matched as written; prefix with `(?i)` to ignore case.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fg: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bg: Option<String>,
+    /// Any of bold, dim, italic, underlined, reversed, crossed_out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<String>,
+    pub priority: u8,
+}
+
+impl HighlightRuleConfig {
+    pub fn pattern(&self) -> Result<HighlightPattern, String> {
+        match (&self.term, &self.phrase, &self.regex) {
+            (Some(term), None, None) => {
+                Ok(HighlightPattern::Words(HashSet::from(
+                    [term.to_lowercase()],
+                )))
+            }
+            (None, Some(phrase), None) => Ok(HighlightPattern::Phrase(phrase.to_lowercase())),
+            (None, None, Some(regex)) => Regex::new(regex)
+                .map(HighlightPattern::Regex)
+                .map_err(|e| format!("invalid highlight regex '{}': {}", regex, e)),
+            _ => Err("highlight rule needs exactly one of term, phrase or regex".to_owned()),
+        }
+    }
+
+    pub fn style(&self) -> Result<Style, String> {
+        let color = |name: &str| {
+            Color::from_str(name).map_err(|_| format!("invalid highlight color '{}'", name))
+        };
+        let mut style = Style::default();
+        if let Some(fg) = &self.fg {
+            style = style.fg(color(fg)?);
+        }
+        if let Some(bg) = &self.bg {
+            style = style.bg(color(bg)?);
+        }
+        for name in &self.modifiers {
+            let modifier = match name.to_ascii_lowercase().as_str() {
+                "bold" => Modifier::BOLD,
+                "dim" => Modifier::DIM,
+                "italic" => Modifier::ITALIC,
+                "underlined" => Modifier::UNDERLINED,
+                "reversed" => Modifier::REVERSED,
+                "crossed_out" => Modifier::CROSSED_OUT,
+                _ => return Err(format!("invalid highlight modifier '{}'", name)),
+            };
+            style = style.add_modifier(modifier);
+        }
+        Ok(style)
+    }
+}
+
+/// Color names as understood by ratatui (`red`, `lightblue`, `#ff8800`, `208`, …).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HighlightColors {
+    pub red: String,
+    pub green: String,
+    pub yellow: String,
+    /// Background color for `-i` highlighted items.
+    pub custom: String,
+}
+
+impl Default for HighlightColors {
+    fn default() -> Self {
+        Self {
+            red: "red".to_owned(),
+            green: "green".to_owned(),
+            yellow: "yellow".to_owned(),
+            custom: "yellow".to_owned(),
+        }
+    }
+}
+
+impl Default for HighlightConfig {
+    fn default() -> Self {
+        let to_vec = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+        Self {
+            red: to_vec(&[
+                "error",
+                "removed",
+                "unfollowed",
+                "not followed",
+                "unvisited",
+                "deviation",
+                "off-road",
+                "off-route",
+            ]),
+            green: to_vec(&[
+                "success",
+                "created",
+                "added",
+                "followed",
+                "following",
+                "visited",
+                "planned",
+                "arrived",
+                "departed",
+                "started",
+                "starting",
+                "resumed",
+                "resuming",
+                "stopped",
+                "stopping",
+            ]),
+            yellow: to_vec(&[
+                "warning",
+                "updated",
+                "changed",
+                "segment",
+                "old",
+                "new",
+                "map matching",
+                "projected",
+                "matchlocation",
+                "matched",
+                "replan",
+                "should replan",
+                "refresh",
+                "back to route",
+                "replanning",
+                "language change",
+                "increment",
+                "progress",
+                "current location",
+                "distancealongroute",
+                "traffic jam",
+                "instruction",
+                "guidance",
+                "lane guidance",
+                "lane level guidance",
+                "route",
+                "waypoint",
+                "planning route",
+            ]),
+            colors: HighlightColors::default(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LevelState {
     pub verbose: bool,
     pub debug: bool,
@@ -59,7 +274,7 @@ impl LevelState {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn parse(s: &str) -> Self {
         let mut ls = Self {
             verbose: false,
             debug: false,
@@ -113,8 +328,12 @@ pub(crate) struct FilterConfig {
     pub blacklisted_items: Vec<String>,
     pub highlighted_items: Vec<String>,
     pub show_items: Vec<String>,
+    pub processes: Vec<String>,
     /// When true, empty tag list means "show all". When false, empty tag list means "show nothing".
     pub no_tag_filter: bool,
+    /// Show kernel lines (level `K`), which bypass the level and tag filters.
+    pub kernel: bool,
+    pub highlight: HighlightConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -124,11 +343,15 @@ pub struct TagCategories {
 
 impl TagCategories {
     pub fn new(tags: Vec<String>) -> Self {
+        Self::with_patterns(tags, &DEFAULT_CATEGORY_PATTERNS)
+    }
+
+    pub fn with_patterns(tags: Vec<String>, patterns: &CategoryPatterns) -> Self {
         Self {
             tags: tags
                 .into_iter()
                 .map(|t| {
-                    let cat = TagCategory::classify(&t);
+                    let cat = patterns.classify(&t);
                     (t, cat)
                 })
                 .collect(),
@@ -169,12 +392,14 @@ impl TagCategories {
 
 /// Runtime-mutable filter state. Holds the immutable parts set from CLI args plus
 /// the four category toggles that can be flipped at runtime in the TUI.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FilterState {
     pub level_state: LevelState,
     pub base_tags: Vec<String>,
     pub highlighted_items: Vec<String>,
     pub show_items: Vec<String>,
+    /// Lowercased process/thread name terms; non-empty shows only lines whose name contains one.
+    pub processes: Vec<String>,
     pub no_tag_filter: bool,
     /// true = show core navigation messages (progress, tracking, waypoints, …)
     pub navigation: bool,
@@ -184,15 +409,85 @@ pub struct FilterState {
     pub routing: bool,
     /// true = show map-matching messages
     pub mapmatching: bool,
+    /// true = show kernel log lines (level `K`)
+    pub kernel: bool,
+}
+
+/// Highlight rules and tag category patterns from the config. They shape what the
+/// filter state means but are not part of it, so undo never snapshots them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterRules {
+    pub highlight: HighlightConfig,
+    pub categories: CategoryPatterns,
+}
+
+/// Navigation SDK tags shown unless the config or `-t` names others.
+pub const DEFAULT_TAGS: &[&str] = &[
+    "DefaultTomTomNavigation",
+    "DistanceAlongRouteCalculator",
+    "ProgressCalculationStep",
+    "RouteTrackingStateStep",
+    "WaypointStatusCheckStep",
+    "DestinationArrivalCheckStep",
+    "DefaultRouteTrackingEngine",
+    "DefaultRouteProgressEngine",
+    "Replan",
+    "Warning",
+    "Guidance",
+    "Planner",
+    "Match",
+    "Project",
+];
+
+/// Levels shown unless the config or `-l` names others.
+pub const DEFAULT_LEVELS: &str = "I,D,E,W";
+
+/// Where the filters start before the command line: the built-in defaults, or
+/// the tags and levels of the config files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterDefaults {
+    pub tags: Vec<String>,
+    /// Comma-separated levels, same syntax as `-l`.
+    pub levels: String,
+}
+
+impl Default for FilterDefaults {
+    fn default() -> Self {
+        Self {
+            tags: DEFAULT_TAGS.iter().map(|t| t.to_string()).collect(),
+            levels: DEFAULT_LEVELS.to_owned(),
+        }
+    }
+}
+
+impl FilterDefaults {
+    /// `-l` and `-t` replace the levels and tags.
+    pub fn with_cli_overrides(mut self, args: &Args) -> Self {
+        if let Some(levels) = &args.logcat_levels {
+            self.levels = levels.clone();
+        }
+        if let Some(tags) = &args.tags {
+            self.tags = FilterConfig::to_tags(tags);
+        }
+        self
+    }
 }
 
 impl FilterState {
+    /// Builds the filter state from CLI args on top of the built-in defaults.
     pub fn from_args(args: &Args) -> Self {
-        let level_state = LevelState::from_str(&args.logcat_levels);
+        Self::from_defaults(args, FilterDefaults::default())
+    }
+
+    /// Builds the filter state from CLI args, falling back to `defaults` for
+    /// anything the command line leaves unset.
+    pub fn from_defaults(args: &Args, defaults: FilterDefaults) -> Self {
+        let defaults = defaults.with_cli_overrides(args);
+        let level_state = LevelState::parse(&defaults.levels);
         let mut base_tags = if args.no_tag_filter {
             vec![]
         } else {
-            FilterConfig::to_tags(&args.tags)
+            defaults.tags
         };
         base_tags.extend(
             args.add_tag
@@ -214,47 +509,59 @@ impl FilterState {
                 .iter()
                 .map(|s| s.to_ascii_lowercase())
                 .collect(),
+            processes: args
+                .process
+                .iter()
+                .map(|s| s.trim().to_ascii_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
             no_tag_filter: args.no_tag_filter,
             navigation: true,
             guidance: true,
             routing: true,
             mapmatching: true,
+            kernel: true,
         }
     }
 
-    pub(crate) fn to_filter_config(&self) -> FilterConfig {
+    /// Base tags whose category toggle is on.
+    pub(crate) fn enabled_tags(&self, categories: &CategoryPatterns) -> Vec<String> {
+        self.base_tags
+            .iter()
+            .filter(|tag| match categories.classify(tag) {
+                TagCategory::Navigation => self.navigation,
+                TagCategory::Guidance => self.guidance,
+                TagCategory::Routing => self.routing,
+                TagCategory::MapMatching => self.mapmatching,
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn to_filter_config(&self, rules: &FilterRules) -> FilterConfig {
         // Additive model: each toggle owns its tag bucket exclusively.
         // Tags are assigned to exactly one category by pattern, and only
         // tags in enabled categories are passed to the filter. This means
         // the visible set is the union of enabled categories — all off
         // produces an empty tag list, which the filter treats as "show nothing"
         // (contrast with no_tag_filter=true, which means "show everything").
-        let mut tags = Vec::new();
+        let tags = self.enabled_tags(&rules.categories);
         let mut blacklisted_items = Vec::new();
 
-        for tag in &self.base_tags {
-            let enabled = match TagCategory::classify(tag) {
-                TagCategory::Navigation => self.navigation,
-                TagCategory::Guidance => self.guidance,
-                TagCategory::Routing => self.routing,
-                TagCategory::MapMatching => self.mapmatching,
-            };
-            if enabled {
-                tags.push(tag.clone());
-            }
-        }
-
         if !self.guidance {
             blacklisted_items.extend(GUIDANCE_BLACKLIST.iter().map(|&s| s.to_string()));
         }
 
         FilterConfig {
             levels: self.level_state.to_levels(),
-            tags: TagCategories::new(tags),
+            tags: TagCategories::with_patterns(tags, &rules.categories),
             blacklisted_items,
             highlighted_items: self.highlighted_items.clone(),
             show_items: self.show_items.clone(),
+            processes: self.processes.clone(),
             no_tag_filter: self.no_tag_filter,
+            kernel: self.kernel,
+            highlight: rules.highlight.clone(),
         }
     }
 }
@@ -316,17 +623,11 @@ mod tests {
     #[test]
     fn from_args_drops_empty_add_tags_and_lowercases_show_items() {
         let args = Args {
-            file: None,
-            logcat_levels: "I".to_string(),
-            tags: "foo".to_string(),
+            logcat_levels: Some("I".to_string()),
+            tags: Some("foo".to_string()),
             add_tag: vec!["".to_string(), " Bar ".to_string()],
-            no_tag_filter: false,
-            serial: None,
-            debug_level: crate::application::cli::VerbosityLevel::None,
-            highlighted_items: vec![],
             show_items: vec!["Error".to_string()],
-            completions: None,
-            version: false,
+            ..Args::default()
         };
 
         let state = FilterState::from_args(&args);
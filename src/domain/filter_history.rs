@@ -0,0 +1,170 @@
+use crate::domain::filter_config::{FilterState, LevelState};
+
+const MAX_HISTORY: usize = 100;
+
+/// Everything the user can change at runtime that affects which lines are visible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterSnapshot {
+    pub filter_state: FilterState,
+    pub search_query: String,
+}
+
+/// Undo/redo stacks of filter snapshots. Each entry is the state *before* a change,
+/// so undoing swaps it with the current state and pushes the current one onto redo.
+#[derive(Debug, Default)]
+pub struct FilterHistory {
+    undo: Vec<FilterSnapshot>,
+    redo: Vec<FilterSnapshot>,
+}
+
+impl FilterHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the state before a change. A new change invalidates the redo stack.
+    pub fn record(&mut self, before: FilterSnapshot) {
+        self.undo.push(before);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self, current: FilterSnapshot) -> Option<FilterSnapshot> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: FilterSnapshot) -> Option<FilterSnapshot> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+}
+
+/// Short human-readable summary of what differs between two snapshots,
+/// e.g. `g:on r:off -V search:"timeout"`.
+pub fn describe_change(from: &FilterSnapshot, to: &FilterSnapshot) -> String {
+    let mut parts = Vec::new();
+    let (a, b) = (&from.filter_state, &to.filter_state);
+
+    for (key, was, now) in [
+        ('n', a.navigation, b.navigation),
+        ('g', a.guidance, b.guidance),
+        ('r', a.routing, b.routing),
+        ('m', a.mapmatching, b.mapmatching),
+        ('K', a.kernel, b.kernel),
+    ] {
+        if was != now {
+            parts.push(format!("{}:{}", key, if now { "on" } else { "off" }));
+        }
+    }
+
+    parts.extend(describe_levels(&a.level_state, &b.level_state));
+
+    if a.show_items != b.show_items {
+        parts.push(format!("show:[{}]", b.show_items.join(",")));
+    }
+    if a.processes != b.processes {
+        parts.push(format!("proc:[{}]", b.processes.join(",")));
+    }
+    if a.highlighted_items != b.highlighted_items {
+        parts.push(format!("highlight:[{}]", b.highlighted_items.join(",")));
+    }
+    if a.base_tags != b.base_tags || a.no_tag_filter != b.no_tag_filter {
+        parts.push("tags".to_owned());
+    }
+    if from.search_query != to.search_query {
+        if to.search_query.is_empty() {
+            parts.push("search cleared".to_owned());
+        } else {
+            parts.push(format!("search:\"{}\"", to.search_query));
+        }
+    }
+
+    if parts.is_empty() {
+        "no change".to_owned()
+    } else {
+        parts.join(" ")
+    }
+}
+
+fn describe_levels(from: &LevelState, to: &LevelState) -> Vec<String> {
+    [
+        ("V", from.verbose, to.verbose),
+        ("D", from.debug, to.debug),
+        ("I", from.info, to.info),
+        ("W", from.warn, to.warn),
+        ("E", from.error, to.error),
+        ("F", from.fatal, to.fatal),
+    ]
+    .into_iter()
+    .filter(|(_, was, now)| was != now)
+    .map(|(level, _, now)| format!("{}{}", if now { '+' } else { '-' }, level))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::cli::Args;
+
+    fn snapshot(routing: bool, query: &str) -> FilterSnapshot {
+        FilterSnapshot {
+            filter_state: FilterState {
+                routing,
+                ..FilterState::from_args(&Args::default())
+            },
+            search_query: query.to_owned(),
+        }
+    }
+
+    #[test]
+    fn undo_returns_previous_and_redo_returns_it_back() {
+        let mut history = FilterHistory::new();
+        history.record(snapshot(true, ""));
+
+        let undone = history.undo(snapshot(false, "")).unwrap();
+        assert!(undone.filter_state.routing);
+
+        let redone = history.redo(undone).unwrap();
+        assert!(!redone.filter_state.routing);
+    }
+
+    #[test]
+    fn new_change_clears_redo_stack() {
+        let mut history = FilterHistory::new();
+        history.record(snapshot(true, ""));
+        history.undo(snapshot(false, "")).unwrap();
+
+        history.record(snapshot(true, ""));
+
+        assert!(history.redo(snapshot(true, "x")).is_none());
+    }
+
+    #[test]
+    fn empty_history_undo_is_none() {
+        let mut history = FilterHistory::new();
+        assert!(history.undo(snapshot(true, "")).is_none());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = FilterHistory::new();
+        for _ in 0..MAX_HISTORY + 10 {
+            history.record(snapshot(true, ""));
+        }
+        assert_eq!(history.undo.len(), MAX_HISTORY);
+    }
+
+    #[test]
+    fn describe_change_lists_toggles_levels_and_search() {
+        let from = snapshot(true, "");
+        let mut to = snapshot(false, "timeout");
+        to.filter_state.level_state.verbose = true;
+
+        assert_eq!(describe_change(&from, &to), "r:off +V search:\"timeout\"");
+    }
+}
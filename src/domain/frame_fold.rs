@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+
+use crate::domain::filter::{LineRole, StyledLine};
+
+/// Which crash reports have their framework frames folded. Crashes are identified
+/// by the text of their first report line, which survives cache rebuilds and trims.
+#[derive(Debug, Clone)]
+pub struct FrameFolds {
+    fold_all: bool,
+    /// Crashes whose fold state differs from `fold_all`.
+    toggled: HashSet<String>,
+}
+
+impl Default for FrameFolds {
+    fn default() -> Self {
+        Self {
+            fold_all: true,
+            toggled: HashSet::new(),
+        }
+    }
+}
+
+impl FrameFolds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_folded(&self, crash: Option<&str>) -> bool {
+        self.fold_all != crash.is_some_and(|key| self.toggled.contains(key))
+    }
+
+    pub fn toggle(&mut self, crash: &str) {
+        if !self.toggled.remove(crash) {
+            self.toggled.insert(crash.to_owned());
+        }
+    }
+
+    /// Flips the default for every crash and forgets per-crash toggles.
+    pub fn toggle_all(&mut self) {
+        self.fold_all = !self.fold_all;
+        self.toggled.clear();
+    }
+
+    /// True if `line` should not be displayed given the fold state of its crash.
+    pub fn hides(&self, line: &StyledLine, crash: Option<&str>) -> bool {
+        match line.role {
+            LineRole::FrameworkFrame => self.is_folded(crash),
+            LineRole::FoldedFrames(_) => !self.is_folded(crash),
+            LineRole::Log | LineRole::Separator => false,
+        }
+    }
+}
+
+/// Follows the filtered lines in order and reports which crash each belongs to.
+#[derive(Debug, Clone, Default)]
+pub struct CrashTracker {
+    key: Option<String>,
+    awaiting_first_line: bool,
+}
+
+impl CrashTracker {
+    /// Advances past `line` and returns the key of the crash it belongs to.
+    pub fn advance(&mut self, line: &StyledLine) -> Option<&str> {
+        if line.is_crash_start() {
+            self.key = None;
+            self.awaiting_first_line = true;
+        } else if self.awaiting_first_line && line.crash.is_some() {
+            self.key = Some(line.content.clone());
+            self.awaiting_first_line = false;
+        } else if line.crash.is_none() && line.role == LineRole::Log {
+            self.key = None;
+            self.awaiting_first_line = false;
+        }
+        self.key.as_deref()
+    }
+}
+
+/// Appends `line` to `cache`, opening a folded-frames row before each run of
+/// framework frames and counting the run in it. `open_fold` is the index of the
+/// row for the run in progress.
+pub fn append_line(cache: &mut Vec<StyledLine>, open_fold: &mut Option<usize>, line: StyledLine) {
+    if line.role == LineRole::FrameworkFrame {
+        match *open_fold {
+            Some(index) => {
+                let LineRole::FoldedFrames(frames) = cache[index].role else {
+                    unreachable!("open fold points at a folded-frames row");
+                };
+                let (entry, device) = (cache[index].entry, cache[index].device);
+                cache[index] = StyledLine::folded_frames(frames + 1, cache[index].crash);
+                cache[index].entry = entry;
+                cache[index].device = device;
+            }
+            None => {
+                *open_fold = Some(cache.len());
+                let mut row = StyledLine::folded_frames(1, line.crash);
+                row.entry = line.entry;
+                row.device = line.device;
+                cache.push(row);
+            }
+        }
+    } else {
+        *open_fold = None;
+    }
+    cache.push(line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::filter::CrashKind;
+    use ratatui::text::Span;
+
+    fn line(content: &str, crash: Option<CrashKind>, role: LineRole) -> StyledLine {
+        StyledLine {
+            content: content.to_owned(),
+            spans: vec![Span::raw(content.to_owned())],
+            crash,
+            role,
+            original: None,
+            entry: 0,
+            device: 0,
+        }
+    }
+
+    fn crash(first: &str) -> Vec<StyledLine> {
+        let java = Some(CrashKind::Java);
+        vec![
+            StyledLine::crash_separator(CrashKind::Java),
+            line(first, java, LineRole::Log),
+            line("at android.os.Looper.loop", java, LineRole::FrameworkFrame),
+            line("at java.lang.Thread.run", java, LineRole::FrameworkFrame),
+            line("at com.tomtom.nav.Router.plan", java, LineRole::Log),
+        ]
+    }
+
+    fn build(lines: Vec<StyledLine>) -> Vec<StyledLine> {
+        let mut cache = Vec::new();
+        let mut open_fold = None;
+        for l in lines {
+            append_line(&mut cache, &mut open_fold, l);
+        }
+        cache
+    }
+
+    fn visible(cache: &[StyledLine], folds: &FrameFolds) -> Vec<String> {
+        let mut tracker = CrashTracker::default();
+        cache
+            .iter()
+            .filter(|l| !folds.hides(l, tracker.advance(l)))
+            .map(|l| l.content.clone())
+            .collect()
+    }
+
+    #[test]
+    fn framework_run_is_counted_in_one_fold_row() {
+        let cache = build(crash("FATAL EXCEPTION: main"));
+        assert_eq!(cache.len(), 6);
+        assert_eq!(cache[2].role, LineRole::FoldedFrames(2));
+        assert_eq!(cache[2].content.trim(), "… 2 framework frames");
+    }
+
+    #[test]
+    fn folded_crash_shows_row_instead_of_frames() {
+        let cache = build(crash("FATAL EXCEPTION: main"));
+        let shown = visible(&cache, &FrameFolds::new());
+        assert_eq!(shown.len(), 4);
+        assert!(shown.iter().any(|l| l.contains("2 framework frames")));
+        assert!(!shown.iter().any(|l| l.contains("android.os")));
+    }
+
+    #[test]
+    fn toggling_one_crash_leaves_others_folded() {
+        let mut lines = crash("FATAL EXCEPTION: main");
+        lines.extend(crash("FATAL EXCEPTION: worker"));
+        let cache = build(lines);
+        let mut folds = FrameFolds::new();
+
+        folds.toggle("FATAL EXCEPTION: worker");
+        let shown = visible(&cache, &folds);
+
+        assert_eq!(shown.iter().filter(|l| l.contains("android.os")).count(), 1);
+        assert_eq!(
+            shown
+                .iter()
+                .filter(|l| l.contains("framework frames"))
+                .count(),
+            1
+        );
+
+        folds.toggle_all();
+        let shown = visible(&cache, &folds);
+        assert_eq!(shown.iter().filter(|l| l.contains("android.os")).count(), 2);
+    }
+}
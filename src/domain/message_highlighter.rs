@@ -1,50 +1,124 @@
 use std::collections::HashSet;
 
+use aho_corasick::{AhoCorasick, MatchKind};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::Span;
+use regex::Regex;
 
 use crate::shared::logger::Logger;
 
+/// The built-in highlight buckets. Their ranks are spaced out so configured
+/// rules can slot in between them.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum HighlightPriority {
     Custom = 0,
-    Green = 1,
-    Yellow = 2,
-    Red = 3,
+    Green = 10,
+    Yellow = 20,
+    Red = 30,
 }
 
 impl HighlightPriority {
+    pub fn rank(self) -> u8 {
+        self as u8
+    }
+
     pub fn style(self) -> Style {
+        self.style_with(self.default_color())
+    }
+
+    fn default_color(self) -> Color {
+        match self {
+            Self::Red => Color::Red,
+            Self::Green => Color::Green,
+            Self::Yellow | Self::Custom => Color::Yellow,
+        }
+    }
+
+    /// The style of this priority with `color` in place of its default color.
+    pub fn style_with(self, color: Color) -> Style {
         match self {
-            Self::Red => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-            Self::Green => Style::default()
-                .fg(Color::Green)
-                .add_modifier(Modifier::BOLD),
-            Self::Yellow => Style::default().fg(Color::Yellow),
-            Self::Custom => Style::default().bg(Color::Yellow),
+            Self::Red | Self::Green => Style::default().fg(color).add_modifier(Modifier::BOLD),
+            Self::Yellow => Style::default().fg(color),
+            Self::Custom => Style::default().bg(color),
         }
     }
 }
 
+/// What a highlight rule looks for in a message.
+#[derive(Debug, Clone)]
+pub enum HighlightPattern {
+    /// Terms matched ASCII case-insensitively on word boundaries.
+    Words(HashSet<String>),
+    /// Text matched ASCII case-insensitively anywhere, even inside words.
+    Phrase(String),
+    /// Matched against the original message as written.
+    Regex(Regex),
+}
+
 #[derive(Debug, Clone)]
 struct HighlightRule {
-    terms: HashSet<String>,
-    priority: HighlightPriority,
+    pattern: HighlightPattern,
+    /// The built-in bucket this rule belongs to, if any; used by `set_color`.
+    bucket: Option<HighlightPriority>,
+    priority: u8,
+    style: Style,
 }
 
 #[derive(Debug, Clone)]
 struct Match {
     start: usize,
     end: usize,
-    priority: HighlightPriority,
+    priority: u8,
+    style: Style,
 }
 
 impl Match {
-    fn new(start: usize, end: usize, priority: HighlightPriority) -> Self {
+    fn new(start: usize, end: usize, priority: u8, style: Style) -> Self {
         Self {
             start,
             end,
             priority,
+            style,
+        }
+    }
+}
+
+/// Every term and phrase of every rule compiled into a single automaton, so a
+/// message is scanned once regardless of how many literals are configured.
+#[derive(Debug, Clone)]
+struct LiteralMatcher {
+    automaton: AhoCorasick,
+    /// Per automaton pattern: the index of its rule and whether it must sit on word boundaries.
+    patterns: Vec<(usize, bool)>,
+}
+
+impl LiteralMatcher {
+    fn compile(rules: &[HighlightRule]) -> Self {
+        let mut literals = Vec::new();
+        let mut patterns = Vec::new();
+        for (index, rule) in rules.iter().enumerate() {
+            match &rule.pattern {
+                HighlightPattern::Words(terms) => {
+                    for term in terms.iter().filter(|t| !t.is_empty()) {
+                        literals.push(term.as_str());
+                        patterns.push((index, true));
+                    }
+                }
+                HighlightPattern::Phrase(phrase) if !phrase.is_empty() => {
+                    literals.push(phrase.as_str());
+                    patterns.push((index, false));
+                }
+                HighlightPattern::Phrase(_) | HighlightPattern::Regex(_) => {}
+            }
+        }
+        let automaton = AhoCorasick::builder()
+            .ascii_case_insensitive(true)
+            .match_kind(MatchKind::Standard)
+            .build(literals)
+            .expect("highlight terms fit in an automaton");
+        Self {
+            automaton,
+            patterns,
         }
     }
 }
@@ -52,36 +126,43 @@ impl Match {
 #[derive(Debug, Clone)]
 pub struct MessageHighlighter {
     rules: Vec<HighlightRule>,
+    literals: LiteralMatcher,
 }
 
 struct MessageProcessor<'a> {
     message: &'a str,
-    message_lower: String,
 }
 
 impl<'a> MessageProcessor<'a> {
     fn new(message: &'a str) -> Self {
-        Self {
-            message,
-            message_lower: message.to_lowercase(),
-        }
+        Self { message }
     }
 
     fn find_matches(&self, highlighter: &MessageHighlighter) -> Vec<Match> {
         let mut matches = Vec::new();
 
-        Logger::debug_fmt("Checking message: {}", &[&self.message_lower]);
+        Logger::debug_fmt("Checking message: {}", &[&self.message]);
+
+        let literals = &highlighter.literals;
+        for found in literals.automaton.find_overlapping_iter(self.message) {
+            let (index, whole_word) = literals.patterns[found.pattern().as_usize()];
+            if whole_word && !self.is_complete_match(found.start(), found.len()) {
+                Logger::debug_fmt("Not a complete match at pos {}", &[&found.start()]);
+                continue;
+            }
+            let rule = &highlighter.rules[index];
+            matches.push(Match::new(
+                found.start(),
+                found.end(),
+                rule.priority,
+                rule.style,
+            ));
+        }
 
         for rule in &highlighter.rules {
-            for term in &rule.terms {
-                for (pos, _) in self.message_lower.match_indices(term.as_str()) {
-                    Logger::debug_fmt("Found term at pos {}", &[&pos]);
-                    if self.is_complete_match(pos, term.len()) {
-                        Logger::debug("Complete match!");
-                        matches.push(Match::new(pos, pos + term.len(), rule.priority));
-                    } else {
-                        Logger::debug("Not a complete match");
-                    }
+            if let HighlightPattern::Regex(regex) = &rule.pattern {
+                for m in regex.find_iter(self.message).filter(|m| !m.is_empty()) {
+                    matches.push(Match::new(m.start(), m.end(), rule.priority, rule.style));
                 }
             }
         }
@@ -115,10 +196,10 @@ impl<'a> MessageProcessor<'a> {
             }
         }
 
-        if !overlapping.is_empty() {
-            if let Some(best) = self.find_highest_priority_match(&overlapping) {
-                resolved.push(best);
-            }
+        if !overlapping.is_empty()
+            && let Some(best) = self.find_highest_priority_match(&overlapping)
+        {
+            resolved.push(best);
         }
 
         resolved
@@ -134,13 +215,13 @@ impl<'a> MessageProcessor<'a> {
         };
 
         let starts_at_boundary = pos == 0
-            || self.message_lower[..pos]
+            || self.message[..pos]
                 .chars()
                 .next_back()
                 .is_none_or(is_word_boundary);
 
-        let ends_at_boundary = pos + word_len == self.message_lower.len()
-            || self.message_lower[pos + word_len..]
+        let ends_at_boundary = pos + word_len == self.message.len()
+            || self.message[pos + word_len..]
                 .chars()
                 .next()
                 .is_none_or(is_word_boundary);
@@ -158,7 +239,7 @@ impl<'a> MessageProcessor<'a> {
             }
             spans.push(Span::styled(
                 self.message[m.start..m.end].to_owned(),
-                m.priority.style(),
+                m.style,
             ));
             last_end = m.end;
         }
@@ -178,28 +259,43 @@ impl MessageHighlighter {
         yellow_words: HashSet<String>,
         custom_words: HashSet<String>,
     ) -> Self {
+        let bucket = |terms, priority: HighlightPriority| HighlightRule {
+            pattern: HighlightPattern::Words(terms),
+            bucket: Some(priority),
+            priority: priority.rank(),
+            style: priority.style(),
+        };
+        let rules = vec![
+            bucket(red_words, HighlightPriority::Red),
+            bucket(yellow_words, HighlightPriority::Yellow),
+            bucket(green_words, HighlightPriority::Green),
+            bucket(custom_words, HighlightPriority::Custom),
+        ];
         Self {
-            rules: vec![
-                HighlightRule {
-                    terms: red_words,
-                    priority: HighlightPriority::Red,
-                },
-                HighlightRule {
-                    terms: yellow_words,
-                    priority: HighlightPriority::Yellow,
-                },
-                HighlightRule {
-                    terms: green_words,
-                    priority: HighlightPriority::Green,
-                },
-                HighlightRule {
-                    terms: custom_words,
-                    priority: HighlightPriority::Custom,
-                },
-            ],
+            literals: LiteralMatcher::compile(&rules),
+            rules,
+        }
+    }
+
+    /// Recolors every rule of the given priority.
+    pub fn set_color(&mut self, priority: HighlightPriority, color: Color) {
+        for rule in self.rules.iter_mut().filter(|r| r.bucket == Some(priority)) {
+            rule.style = priority.style_with(color);
         }
     }
 
+    /// Adds a rule with its own style. On overlap the match with the higher
+    /// `priority` wins; compare with `HighlightPriority::rank` of the built-in buckets.
+    pub fn add_rule(&mut self, pattern: HighlightPattern, priority: u8, style: Style) {
+        self.rules.push(HighlightRule {
+            pattern,
+            bucket: None,
+            priority,
+            style,
+        });
+        self.literals = LiteralMatcher::compile(&self.rules);
+    }
+
     pub fn highlight_message(&self, message: &str) -> Vec<Span<'static>> {
         let processor = MessageProcessor::new(message);
         let matches = processor.find_matches(self);
@@ -285,6 +381,79 @@ mod tests {
         assert!(!has_style(&result, HighlightPriority::Red));
     }
 
+    #[test]
+    fn set_color_changes_style_but_keeps_priority() {
+        let mut h = make_highlighter(&["error"], &[], &["error"], &[]);
+        h.set_color(HighlightPriority::Red, Color::LightMagenta);
+        let result = h.highlight_message("error occurred");
+        let expected = HighlightPriority::Red.style_with(Color::LightMagenta);
+        assert!(result.iter().any(|s| s.style == expected));
+        assert!(!has_style(&result, HighlightPriority::Yellow));
+    }
+
+    #[test]
+    fn regex_rule_above_red_wins_overlap() {
+        let mut h = make_highlighter(&["route_id"], &[], &[], &[]);
+        let style = Style::default().fg(Color::Cyan);
+        h.add_rule(
+            HighlightPattern::Regex(Regex::new("route_id=[0-9a-f-]+").unwrap()),
+            HighlightPriority::Red.rank() + 1,
+            style,
+        );
+        let result = h.highlight_message("replan route_id=3fa2-b1 done");
+        assert!(
+            result
+                .iter()
+                .any(|s| s.style == style && s.content == "route_id=3fa2-b1")
+        );
+        assert!(!has_style(&result, HighlightPriority::Red));
+    }
+
+    #[test]
+    fn rule_below_builtin_loses_overlap() {
+        let mut h = make_highlighter(&["error"], &[], &[], &[]);
+        let style = Style::default().add_modifier(Modifier::ITALIC);
+        h.add_rule(
+            HighlightPattern::Regex(Regex::new(r"error \d+").unwrap()),
+            HighlightPriority::Green.rank(),
+            style,
+        );
+        let result = h.highlight_message("error 42");
+        assert!(has_style(&result, HighlightPriority::Red));
+        assert!(!result.iter().any(|s| s.style == style));
+    }
+
+    #[test]
+    fn phrase_matches_inside_words() {
+        let mut h = make_highlighter(&[], &[], &[], &[]);
+        let style = Style::default().fg(Color::Blue);
+        h.add_rule(HighlightPattern::Phrase("fold".to_owned()), 5, style);
+        let result = h.highlight_message("Unfolded map");
+        assert!(
+            result
+                .iter()
+                .any(|s| s.style == style && s.content == "fold")
+        );
+    }
+
+    #[test]
+    fn matching_ignores_case_and_keeps_original_text() {
+        let h = make_highlighter(&["error"], &[], &[], &[]);
+        let result = h.highlight_message("Fatal ERROR in Planner");
+        assert!(
+            result
+                .iter()
+                .any(|s| s.content == "ERROR" && s.style == HighlightPriority::Red.style())
+        );
+    }
+
+    #[test]
+    fn overlapping_terms_of_one_rule_are_all_found() {
+        let h = make_highlighter(&[], &[], &["lane guidance", "guidance"], &[]);
+        let result = h.highlight_message("lane guidance then guidance");
+        assert_eq!(count_style(&result, HighlightPriority::Yellow), 2);
+    }
+
     #[test]
     fn exact_word_is_highlighted() {
         let h = make_highlighter(&["old"], &[], &[], &[]);
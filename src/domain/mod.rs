@@ -1,3 +1,12 @@
+pub mod crash_capture;
+pub mod crash_index;
+pub mod device_filter;
+pub mod entry_group;
 pub mod filter;
 pub mod filter_config;
+pub mod filter_history;
+pub mod frame_fold;
 pub mod message_highlighter;
+pub mod preset;
+pub mod process_names;
+pub mod retrace;
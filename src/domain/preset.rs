@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::filter_config::{FilterState, LevelState};
+
+/// A named, saved filter setup. Every field is optional so a hand-written preset
+/// only needs to mention what it changes; missing fields keep their current value.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preset {
+    /// Comma-separated levels, same syntax as `-l` (e.g. "D,I,W,E").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub levels: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_tag_filter: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_items: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processes: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlighted_items: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub navigation: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guidance: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mapmatching: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+}
+
+impl Preset {
+    pub fn from_state(state: &FilterState, search: &str) -> Self {
+        Self {
+            levels: Some(state.level_state.to_levels().join(",")),
+            tags: Some(state.base_tags.clone()),
+            no_tag_filter: Some(state.no_tag_filter),
+            show_items: Some(state.show_items.clone()),
+            processes: Some(state.processes.clone()),
+            highlighted_items: Some(state.highlighted_items.clone()),
+            navigation: Some(state.navigation),
+            guidance: Some(state.guidance),
+            routing: Some(state.routing),
+            mapmatching: Some(state.mapmatching),
+            kernel: Some(state.kernel),
+            search: (!search.is_empty()).then(|| search.to_owned()),
+        }
+    }
+
+    /// Overlays this preset onto `state` and returns the search query to use.
+    pub fn apply_to(&self, state: &mut FilterState) -> String {
+        if let Some(levels) = &self.levels {
+            state.level_state = LevelState::parse(levels);
+        }
+        if let Some(tags) = &self.tags {
+            state.base_tags = tags.clone();
+        }
+        if let Some(no_tag_filter) = self.no_tag_filter {
+            state.no_tag_filter = no_tag_filter;
+        }
+        if let Some(show_items) = &self.show_items {
+            state.show_items = show_items.iter().map(|s| s.to_ascii_lowercase()).collect();
+        }
+        if let Some(processes) = &self.processes {
+            state.processes = processes.iter().map(|s| s.to_ascii_lowercase()).collect();
+        }
+        if let Some(highlighted_items) = &self.highlighted_items {
+            state.highlighted_items = highlighted_items.clone();
+        }
+        if let Some(on) = self.navigation {
+            state.navigation = on;
+        }
+        if let Some(on) = self.guidance {
+            state.guidance = on;
+        }
+        if let Some(on) = self.routing {
+            state.routing = on;
+        }
+        if let Some(on) = self.mapmatching {
+            state.mapmatching = on;
+        }
+        if let Some(on) = self.kernel {
+            state.kernel = on;
+        }
+        self.search.clone().unwrap_or_default()
+    }
+}
+
+/// Contents of a presets file: a `[presets.<name>]` table per preset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresetFile {
+    #[serde(default)]
+    pub presets: BTreeMap<String, Preset>,
+}
+
+impl PresetFile {
+    /// Loads presets from `path`. A missing file is an empty preset list.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text)
+                .map_err(|e| format!("invalid presets file {}: {}", path.display(), e).into()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.presets.keys().cloned().collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.presets.get(name)
+    }
+
+    pub fn insert(&mut self, name: String, preset: Preset) {
+        self.presets.insert(name, preset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::cli::Args;
+
+    fn state() -> FilterState {
+        FilterState {
+            guidance: false,
+            mapmatching: false,
+            ..FilterState::from_args(&Args {
+                tags: Some("Replan,Planner".to_owned()),
+                highlighted_items: vec!["timeout".to_owned()],
+                ..Args::default()
+            })
+        }
+    }
+
+    #[test]
+    fn round_trips_state_and_search_through_toml() {
+        let mut file = PresetFile::default();
+        file.insert("replan".to_owned(), Preset::from_state(&state(), "timeout"));
+
+        let text = toml::to_string_pretty(&file).unwrap();
+        let parsed = PresetFile::parse(&text).unwrap();
+
+        let mut restored = FilterState {
+            base_tags: vec![],
+            guidance: true,
+            ..state()
+        };
+        let search = parsed.get("replan").unwrap().apply_to(&mut restored);
+        assert_eq!(restored, state());
+        assert_eq!(search, "timeout");
+    }
+
+    #[test]
+    fn hand_written_preset_only_overrides_given_fields() {
+        let parsed = PresetFile::parse(
+            r#"
+            [presets.jitter]
+            levels = "V,D"
+            show_items = ["MatchLocation"]
+            "#,
+        )
+        .unwrap();
+
+        let mut s = state();
+        let search = parsed.get("jitter").unwrap().apply_to(&mut s);
+
+        assert!(s.level_state.verbose && s.level_state.debug && !s.level_state.info);
+        assert_eq!(s.show_items, vec!["matchlocation"]);
+        assert_eq!(s.base_tags, state().base_tags);
+        assert!(search.is_empty());
+    }
+
+    #[test]
+    fn missing_file_loads_as_empty() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = PresetFile::load(&dir.path().join("nope.toml")).unwrap();
+        assert!(file.names().is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use crate::domain::filter::LogFilter;
+
+/// Process and thread names of one device, keyed by pid and tid. Filled from
+/// `ps -A -T` and from `ActivityManager: Start proc` lines; a pid keeps its last
+/// known name after the process exits so older lines stay labelled. Threads come
+/// and go far more often, so only those of the latest listing are kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessNames {
+    processes: HashMap<u32, String>,
+    threads: HashMap<u32, String>,
+}
+
+impl ProcessNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the process names in a `ps -A -T` listing and replaces the thread
+    /// names with its threads. The header names the columns:
+    /// `NAME` (the process name) is used when present, otherwise the `CMD` of the
+    /// thread whose tid is the pid. `CMD` is last and may contain spaces.
+    /// Returns true if any pid got a new name.
+    pub fn update_from_ps(&mut self, output: &str) -> bool {
+        let mut lines = output.lines();
+        let Some(header) = lines.next() else {
+            return false;
+        };
+        let columns: Vec<&str> = header.split_whitespace().collect();
+        let column = |name: &str| columns.iter().position(|c| *c == name);
+        let (Some(pid_col), Some(cmd_col)) = (column("PID"), column("CMD")) else {
+            return false;
+        };
+        if cmd_col + 1 != columns.len() {
+            return false;
+        }
+        let tid_col = column("TID");
+        let name_col = column("NAME");
+
+        let mut changed = false;
+        let mut threads = HashMap::new();
+        for line in lines {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() <= cmd_col {
+                continue;
+            }
+            let Ok(pid) = fields[pid_col].parse::<u32>() else {
+                continue;
+            };
+            let tid = match tid_col {
+                Some(col) => match fields[col].parse::<u32>() {
+                    Ok(tid) => tid,
+                    Err(_) => continue,
+                },
+                None => pid,
+            };
+            let cmd = fields[cmd_col..].join(" ");
+            let process = match name_col {
+                Some(col) => Some(fields[col]),
+                None => (tid == pid).then_some(cmd.as_str()),
+            };
+            if let Some(process) = process {
+                changed |= self.set_process(pid, process);
+            }
+            threads.insert(tid, cmd);
+        }
+        self.threads = threads;
+        changed
+    }
+
+    /// Learns the pid of a process from an `ActivityManager` `Start proc` line,
+    /// either `Start proc 12345:com.example.nav:navigation/u0a123 for service …`
+    /// or the older `Start proc com.example.nav for activity …: pid=12345 …`.
+    /// Returns true if the line named a pid.
+    pub fn observe_line(&mut self, line: &str) -> bool {
+        let Some(header) = LogFilter::parse_header(line) else {
+            return false;
+        };
+        if header.tag != "ActivityManager" {
+            return false;
+        }
+        let Some((pid, name)) = parse_start_proc(&header.message) else {
+            return false;
+        };
+        self.set_process(pid, name);
+        true
+    }
+
+    pub fn process(&self, pid: u32) -> Option<&str> {
+        self.processes.get(&pid).map(String::as_str)
+    }
+
+    pub fn thread(&self, tid: u32) -> Option<&str> {
+        self.threads.get(&tid).map(String::as_str)
+    }
+
+    /// `process/thread` for a logcat line's pid and tid, or just the process for
+    /// its main thread; `None` while the pid is unknown or the line has no header.
+    pub fn label(&self, line: &str) -> Option<String> {
+        let header = LogFilter::parse_header(line)?;
+        let pid: u32 = header.pid?.parse().ok()?;
+        let process = self.process(pid)?;
+        let tid: u32 = header.tid?.parse().ok()?;
+        match self.thread(tid) {
+            Some(thread) if tid != pid => Some(format!("{}/{}", process, thread)),
+            _ => Some(process.to_owned()),
+        }
+    }
+
+    fn set_process(&mut self, pid: u32, name: &str) -> bool {
+        if self.process(pid) == Some(name) {
+            return false;
+        }
+        self.processes.insert(pid, name.to_owned());
+        true
+    }
+}
+
+fn parse_start_proc(message: &str) -> Option<(u32, &str)> {
+    let rest = message.split_once("Start proc ")?.1;
+    let token = rest.split_whitespace().next()?;
+    if let Some((pid, name)) = token.split_once(':')
+        && let Ok(pid) = pid.parse()
+    {
+        let name = name.split('/').next()?;
+        return (!name.is_empty()).then_some((pid, name));
+    }
+    let pid = rest
+        .split_once("pid=")?
+        .1
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    Some((pid, token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PS: &str = "\
+USER           PID   TID  PPID     VSZ    RSS WCHAN            ADDR S CMD
+root             1     1     0 10915408 12345 do_epoll_wait       0 S init
+u0_a123      12345 12345   678 15000000 90000 do_epoll_wait       0 S e.nav:navigation
+u0_a123      12345 12360   678 15000000 90000 futex_wait_queue    0 S Jit thread pool
+";
+
+    #[test]
+    fn ps_listing_names_processes_by_their_main_thread() {
+        let mut names = ProcessNames::new();
+        assert!(names.update_from_ps(PS));
+        assert!(!names.update_from_ps(PS));
+
+        assert_eq!(names.process(1), Some("init"));
+        assert_eq!(names.process(12345), Some("e.nav:navigation"));
+        assert_eq!(names.thread(12360), Some("Jit thread pool"));
+    }
+
+    #[test]
+    fn each_ps_listing_replaces_the_threads_but_keeps_exited_processes() {
+        let mut names = ProcessNames::new();
+        names.update_from_ps(PS);
+        names.update_from_ps(
+            "USER           PID   TID  PPID     VSZ    RSS WCHAN            ADDR S CMD\n\
+             root             1     1     0 10915408 12345 do_epoll_wait       0 S init\n",
+        );
+
+        assert_eq!(names.thread(12360), None);
+        assert_eq!(names.thread(1), Some("init"));
+        assert_eq!(names.process(12345), Some("e.nav:navigation"));
+    }
+
+    #[test]
+    fn ps_name_column_gives_the_full_process_name() {
+        let mut names = ProcessNames::new();
+        names.update_from_ps(
+            "  PID   TID NAME                       CMD\n\
+             12345 12345 com.example.nav:navigation e.nav:navigation\n\
+             12345 12360 com.example.nav:navigation Planner\n",
+        );
+
+        assert_eq!(names.process(12345), Some("com.example.nav:navigation"));
+        assert_eq!(
+            names
+                .label("01-15 10:30:45.123 12345 12360 I Planner: route")
+                .as_deref(),
+            Some("com.example.nav:navigation/Planner")
+        );
+        assert_eq!(
+            names
+                .label("01-15 10:30:45.123 12345 12345 I Nav: start")
+                .as_deref(),
+            Some("com.example.nav:navigation")
+        );
+    }
+
+    #[test]
+    fn start_proc_lines_name_new_processes() {
+        let mut names = ProcessNames::new();
+        assert!(names.observe_line(
+            "01-15 10:30:45.123  1500  1530 I ActivityManager: Start proc 12345:com.example.nav:navigation/u0a123 for service {com.example.nav/.NavService}"
+        ));
+        assert!(names.observe_line(
+            "01-15 10:30:46.123  1500  1530 I ActivityManager: Start proc com.example.maps for activity com.example.maps/.Main: pid=23456 uid=10124 gids={}"
+        ));
+        assert!(!names.observe_line("01-15 10:30:47.123  1500  1530 I Planner: Start proc 1:x"));
+
+        assert_eq!(names.process(12345), Some("com.example.nav:navigation"));
+        assert_eq!(names.process(23456), Some("com.example.maps"));
+        assert_eq!(names.process(1), None);
+    }
+
+    #[test]
+    fn unknown_pid_has_no_label() {
+        let names = ProcessNames::new();
+        assert_eq!(
+            names.label("01-15 10:30:45.123 12345 12360 I Planner: route"),
+            None
+        );
+        assert_eq!(names.label("at com.example.Foo.bar(Foo.java:1)"), None);
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    // `1:5:void method(int):10:14 -> a`; the line ranges are optional.
+    static ref MEMBER: Regex = Regex::new(
+        r"^(?:(\d+):(\d+):)?\S+\s+([^\s(]+)\([^)]*\)(?::(\d+)(?::(\d+))?)?\s+->\s+(\S+)$"
+    )
+    .unwrap();
+    // `at a.b.c(SourceFile:12)`
+    static ref FRAME: Regex =
+        Regex::new(r"^at ([\w$.]+)\.([\w$<>-]+)\(([^:)]*)(?::(\d+))?\)$").unwrap();
+    // `# {"id":"sourceFile","fileName":"Router.kt"}`
+    static ref SOURCE_FILE: Regex =
+        Regex::new(r#""id"\s*:\s*"sourceFile".*"fileName"\s*:\s*"([^"]+)""#).unwrap();
+}
+
+#[derive(Debug, Clone)]
+struct MethodMapping {
+    /// Obfuscated line range this entry covers, if the mapping has one.
+    lines: Option<(u32, u32)>,
+    /// Set when the method was inlined from another class.
+    class: Option<String>,
+    name: String,
+    original_start: Option<u32>,
+    original_end: Option<u32>,
+}
+
+impl MethodMapping {
+    fn covers(&self, line: Option<u32>) -> bool {
+        match (self.lines, line) {
+            (Some((start, end)), Some(line)) => (start..=end).contains(&line),
+            (None, _) => true,
+            (Some(_), None) => false,
+        }
+    }
+
+    fn original_line(&self, line: u32) -> u32 {
+        match (self.lines, self.original_start, self.original_end) {
+            (Some((start, _)), Some(original), Some(_)) => original + line.saturating_sub(start),
+            (_, Some(original), _) => original,
+            _ => line,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ClassMapping {
+    original: String,
+    methods: HashMap<String, Vec<MethodMapping>>,
+}
+
+/// An R8/ProGuard `mapping.txt`, used to restore class names, methods and line
+/// numbers in obfuscated stack frames and tags.
+#[derive(Debug, Clone, Default)]
+pub struct Retracer {
+    /// Keyed by obfuscated class name.
+    classes: HashMap<String, ClassMapping>,
+    /// Keyed by original class name.
+    source_files: HashMap<String, String>,
+    /// Obfuscated simple name to original simple name; `None` when ambiguous.
+    simple_names: HashMap<String, Option<String>>,
+}
+
+impl Retracer {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read mapping {}: {}", path.display(), e))?;
+        Ok(Self::parse(&text))
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut retracer = Self::default();
+        let mut current: Option<String> = None;
+
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            if trimmed.starts_with('#') {
+                if let (Some(obfuscated), Some(caps)) = (&current, SOURCE_FILE.captures(trimmed)) {
+                    let original = retracer.classes[obfuscated].original.clone();
+                    retracer.source_files.insert(original, caps[1].to_owned());
+                }
+                continue;
+            }
+            if !line.starts_with(char::is_whitespace) {
+                // `com.example.Router -> a.b:`
+                current = trimmed
+                    .strip_suffix(':')
+                    .and_then(|l| l.split_once(" -> "))
+                    .map(|(original, obfuscated)| {
+                        retracer.add_class(original.trim(), obfuscated.trim());
+                        obfuscated.trim().to_owned()
+                    });
+                continue;
+            }
+            let (Some(obfuscated), Some(caps)) = (&current, MEMBER.captures(trimmed)) else {
+                continue; // fields and anything unrecognised
+            };
+            let number = |i: usize| caps.get(i).and_then(|m| m.as_str().parse().ok());
+            let (class, name) = match caps[3].rsplit_once('.') {
+                Some((class, name)) => (Some(class.to_owned()), name.to_owned()),
+                None => (None, caps[3].to_owned()),
+            };
+            let method = MethodMapping {
+                lines: number(1).zip(number(2)),
+                class,
+                name,
+                original_start: number(4),
+                original_end: number(5),
+            };
+            if let Some(mapping) = retracer.classes.get_mut(obfuscated) {
+                mapping
+                    .methods
+                    .entry(caps[6].to_owned())
+                    .or_default()
+                    .push(method);
+            }
+        }
+        retracer
+    }
+
+    fn add_class(&mut self, original: &str, obfuscated: &str) {
+        let simple = |name: &str| name.rsplit('.').next().unwrap_or(name).to_owned();
+        self.simple_names
+            .entry(simple(obfuscated))
+            .and_modify(|existing| *existing = None)
+            .or_insert_with(|| Some(simple(original)));
+        self.classes.insert(
+            obfuscated.to_owned(),
+            ClassMapping {
+                original: original.to_owned(),
+                methods: HashMap::new(),
+            },
+        );
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    pub fn retrace_class(&self, obfuscated: &str) -> Option<&str> {
+        self.classes.get(obfuscated).map(|c| c.original.as_str())
+    }
+
+    /// Original simple class name for a tag that is an obfuscated class name,
+    /// full or simple. Ambiguous simple names are left alone.
+    pub fn retrace_tag(&self, tag: &str) -> Option<String> {
+        if let Some(original) = self.retrace_class(tag) {
+            return Some(original.rsplit('.').next().unwrap_or(original).to_owned());
+        }
+        self.simple_names.get(tag).cloned().flatten()
+    }
+
+    /// Retraces `at a.b.c(SourceFile:12)`. For inlined code, this is the innermost frame.
+    pub fn retrace_frame(&self, frame: &str) -> Option<String> {
+        let caps = FRAME.captures(frame)?;
+        let class = self.classes.get(&caps[1])?;
+        let line: Option<u32> = caps.get(4).and_then(|m| m.as_str().parse().ok());
+        let method = class
+            .methods
+            .get(&caps[2])
+            .and_then(|candidates| candidates.iter().find(|m| m.covers(line)));
+
+        let Some(method) = method else {
+            // Known class, unknown method: restore the class name only.
+            let location = match line {
+                Some(line) => format!("{}:{}", &caps[3], line),
+                None => caps[3].to_owned(),
+            };
+            return Some(format!("at {}.{}({})", class.original, &caps[2], location));
+        };
+        let original_class = method.class.as_deref().unwrap_or(&class.original);
+        let file = self.source_file(original_class);
+        Some(match line {
+            Some(line) => format!(
+                "at {}.{}({}:{})",
+                original_class,
+                method.name,
+                file,
+                method.original_line(line)
+            ),
+            None => format!("at {}.{}({})", original_class, method.name, file),
+        })
+    }
+
+    fn source_file(&self, class: &str) -> String {
+        self.source_files.get(class).cloned().unwrap_or_else(|| {
+            let simple = class.rsplit('.').next().unwrap_or(class);
+            format!("{}.java", simple.split('$').next().unwrap_or(simple))
+        })
+    }
+
+    /// Retraces a stack frame or exception line (`a.b: msg`, `Caused by: a.b: msg`).
+    /// Returns `None` when nothing in the message is obfuscated.
+    pub fn retrace_message(&self, message: &str) -> Option<String> {
+        let trimmed = message.trim_start();
+        let indent = &message[..message.len() - trimmed.len()];
+        if trimmed.starts_with("at ") {
+            return self
+                .retrace_frame(trimmed)
+                .map(|frame| format!("{}{}", indent, frame));
+        }
+        let (prefix, rest) = match trimmed.strip_prefix("Caused by: ") {
+            Some(rest) => ("Caused by: ", rest),
+            None => ("", trimmed),
+        };
+        let class = rest.split(':').next().unwrap_or(rest).trim_end();
+        let original = self.retrace_class(class)?;
+        Some(format!(
+            "{}{}{}{}",
+            indent,
+            prefix,
+            original,
+            &rest[class.len()..]
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPPING: &str = "\
+# compiler: R8
+com.tomtom.nav.Router -> a.b:
+# {\"id\":\"sourceFile\",\"fileName\":\"Router.kt\"}
+    int retries -> a
+    1:3:void plan(int):40:42 -> c
+    4:4:void com.tomtom.nav.Graph.search():17:17 -> c
+    void reset() -> d
+com.tomtom.nav.RouteException -> a.c:
+com.tomtom.nav.Graph -> b.c:
+";
+
+    #[test]
+    fn retraces_frame_line_numbers_and_source_file() {
+        let r = Retracer::parse(MAPPING);
+        assert_eq!(
+            r.retrace_frame("at a.b.c(SourceFile:2)").as_deref(),
+            Some("at com.tomtom.nav.Router.plan(Router.kt:41)")
+        );
+        assert_eq!(
+            r.retrace_frame("at a.b.c(SourceFile:4)").as_deref(),
+            Some("at com.tomtom.nav.Graph.search(Graph.java:17)")
+        );
+        assert_eq!(
+            r.retrace_frame("at android.os.Looper.loop(Looper.java:1)"),
+            None
+        );
+    }
+
+    #[test]
+    fn retraces_exception_lines_keeping_message() {
+        let r = Retracer::parse(MAPPING);
+        assert_eq!(
+            r.retrace_message("Caused by: a.c: no route").as_deref(),
+            Some("Caused by: com.tomtom.nav.RouteException: no route")
+        );
+        assert_eq!(
+            r.retrace_message("\tat a.b.d(SourceFile)").as_deref(),
+            Some("\tat com.tomtom.nav.Router.reset(Router.kt)")
+        );
+        assert_eq!(
+            r.retrace_message("java.lang.IllegalStateException: x"),
+            None
+        );
+    }
+
+    #[test]
+    fn retraces_unambiguous_tags_only() {
+        let r = Retracer::parse(MAPPING);
+        assert_eq!(r.retrace_tag("a.b").as_deref(), Some("Router"));
+        assert_eq!(r.retrace_tag("b").as_deref(), Some("Router"));
+        // `c` is the simple name of both a.c and b.c.
+        assert_eq!(r.retrace_tag("c"), None);
+        assert_eq!(r.retrace_tag("Planner"), None);
+    }
+}
@@ -1,14 +1,14 @@
 use clap::Parser;
 use std::error::Error;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::Arc;
 
 const ZSH_COMPLETION: &str = r#"#compdef navcat
 
 _navcat() {
   _arguments \
     '(-f --file)'{-f,--file}'[Load a logcat file into the TUI]:file:_files -g "*.txt(-.)"' \
-    '--serial[Target adb device serial for live mode]:serial' \
+    '*--serial[Target adb device serial for live mode; repeat for several devices]:serial' \
     '(-l --logcat-levels)'{-l,--logcat-levels}'[Log levels to show, comma-separated (V/D/I/W/E/F)]:levels' \
     '(-t --tags)'{-t,--tags}'[Override the default tag filter list]:tags' \
     '(-a --add-tag)'{-a,--add-tag}'[Add tags on top of the default list]:tag' \
@@ -16,17 +16,43 @@ _navcat() {
     '--debug-level[navcat internal debug logging]:level:(none error info debug)' \
     '(-i --highlighted-items)'{-i,--highlighted-items}'[Terms to highlight, comma-separated]:items' \
     '(-s --show-items)'{-s,--show-items}'[Only show lines containing these terms, comma-separated]:items' \
+    '--process[Only show lines from processes or threads with these names, comma-separated]:names' \
+    '--preset[Load a named filter preset at startup]:preset' \
+    '--presets-file[Presets file to load and save named filter presets]:file:_files -g "*.toml(-.)"' \
+    '--buffers[Logcat buffers to read, comma-separated]:buffers:_values -s , buffer main system crash events radio kernel default all' \
+    '--history[Log from before startup: line count, all, or since=<time>]:history' \
+    '--device-filter[Apply exact tags and levels on the device with logcat filterspecs]' \
+    '--kernel[Also stream the kernel log as level K lines]' \
+    '--adb-server=-[Talk to the adb server directly instead of running adb]::address' \
+    '--cmd[Stream a shell command'"'"'s stdout instead of adb logcat]:command:_cmdstring' \
+    '--package[App package for the force-stop and start device actions]:package' \
+    '--overflow[What to do with lines while the screen falls behind]:policy:(block drop-oldest drop)' \
+    '--mapping[R8 mapping.txt to retrace obfuscated crashes]:file:_files -g "*.txt(-.)"' \
+    '--crash-dir[Write the log around every crash to this directory]:directory:_files -/' \
+    '--crash-window[Log kept around each captured crash, lines or seconds (30s)]:window' \
     '--help[Show help]' \
+    '1::command:(config)' \
     '--version[Show version]'
 }
 
 _navcat "$@"
 "#;
 
-use navcat::application::adb::{check_adb_available, check_device_connected, spawn_logcat};
-use navcat::application::cli::{Args, VerbosityLevel};
-use navcat::application::tui::run_tui;
+use navcat::application::adb::{
+    DeviceEntry, LogcatOptions, check_adb_available, check_device_connected, check_listed_device,
+    device_info, list_devices,
+};
+use navcat::application::adb_client::AdbClient;
+use navcat::application::cli::{Args, Command, ConfigAction, VerbosityLevel};
+use navcat::application::config::Config;
+use navcat::application::device_picker::pick_device;
+use navcat::application::source::{AdbSource, CommandSource, FileSource, LogSource};
+use navcat::application::tui::{TuiOptions, labeled_device_info, run_tui};
+use navcat::domain::crash_capture::CrashCapture;
+use navcat::domain::device_filter::DeviceFilter;
 use navcat::domain::filter_config::FilterState;
+use navcat::domain::preset::PresetFile;
+use navcat::domain::retrace::Retracer;
 use navcat::shared::logger::Logger;
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -57,25 +83,180 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
-    let filter_state = FilterState::from_args(&args);
+    let config_paths = Config::default_paths();
+    let config = Config::load(&config_paths)?;
 
-    match &args.file {
-        Some(file_path) => {
-            Logger::info_fmt("Reading from file:", &[&file_path]);
-            let lines: Vec<String> = BufReader::new(File::open(file_path)?)
-                .lines()
-                .collect::<Result<_, _>>()?;
-            run_tui(None, None, filter_state, lines)
+    if let Some(Command::Config {
+        action: ConfigAction::Show,
+    }) = &args.command
+    {
+        for path in &config_paths {
+            let status = if path.exists() { "loaded" } else { "not found" };
+            println!("# {} ({})", path.display(), status);
         }
-        None => {
-            check_adb_available()?;
-            check_device_connected(args.serial.as_deref())?;
+        print!("{}", config.clone().with_cli_overrides(&args).to_toml()?);
+        return Ok(());
+    }
 
+    let mut filter_state = FilterState::from_defaults(&args, config.filter_defaults());
+    let presets_path = Path::new(&args.presets_file);
+    let mut initial_search = String::new();
+    if let Some(name) = &args.preset {
+        let presets = PresetFile::load(presets_path)?;
+        let preset = presets.get(name).ok_or_else(|| {
+            format!(
+                "Preset '{}' not found in {}. Available: {}",
+                name,
+                presets_path.display(),
+                presets.names().join(", ")
+            )
+        })?;
+        initial_search = preset.apply_to(&mut filter_state);
+    }
+    let retracer = match &args.mapping {
+        Some(path) => Some(Arc::new(Retracer::load(Path::new(path))?)),
+        None => None,
+    };
+    // Live mode settles on its devices first, so crash captures can record which ones.
+    let server = args.adb_server.clone().map(AdbClient::new);
+    let serials: Vec<Option<String>> = if args.file.is_some() || args.cmd.is_some() {
+        vec![]
+    } else {
+        match &server {
+            Some(server) => {
+                server.version()?;
+            }
+            None => check_adb_available()?,
+        }
+        if args.serial.is_empty() {
+            let serial = choose_device(server.as_ref())?;
+            check_device(server.as_ref(), serial.as_deref())?;
+            vec![serial]
+        } else {
+            for serial in &args.serial {
+                check_device(server.as_ref(), Some(serial))?;
+            }
+            args.serial.iter().cloned().map(Some).collect()
+        }
+    };
+    let labels = match &args.cmd {
+        Some(_) => vec!["cmd".to_owned()],
+        None => device_labels(server.as_ref(), &serials),
+    };
+    let device_filter = (args.device_filter && args.file.is_none())
+        .then(|| DeviceFilter::from_state(&filter_state, &config.categories));
+    // Read at connect time for the header pane, saved dumps and crash captures,
+    // and again by the TUI whenever a device comes back after a disconnect.
+    let device_info: Vec<Vec<(String, String)>> = serials
+        .iter()
+        .map(|serial| device_info(serial.as_deref(), server.as_ref(), args.package.as_deref()))
+        .collect();
+    let crash_capture = args.crash_dir.as_ref().map(|dir| {
+        let device = match (&args.file, &args.cmd) {
+            (Some(file_path), _) => vec![("file".to_owned(), file_path.clone())],
+            (None, Some(command)) => vec![("command".to_owned(), command.clone())],
+            (None, None) => labeled_device_info(&labels, &device_info),
+        };
+        CrashCapture::new(dir.into(), args.crash_window, device)
+    });
+    let options = TuiOptions {
+        presets_path: presets_path.to_path_buf(),
+        initial_search,
+        buffer_size: config.buffer_size,
+        rules: config.filter_rules(),
+        keys: config.keys,
+        retracer,
+        crash_capture,
+        devices: labels,
+        device_info,
+        device_filter: device_filter.clone(),
+    };
+
+    let mut source: Box<dyn LogSource> = match (&args.file, &args.cmd) {
+        (Some(file_path), _) => {
+            Logger::info_fmt("Reading from file:", &[&file_path]);
+            Box::new(FileSource::new(file_path))
+        }
+        (None, command) => {
             Logger::set_log_file("/tmp/navcat.log")
                 .unwrap_or_else(|e| eprintln!("Warning: could not open log file: {}", e));
+            match command {
+                Some(command) => Box::new(CommandSource::new(command).with_overflow(args.overflow)),
+                None => Box::new(
+                    AdbSource::new(
+                        serials,
+                        LogcatOptions {
+                            buffers: args.buffers.clone(),
+                            history: args.history.clone(),
+                            filterspecs: device_filter
+                                .map(|filter| filter.filterspecs())
+                                .unwrap_or_default(),
+                            kernel: args.kernel,
+                            server,
+                            overflow: args.overflow,
+                        },
+                    )
+                    .with_package(args.package.clone()),
+                ),
+            }
+        }
+    };
+    run_tui(source.as_mut(), filter_state, options)
+}
 
-            let logcat = spawn_logcat(args.serial.as_deref())?;
-            run_tui(Some(logcat), None, filter_state, vec![])
+/// Short names for the status bar and device column: the model when no other
+/// device shares it, otherwise the serial.
+fn device_labels(server: Option<&AdbClient>, serials: &[Option<String>]) -> Vec<String> {
+    let models: Vec<Option<String>> = match serials {
+        [_, _, ..] => {
+            let devices = attached_devices(server).unwrap_or_default();
+            serials
+                .iter()
+                .map(|serial| {
+                    devices
+                        .iter()
+                        .find(|d| Some(&d.serial) == serial.as_ref())
+                        .and_then(|d| d.model.clone())
+                })
+                .collect()
         }
+        _ => vec![None; serials.len()],
+    };
+    serials
+        .iter()
+        .zip(&models)
+        .map(|(serial, model)| match model {
+            Some(model) if models.iter().filter(|m| m.as_ref() == Some(model)).count() == 1 => {
+                model.clone()
+            }
+            _ => serial.clone().unwrap_or_else(|| "device".to_owned()),
+        })
+        .collect()
+}
+
+/// Lists devices through the adb server when `--adb-server` is given, else `adb devices -l`.
+fn attached_devices(server: Option<&AdbClient>) -> Result<Vec<DeviceEntry>, Box<dyn Error>> {
+    match server {
+        Some(server) => Ok(server.devices()?),
+        None => list_devices(),
+    }
+}
+
+fn check_device(server: Option<&AdbClient>, serial: Option<&str>) -> Result<(), Box<dyn Error>> {
+    match server {
+        Some(server) => check_listed_device(&server.devices()?, serial),
+        None => check_device_connected(serial),
+    }
+}
+
+/// Opens the device picker when several devices are ready and no `--serial` was given.
+fn choose_device(server: Option<&AdbClient>) -> Result<Option<String>, Box<dyn Error>> {
+    let devices = attached_devices(server)?;
+    if devices.iter().filter(|d| d.is_ready()).count() < 2 {
+        return Ok(None);
+    }
+    match pick_device(devices, || attached_devices(server))? {
+        Some(serial) => Ok(Some(serial)),
+        None => Err("No device selected.".into()),
     }
 }
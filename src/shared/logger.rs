@@ -43,48 +43,48 @@ impl Logger {
     }
 
     pub fn info_fmt(msg: &str, args: &[&dyn std::fmt::Debug]) {
-        if let Ok(logger) = LOGGER.lock() {
-            if logger.level >= LogLevel::Info {
-                let line = match args.len() {
-                    0 => format!("INFO: {}\n", msg),
-                    1 => format!("INFO: {} {:?}\n", msg, args[0]),
-                    2 => format!("INFO: {} {:?} {:?}\n", msg, args[0], args[1]),
-                    _ => format!("INFO: {}\n", msg),
-                };
-                log_output(&line);
-            }
+        if let Ok(logger) = LOGGER.lock()
+            && logger.level >= LogLevel::Info
+        {
+            let line = match args.len() {
+                0 => format!("INFO: {}\n", msg),
+                1 => format!("INFO: {} {:?}\n", msg, args[0]),
+                2 => format!("INFO: {} {:?} {:?}\n", msg, args[0], args[1]),
+                _ => format!("INFO: {}\n", msg),
+            };
+            log_output(&line);
         }
     }
 
     pub fn debug(msg: &str) {
-        if let Ok(logger) = LOGGER.lock() {
-            if logger.level >= LogLevel::Debug {
-                log_output(&format!("DEBUG: {}\n", msg));
-            }
+        if let Ok(logger) = LOGGER.lock()
+            && logger.level >= LogLevel::Debug
+        {
+            log_output(&format!("DEBUG: {}\n", msg));
         }
     }
 
     pub fn debug_fmt(msg: &str, args: &[&dyn std::fmt::Debug]) {
-        if let Ok(logger) = LOGGER.lock() {
-            if logger.level >= LogLevel::Debug {
-                let line = match args.len() {
-                    0 => format!("DEBUG: {}\n", msg),
-                    1 => format!("DEBUG: {} {:?}\n", msg, args[0]),
-                    2 => format!("DEBUG: {} {:?} {:?}\n", msg, args[0], args[1]),
-                    _ => format!("DEBUG: {}\n", msg),
-                };
-                log_output(&line);
-            }
+        if let Ok(logger) = LOGGER.lock()
+            && logger.level >= LogLevel::Debug
+        {
+            let line = match args.len() {
+                0 => format!("DEBUG: {}\n", msg),
+                1 => format!("DEBUG: {} {:?}\n", msg, args[0]),
+                2 => format!("DEBUG: {} {:?} {:?}\n", msg, args[0], args[1]),
+                _ => format!("DEBUG: {}\n", msg),
+            };
+            log_output(&line);
         }
     }
 }
 
 fn log_output(line: &str) {
-    if let Ok(mut file_opt) = LOG_FILE.lock() {
-        if let Some(ref mut file) = *file_opt {
-            let _ = file.write_all(line.as_bytes());
-            return;
-        }
+    if let Ok(mut file_opt) = LOG_FILE.lock()
+        && let Some(ref mut file) = *file_opt
+    {
+        let _ = file.write_all(line.as_bytes());
+        return;
     }
     print!("{}", line);
 }
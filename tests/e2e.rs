@@ -1,14 +1,24 @@
 use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 
-use navcat::application::adb::{LogcatEvent, check_device_connected, spawn_logcat};
-use navcat::application::cli::{Args, VerbosityLevel};
+use navcat::application::adb::{
+    History, LogcatOptions, MultiLogcat, check_device_connected, process_table, run_device_action,
+    spawn_multi_command, spawn_multi_logcat,
+};
+use navcat::application::adb_client::{AdbClient, AdbError};
+use navcat::application::cli::Args;
+use navcat::application::ingest::Overflow;
+use navcat::application::source::{DeviceAction, DeviceEvent, DeviceState, LogcatEvent};
 use navcat::domain::filter::LogFilter;
-use navcat::domain::filter_config::FilterState;
+use navcat::domain::filter_config::{FilterRules, FilterState};
 use tempfile::TempDir;
 
 fn env_lock() -> &'static Mutex<()> {
@@ -36,6 +46,15 @@ case "${1:-}" in
     fi
     ;;
   logcat)
+    case " $* " in
+      *" -b kernel "*)
+        trap 'exit 0' TERM INT
+        printf '%s\n' '01-15 10:30:45.500     0     0 I gnss    : fix lost'
+        while :; do
+          sleep 1
+        done
+        ;;
+    esac
     count_file="$STATE_DIR/logcat_count"
     count=0
     if [ -f "$count_file" ]; then
@@ -43,6 +62,7 @@ case "${1:-}" in
     fi
     count=$((count + 1))
     echo "$count" > "$count_file"
+    echo "$*" > "$STATE_DIR/logcat_args_$count"
 
     if [ "$count" -eq 1 ]; then
       printf '%s\n' '01-15 10:30:45.123 1234 5678 I FirstTag: first'
@@ -51,11 +71,22 @@ case "${1:-}" in
 
     echo $$ > "$STATE_DIR/restart_pid"
     trap 'exit 0' TERM INT
+    if [ -n "${NAVCAT_TEST_REPLAY:-}" ]; then
+      printf '%s\n' '01-15 10:30:45.123 1234 5678 I FirstTag: first'
+    fi
     printf '%s\n' '01-15 10:30:46.123 1234 5678 I SecondTag: second'
     while :; do
       sleep 1
     done
     ;;
+  -s)
+    shift 2
+    echo "$*" >> "$STATE_DIR/shell_commands"
+    case "$*" in
+      "shell monkey"*) echo "Events injected: 1" ;;
+      "shell setprop log.tag.Planner LOUD") echo "setprop: failed to set property" ;;
+    esac
+    ;;
   *)
     echo "unexpected adb command: $*" >&2
     exit 1
@@ -70,19 +101,109 @@ esac
     path
 }
 
+/// A fake adb server on a free local port. It knows one device, `emulator-5554`.
+/// Its first logcat ends after one line and later ones stay open; every shell
+/// command is recorded. Its first device tracking stream ends after four device
+/// lists, as when the server restarts; later ones report the device and stay open.
+fn fake_adb_server() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let commands = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&commands);
+    let trackers = Arc::new(AtomicUsize::new(0));
+    std::thread::spawn(move || {
+        for stream in listener.incoming().map_while(Result::ok) {
+            let commands = Arc::clone(&recorded);
+            let trackers = Arc::clone(&trackers);
+            std::thread::spawn(move || serve_fake_adb(stream, &commands, &trackers));
+        }
+    });
+    (addr, commands)
+}
+
+fn serve_fake_adb(mut stream: TcpStream, commands: &Mutex<Vec<String>>, trackers: &AtomicUsize) {
+    fn read_request(stream: &mut TcpStream) -> Option<String> {
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len).ok()?;
+        let len = usize::from_str_radix(std::str::from_utf8(&len).ok()?, 16).ok()?;
+        let mut body = vec![0u8; len];
+        stream.read_exact(&mut body).ok()?;
+        String::from_utf8(body).ok()
+    }
+    fn reply(stream: &mut TcpStream, status: &str, message: &str) {
+        let _ = write!(stream, "{}{:04x}{}", status, message.len(), message);
+    }
+
+    let Some(request) = read_request(&mut stream) else {
+        return;
+    };
+    match request.as_str() {
+        "host:version" => reply(&mut stream, "OKAY", "0029"),
+        "host:track-devices" => {
+            let _ = stream.write_all(b"OKAY");
+            if trackers.fetch_add(1, Ordering::SeqCst) > 0 {
+                let devices = "emulator-5554\tdevice\n";
+                let _ = write!(stream, "{:04x}{}", devices.len(), devices);
+                let _ = stream.read(&mut [0u8; 1]);
+                return;
+            }
+            // Plugged in, unplugged, back unauthorized, then authorized.
+            for devices in [
+                "emulator-5554\tdevice\n",
+                "",
+                "emulator-5554\tunauthorized\n",
+                "emulator-5554\tdevice\n",
+            ] {
+                let _ = write!(stream, "{:04x}{}", devices.len(), devices);
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+        "host:devices-l" => reply(
+            &mut stream,
+            "OKAY",
+            "emulator-5554          device product:sdk_gphone64 model:Pixel_7 device:emu64xa\n",
+        ),
+        "host:transport:emulator-5554" | "host:transport-any" => {
+            let _ = stream.write_all(b"OKAY");
+            let Some(shell) = read_request(&mut stream) else {
+                return;
+            };
+            let _ = stream.write_all(b"OKAY");
+            let count = {
+                let mut commands = commands.lock().unwrap();
+                commands.push(shell.trim_start_matches("shell:").to_string());
+                commands.len()
+            };
+            if count == 1 {
+                let _ = stream.write_all(b"01-15 10:30:45.123 1234 5678 I FirstTag: first\r\n");
+                return;
+            }
+            let _ = stream.write_all(b"01-15 10:30:45.123 1234 5678 I FirstTag: first\r\n");
+            let _ = stream.write_all(b"01-15 10:30:46.123 1234 5678 I SecondTag: second\r\n");
+            // Stay open until the client hangs up.
+            let _ = stream.read(&mut [0u8; 1]);
+        }
+        other => {
+            let serial = other.strip_prefix("host:transport:").unwrap_or(other);
+            reply(
+                &mut stream,
+                "FAIL",
+                &format!("device '{}' not found", serial),
+            )
+        }
+    }
+}
+
+/// The next event of the single stream `handle` reads.
+fn recv_event(handle: &MultiLogcat, timeout: Duration) -> Result<LogcatEvent, RecvTimeoutError> {
+    handle.receiver().recv_timeout(timeout).map(|e| e.event)
+}
+
 fn base_args(tags: &str) -> Args {
     Args {
-        file: None,
-        logcat_levels: "I".to_string(),
-        tags: tags.to_string(),
-        add_tag: vec![],
-        no_tag_filter: false,
-        serial: None,
-        debug_level: VerbosityLevel::None,
-        highlighted_items: vec![],
-        show_items: vec![],
-        completions: None,
-        version: false,
+        logcat_levels: Some("I".to_string()),
+        tags: Some(tags.to_string()),
+        ..Args::default()
     }
 }
 
@@ -90,7 +211,7 @@ fn base_args(tags: &str) -> Args {
 fn lowercase_tags_work_through_cli_to_filter_pipeline() {
     let args = base_args("guidance,replan");
     let state = FilterState::from_args(&args);
-    let filter = LogFilter::from_state(&state);
+    let filter = LogFilter::from_state(&state, &FilterRules::default());
 
     let guidance = "2024-01-15 10:30:45 1234 5678 I LaneGuidance: hello";
     let replan = "2024-01-15 10:30:46 1234 5678 I ReplanEngine: hello";
@@ -112,28 +233,22 @@ fn logcat_reconnects_and_shutdown_kills_restarted_child() {
     }
 
     check_device_connected(None).unwrap();
-    let mut handle = spawn_logcat(None).unwrap();
+    let mut handle = spawn_multi_logcat(&[None], &LogcatOptions::default()).unwrap();
 
     assert!(matches!(
-        handle.receiver().recv_timeout(Duration::from_secs(1)).unwrap(),
+        recv_event(&handle, Duration::from_secs(1)).unwrap(),
         LogcatEvent::Line(line) if line.contains("FirstTag")
     ));
     assert!(matches!(
-        handle
-            .receiver()
-            .recv_timeout(Duration::from_secs(2))
-            .unwrap(),
+        recv_event(&handle, Duration::from_secs(2)).unwrap(),
         LogcatEvent::Disconnected
     ));
     assert!(matches!(
-        handle
-            .receiver()
-            .recv_timeout(Duration::from_secs(2))
-            .unwrap(),
+        recv_event(&handle, Duration::from_secs(2)).unwrap(),
         LogcatEvent::Connected
     ));
     assert!(matches!(
-        handle.receiver().recv_timeout(Duration::from_secs(1)).unwrap(),
+        recv_event(&handle, Duration::from_secs(1)).unwrap(),
         LogcatEvent::Line(line) if line.contains("SecondTag")
     ));
 
@@ -172,6 +287,290 @@ fn logcat_reconnects_and_shutdown_kills_restarted_child() {
     panic!("restarted adb child was not terminated by shutdown");
 }
 
+/// True while `pid` exists and is not a zombie waiting to be reaped.
+fn process_running(pid: &str) -> bool {
+    fs::read_to_string(format!("/proc/{}/stat", pid))
+        .ok()
+        .and_then(|stat| {
+            let state = stat.rsplit_once(") ")?.1.chars().next()?;
+            Some(state != 'Z')
+        })
+        .unwrap_or(false)
+}
+
+#[test]
+fn command_source_restarts_and_shutdown_kills_its_process_group() {
+    let dir = TempDir::new().unwrap();
+    let state = dir.path().display();
+    let command = format!(
+        "if [ -e {state}/started ]; then \
+             sleep 30 & echo $! > {state}/sleep_pid; \
+             echo '2024-01-15 10:30:46 1234 5678 I SecondTag: again'; wait; \
+         else \
+             touch {state}/started; \
+             echo '2024-01-15 10:30:45 1234 5678 I FirstTag: first'; \
+         fi"
+    );
+    let mut handle = spawn_multi_command(&[command], Overflow::Block).unwrap();
+
+    let next = || recv_event(&handle, Duration::from_secs(3)).unwrap();
+    assert!(matches!(next(), LogcatEvent::Line(line) if line.contains("FirstTag")));
+    assert!(matches!(next(), LogcatEvent::Disconnected));
+    assert!(matches!(next(), LogcatEvent::Connected));
+    assert!(matches!(next(), LogcatEvent::Line(line) if line.contains("SecondTag")));
+
+    let sleep_pid = fs::read_to_string(dir.path().join("sleep_pid")).unwrap();
+    let sleep_pid = sleep_pid.trim();
+    assert!(process_running(sleep_pid));
+    handle.shutdown();
+
+    for _ in 0..20 {
+        if !process_running(sleep_pid) {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    panic!("process started by the command outlived shutdown");
+}
+
+#[test]
+fn drop_policy_keeps_the_queued_lines_and_counts_the_rest() {
+    let mut handle =
+        spawn_multi_command(&["seq 1 12000; sleep 30".to_owned()], Overflow::Drop).unwrap();
+
+    for _ in 0..50 {
+        if handle.receiver().dropped() == 2000 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    assert_eq!(handle.receiver().dropped(), 2000);
+    let lines: Vec<String> = handle
+        .receiver()
+        .try_iter()
+        .filter_map(|DeviceEvent { event, .. }| match event {
+            LogcatEvent::Line(line) => Some(line),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(lines.len(), 10_000);
+    assert_eq!(lines.first().map(String::as_str), Some("1"));
+    assert_eq!(lines.last().map(String::as_str), Some("10000"));
+    handle.shutdown();
+}
+
+#[test]
+fn kernel_log_is_merged_by_time_at_level_k() {
+    let _guard = env_lock().lock().unwrap();
+    let dir = TempDir::new().unwrap();
+    let adb = fake_adb_script(dir.path());
+
+    unsafe {
+        std::env::set_var("NAVCAT_ADB", &adb);
+        std::env::set_var("NAVCAT_TEST_STATE_DIR", dir.path());
+    }
+
+    let options = LogcatOptions {
+        kernel: true,
+        ..LogcatOptions::default()
+    };
+    let mut handle = spawn_multi_logcat(&[None], &options).unwrap();
+    let mut lines = Vec::new();
+    while let Ok(event) = recv_event(&handle, Duration::from_secs(3)) {
+        if let LogcatEvent::Line(line) = event {
+            let done = line.contains("SecondTag");
+            lines.push(line);
+            if done {
+                break;
+            }
+        }
+    }
+    handle.shutdown();
+
+    unsafe {
+        std::env::remove_var("NAVCAT_ADB");
+        std::env::remove_var("NAVCAT_TEST_STATE_DIR");
+    }
+    assert_eq!(lines.len(), 3, "{:?}", lines);
+    assert!(lines[0].contains("FirstTag"));
+    assert_eq!(
+        lines[1],
+        "01-15 10:30:45.500     0     0 K gnss    : fix lost"
+    );
+    assert!(lines[2].contains("SecondTag"));
+}
+
+#[test]
+fn logcat_restart_resumes_from_last_timestamp_without_duplicates() {
+    let _guard = env_lock().lock().unwrap();
+    let dir = TempDir::new().unwrap();
+    let adb = fake_adb_script(dir.path());
+
+    unsafe {
+        std::env::set_var("NAVCAT_ADB", &adb);
+        std::env::set_var("NAVCAT_TEST_STATE_DIR", dir.path());
+        std::env::set_var("NAVCAT_TEST_REPLAY", "1");
+    }
+
+    let options = LogcatOptions {
+        buffers: vec!["main".to_string(), "crash".to_string()],
+        history: History::All,
+        filterspecs: vec![],
+        kernel: false,
+        server: None,
+        overflow: Overflow::Block,
+    };
+    let mut handle = spawn_multi_logcat(&[None], &options).unwrap();
+    let lines: Vec<String> =
+        std::iter::from_fn(|| recv_event(&handle, Duration::from_secs(3)).ok())
+            .filter_map(|event| match event {
+                LogcatEvent::Line(line) => Some(line),
+                _ => None,
+            })
+            .take(2)
+            .collect();
+    handle.shutdown();
+
+    let first_args = fs::read_to_string(dir.path().join("logcat_args_1")).unwrap();
+    let restart_args = fs::read_to_string(dir.path().join("logcat_args_2")).unwrap();
+    unsafe {
+        std::env::remove_var("NAVCAT_ADB");
+        std::env::remove_var("NAVCAT_TEST_STATE_DIR");
+        std::env::remove_var("NAVCAT_TEST_REPLAY");
+    }
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("FirstTag"));
+    assert!(lines[1].contains("SecondTag"));
+    assert_eq!(first_args.trim(), "logcat -v threadtime -b main -b crash");
+    assert_eq!(
+        restart_args.trim(),
+        "logcat -v threadtime -b main -b crash -T 01-15 10:30:45.123"
+    );
+}
+
+#[test]
+fn new_filterspecs_restart_logcat_without_a_disconnect() {
+    let _guard = env_lock().lock().unwrap();
+    let dir = TempDir::new().unwrap();
+    let adb = fake_adb_script(dir.path());
+
+    unsafe {
+        std::env::set_var("NAVCAT_ADB", &adb);
+        std::env::set_var("NAVCAT_TEST_STATE_DIR", dir.path());
+    }
+
+    let mut handle = spawn_multi_logcat(&[None], &LogcatOptions::default()).unwrap();
+    let second = std::iter::from_fn(|| recv_event(&handle, Duration::from_secs(3)).ok())
+        .find(|event| matches!(event, LogcatEvent::Line(line) if line.contains("SecondTag")));
+    assert!(second.is_some());
+
+    handle.set_filterspecs(vec!["Planner:D".to_string(), "*:F".to_string()]);
+    let args_file = dir.path().join("logcat_args_3");
+    for _ in 0..20 {
+        if args_file.exists() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    let restart_args = fs::read_to_string(&args_file).unwrap_or_default();
+    let event = recv_event(&handle, Duration::from_millis(500));
+    handle.shutdown();
+    unsafe {
+        std::env::remove_var("NAVCAT_ADB");
+        std::env::remove_var("NAVCAT_TEST_STATE_DIR");
+    }
+
+    assert!(
+        restart_args
+            .trim()
+            .ends_with("-T 01-15 10:30:46.123 Planner:D *:F"),
+        "{}",
+        restart_args
+    );
+    // The replayed SecondTag line is dropped and no disconnect is reported.
+    assert!(event.is_err());
+}
+
+#[test]
+fn adb_server_client_lists_devices_and_reports_failures() {
+    let (addr, _) = fake_adb_server();
+    let client = AdbClient::new(addr);
+
+    assert_eq!(client.version().unwrap(), 0x29);
+    let devices = client.devices().unwrap();
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].serial, "emulator-5554");
+    assert_eq!(devices[0].model.as_deref(), Some("Pixel 7"));
+
+    let error = client.shell(Some("R58M123ABC"), "logcat").unwrap_err();
+    assert!(
+        matches!(&error, AdbError::Failed(message) if message == "device 'R58M123ABC' not found")
+    );
+}
+
+#[test]
+fn logcat_through_adb_server_reconnects_from_last_timestamp() {
+    let (addr, commands) = fake_adb_server();
+    let options = LogcatOptions {
+        server: Some(AdbClient::new(addr)),
+        ..LogcatOptions::default()
+    };
+
+    let mut handle = spawn_multi_logcat(&[Some("emulator-5554".to_owned())], &options).unwrap();
+    let events: Vec<String> =
+        std::iter::from_fn(|| recv_event(&handle, Duration::from_secs(2)).ok())
+            .filter_map(|event| match event {
+                LogcatEvent::Line(line) => Some(line),
+                LogcatEvent::Connected => Some("connected".to_string()),
+                LogcatEvent::Disconnected => Some("disconnected".to_string()),
+                LogcatEvent::State(_) => None,
+            })
+            .take(4)
+            .collect();
+    handle.shutdown();
+
+    assert_eq!(events.len(), 4);
+    assert!(events[0].ends_with("FirstTag: first"));
+    assert_eq!(events[1..3], ["disconnected", "connected"]);
+    assert!(events[3].ends_with("SecondTag: second"));
+    let commands = commands.lock().unwrap();
+    assert_eq!(commands[0], "logcat -v threadtime -T 0");
+    assert_eq!(commands[1], "logcat -v threadtime -T '01-15 10:30:45.123'");
+}
+
+#[test]
+fn device_tracker_reports_unplug_and_authorization_and_survives_a_server_restart() {
+    let (addr, _) = fake_adb_server();
+    let options = LogcatOptions {
+        server: Some(AdbClient::new(addr)),
+        ..LogcatOptions::default()
+    };
+
+    let mut handle = spawn_multi_logcat(&[Some("emulator-5554".to_owned())], &options).unwrap();
+    let states: Vec<DeviceState> =
+        std::iter::from_fn(|| recv_event(&handle, Duration::from_secs(2)).ok())
+            .filter_map(|event| match event {
+                LogcatEvent::State(state) => Some(state),
+                _ => None,
+            })
+            .take(6)
+            .collect();
+    handle.shutdown();
+
+    assert_eq!(
+        states,
+        [
+            DeviceState::Online,
+            DeviceState::Unplugged,
+            DeviceState::Unauthorized,
+            DeviceState::Online,
+            DeviceState::Unknown,
+            DeviceState::Online
+        ]
+    );
+}
+
 #[test]
 fn multiple_ready_devices_require_serial_and_matching_serial_is_accepted() {
     let _guard = env_lock().lock().unwrap();
@@ -245,3 +644,120 @@ fn requested_serial_that_is_attached_but_unready_reports_unready() {
         std::env::remove_var("NAVCAT_TEST_DEVICES_LINE");
     }
 }
+
+#[test]
+fn device_actions_run_adb_shell_on_the_chosen_serial() {
+    let _guard = env_lock().lock().unwrap();
+    let dir = TempDir::new().unwrap();
+    let adb = fake_adb_script(dir.path());
+
+    unsafe {
+        std::env::set_var("NAVCAT_ADB", &adb);
+        std::env::set_var("NAVCAT_TEST_STATE_DIR", dir.path());
+    }
+
+    let run = |action: DeviceAction| {
+        run_device_action(
+            Some("emulator-5554"),
+            None,
+            Some("com.example.nav"),
+            &action,
+        )
+        .map_err(|e| e.to_string())
+    };
+    let set_level = |level: &str| DeviceAction::SetTagLevel {
+        tag: "Planner".to_string(),
+        level: level.to_string(),
+    };
+    assert_eq!(run(DeviceAction::ClearLog).unwrap(), "device log cleared");
+    assert_eq!(
+        run(set_level("VERBOSE")).unwrap(),
+        "log.tag.Planner set to VERBOSE"
+    );
+    assert_eq!(
+        run(set_level("LOUD")).unwrap_err(),
+        "setprop: failed to set property"
+    );
+    assert_eq!(
+        run(DeviceAction::ForceStop).unwrap(),
+        "com.example.nav force-stopped"
+    );
+    assert_eq!(
+        run(DeviceAction::StartApp).unwrap(),
+        "com.example.nav started"
+    );
+
+    unsafe {
+        std::env::remove_var("NAVCAT_ADB");
+        std::env::remove_var("NAVCAT_TEST_STATE_DIR");
+    }
+
+    let commands = fs::read_to_string(dir.path().join("shell_commands")).unwrap();
+    assert_eq!(
+        commands.lines().collect::<Vec<_>>(),
+        [
+            "shell logcat -c",
+            "shell setprop log.tag.Planner VERBOSE",
+            "shell setprop log.tag.Planner LOUD",
+            "shell am force-stop com.example.nav",
+            "shell monkey -p com.example.nav -c android.intent.category.LAUNCHER 1",
+        ]
+    );
+}
+
+#[test]
+fn typed_tag_reaches_the_device_shell_as_one_word_either_way() {
+    let set_level = DeviceAction::SetTagLevel {
+        tag: "My Tag;reboot".to_string(),
+        level: "VERBOSE".to_string(),
+    };
+    let quoted = "setprop 'log.tag.My Tag;reboot' VERBOSE";
+
+    let (addr, commands) = fake_adb_server();
+    let client = AdbClient::new(addr);
+    let _ = run_device_action(Some("emulator-5554"), Some(&client), None, &set_level);
+    assert_eq!(commands.lock().unwrap()[0], quoted);
+
+    let _guard = env_lock().lock().unwrap();
+    let dir = TempDir::new().unwrap();
+    let adb = fake_adb_script(dir.path());
+    unsafe {
+        std::env::set_var("NAVCAT_ADB", &adb);
+        std::env::set_var("NAVCAT_TEST_STATE_DIR", dir.path());
+    }
+    let result = run_device_action(Some("emulator-5554"), None, None, &set_level);
+    unsafe {
+        std::env::remove_var("NAVCAT_ADB");
+        std::env::remove_var("NAVCAT_TEST_STATE_DIR");
+    }
+    result.unwrap();
+    let recorded = fs::read_to_string(dir.path().join("shell_commands")).unwrap();
+    assert_eq!(recorded.trim_end(), format!("shell {}", quoted));
+}
+
+#[test]
+fn screenshot_through_adb_server_uses_exec_and_rejects_non_png_output() {
+    let (addr, commands) = fake_adb_server();
+    let client = AdbClient::new(addr);
+
+    let error = run_device_action(
+        Some("emulator-5554"),
+        Some(&client),
+        None,
+        &DeviceAction::Screenshot,
+    )
+    .unwrap_err();
+
+    assert!(error.to_string().starts_with("screencap failed"));
+    assert_eq!(commands.lock().unwrap()[0], "exec:screencap -p");
+}
+
+#[test]
+fn process_table_asks_ps_for_every_thread_with_its_process_name() {
+    let (addr, commands) = fake_adb_server();
+    let client = AdbClient::new(addr);
+
+    process_table(Some("emulator-5554"), Some(&client)).unwrap();
+
+    assert_eq!(commands.lock().unwrap()[0], "ps -A -T -o PID,TID,NAME,CMD");
+}